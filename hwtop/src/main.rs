@@ -25,8 +25,36 @@ fn main() -> Result<(), Error> {
 				&mut dbus_client,
 				"type='signal',path='/dev/arnavion/sensord/Daemon',interface='dev.arnavion.sensord.Daemon',member='Sensors'",
 			)?;
+
+		// sensord forgets its subscribers when it restarts, so hwtop subscribes again whenever sensord's name gets a new owner.
+		let () =
+			obj.add_match(
+				&mut dbus_client,
+				"type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',member='NameOwnerChanged',arg0='dev.arnavion.sensord.Daemon'",
+			)?;
 	}
 
+	// Method calls are sent on a second connection from the main thread, so that they're sent as soon as a key is pressed
+	// rather than when the receiving thread gets its next signal, and so that waiting for their replies can't consume signals.
+	//
+	// sensord only samples sensors at their configured intervals while a client is subscribed. Subscribing fails if sensord isn't running yet,
+	// or if it just started and hasn't registered its methods yet, so it's retried when sensord starts and whenever an event arrives
+	// while hwtop isn't subscribed. Failing to subscribe isn't fatal, since sensord still sends signals at a slow rate to clients
+	// that aren't subscribed.
+	let mut method_client = {
+		let connection =
+			dbus_pure::Connection::new(
+				dbus_pure::BusPath::System,
+				dbus_pure::SaslAuthType::Uid,
+			)?;
+		dbus_pure::Client::new(connection)?
+	};
+	let daemon = DevArnavionSensordDaemonObject {
+		name: "dev.arnavion.sensord.Daemon".into(),
+		path: dbus_pure::proto::ObjectPath("/dev/arnavion/sensord/Daemon".into()),
+	};
+	let mut subscribed = daemon.subscribe(&mut method_client).is_ok();
+
 	let (event_sender, event_receiver) = std::sync::mpsc::channel();
 
 	std::thread::spawn({
@@ -36,11 +64,18 @@ fn main() -> Result<(), Error> {
 			loop {
 				match dbus_client.recv() {
 					Ok((header, body)) => match header.r#type {
-						dbus_pure::proto::MessageType::Signal { interface, member, path: _ } if interface == "dev.arnavion.sensord.Daemon" && member == "Sensors" => {
-							if event_sender.send(Ok(Event::Sensors(body))).is_err() {
+						dbus_pure::proto::MessageType::Signal { interface, member, path: _ } if interface == "org.freedesktop.DBus" && member == "NameOwnerChanged" =>
+							if let Some(dbus_pure::proto::Variant::Tuple { elements }) = body &&
+								let [_, _, dbus_pure::proto::Variant::String(new_owner)] = &*elements &&
+								!new_owner.is_empty() &&
+								event_sender.send(Ok(Event::DaemonStarted)).is_err()
+							{
 								break;
-							}
-						},
+							},
+
+						dbus_pure::proto::MessageType::Signal { interface, member, path: _ }
+							if interface == "dev.arnavion.sensord.Daemon" && member == "Sensors" && event_sender.send(Ok(Event::Sensors(body))).is_err() => break,
+
 						_ => (),
 					},
 
@@ -82,32 +117,80 @@ fn main() -> Result<(), Error> {
 	let mut message = loop {
 		match event_receiver.recv()?? {
 			Event::Sensors(new_message) => {
+				if !subscribed {
+					subscribed = daemon.subscribe(&mut method_client).is_ok();
+				}
+
 				let new_message = new_message.ok_or("signal has no body")?;
 				let new_message: sensord_common::SensorsMessage<'static> = serde::Deserialize::deserialize(new_message)?;
 				break new_message;
 			},
 
+			Event::DaemonStarted => subscribed = daemon.subscribe(&mut method_client).is_ok(),
+
 			Event::Stdin(_) => (),
 		}
 	};
 
+	// The OOM kill counter is cumulative since boot, so it's compared against its value when hwtop started.
+	let initial_oom_kills = message.vmstat.oom_kills;
+
 	let mut show_sensor_names = false;
+	let mut show_interrupts = false;
+	let mut show_trip_points = false;
+	let mut sort_processes_by_memory = false;
+
+	// The interval that sensord granted to hwtop's request, if any. The `+` and `-` keys step from this rather than from the interval
+	// of the messages, which is the shortest interval that any client requested. It's requested again if sensord restarts.
+	let mut requested_interval = None;
+	// Why the interval couldn't be changed, shown next to it until it's changed successfully.
+	let mut interval_status = None;
 
 	loop {
-		show_sensor_names = match event_receiver.recv()?? {
+		match event_receiver.recv()?? {
 			Event::Sensors(new_message) => {
 				let new_message = new_message.ok_or("signal has no body")?;
 				let new_message: sensord_common::SensorsMessage<'static> = serde::Deserialize::deserialize(new_message)?;
 				message = new_message;
-				show_sensor_names
 			},
 
-			Event::Stdin(b's') => !show_sensor_names,
+			Event::Stdin(b's') => show_sensor_names = !show_sensor_names,
+
+			Event::Stdin(b'i') => show_interrupts = !show_interrupts,
+
+			Event::Stdin(b't') => show_trip_points = !show_trip_points,
+
+			Event::Stdin(b'm') => sort_processes_by_memory = !sort_processes_by_memory,
+
+			// sensord forgot hwtop's subscription and interval, so they're sent again below.
+			Event::DaemonStarted => subscribed = false,
+
+			// `=` is `+` without shift on most keyboards.
+			Event::Stdin(b'+' | b'=') => {
+				let current_interval = requested_interval.unwrap_or(message.interval);
+				if let Some(&interval_ms) = INTERVALS_MS.iter().rev().find(|&&interval_ms| interval_ms < current_interval) {
+					set_interval(&daemon, &mut method_client, interval_ms, &mut requested_interval, &mut interval_status);
+				}
+			},
+
+			Event::Stdin(b'-') => {
+				let current_interval = requested_interval.unwrap_or(message.interval);
+				if let Some(&interval_ms) = INTERVALS_MS.iter().find(|&&interval_ms| interval_ms > current_interval) {
+					set_interval(&daemon, &mut method_client, interval_ms, &mut requested_interval, &mut interval_status);
+				}
+			},
 
 			Event::Stdin(b'q' | b'\x1B') => break,
 
-			Event::Stdin(_) => show_sensor_names,
-		};
+			Event::Stdin(_) => (),
+		}
+
+		if !subscribed && daemon.subscribe(&mut method_client).is_ok() {
+			subscribed = true;
+			if let Some(interval_ms) = requested_interval {
+				set_interval(&daemon, &mut method_client, interval_ms, &mut requested_interval, &mut interval_status);
+			}
+		}
 
 		let max_sensor_group_name_width = message.sensors.iter().map(|sensor_group| sensor_group.name.len()).max().unwrap_or_default();
 		let max_num_temp_sensors = message.sensors.iter().map(|sensor_group| sensor_group.temps.len()).max().unwrap_or_default();
@@ -143,6 +226,15 @@ fn main() -> Result<(), Error> {
 
 		print_cpu(&mut output, None, message.cpu_average_usage)?;
 
+		// The average above is for the whole host. If sensord is running in a container, the container's usage is shown separately.
+		if message.container.enabled {
+			output.write_all(b"\r\n")?;
+			print_container(&mut output, &message.container)?;
+		}
+
+		output.write_all(b"\r\n")?;
+		print_vmstat(&mut output, &message.vmstat, initial_oom_kills)?;
+
 		if !message.sensors.is_empty() {
 			output.write_all(b"\r\n")?;
 
@@ -181,6 +273,28 @@ fn main() -> Result<(), Error> {
 						print_bat_sensor(&mut output, sensor, show_sensor_names)?;
 					}
 				}
+
+				if !sensor_group.cooling.is_empty() {
+					for _ in 0..(max_num_temp_sensors - sensor_group.temps.len()) {
+						output.write_all(b"         ")?;
+					}
+
+					for sensor in &sensor_group.cooling {
+						output.write_all(b"  ")?;
+						print_cooling_sensor(&mut output, sensor, show_sensor_names)?;
+					}
+				}
+
+				if !sensor_group.values.is_empty() {
+					for _ in 0..(max_num_temp_sensors - sensor_group.temps.len()) {
+						output.write_all(b"         ")?;
+					}
+
+					for sensor in &sensor_group.values {
+						output.write_all(b"  ")?;
+						print_value_sensor(&mut output, sensor, show_sensor_names)?;
+					}
+				}
 			}
 		}
 
@@ -193,7 +307,52 @@ fn main() -> Result<(), Error> {
 			}
 		}
 
-		output.write_all(b"  [s]ensor names  [q]uit")?;
+		if !message.cgroups.is_empty() {
+			let max_cgroup_name_width = message.cgroups.iter().map(|cgroup| cgroup.name.len()).max().unwrap_or_default();
+
+			output.write_all(b"\r\n")?;
+
+			for cgroup in &*message.cgroups {
+				output.write_all(b"\r\n")?;
+				print_cgroup(&mut output, cgroup, max_cgroup_name_width)?;
+			}
+		}
+
+		if !message.processes.is_empty() {
+			if sort_processes_by_memory {
+				message.processes.to_mut().sort_by_key(|process| std::cmp::Reverse(process.rss));
+			}
+
+			output.write_all(b"\r\n")?;
+			print_processes(&mut output, &message.processes)?;
+		}
+
+		if show_interrupts {
+			output.write_all(b"\r\n")?;
+			print_interrupts(&mut output, &message.interrupts)?;
+		}
+
+		let has_trip_points = message.sensors.iter().flat_map(|sensor_group| &sensor_group.temps).any(|sensor| !sensor.trip_points.is_empty());
+
+		if show_trip_points && has_trip_points {
+			output.write_all(b"\r\n")?;
+			print_trip_points(&mut output, &message.sensors)?;
+		}
+
+		print_errors(&mut output, &message)?;
+
+		output.write_all(b"  [s]ensor names  [i]nterrupts")?;
+		if has_trip_points {
+			output.write_all(b"  [t]rip points")?;
+		}
+		if !message.processes.is_empty() {
+			output.write_all(b"  [m]emory sort")?;
+		}
+		write!(output, "  [+/-] refresh {} ms", message.interval)?;
+		if let Some(interval_status) = &interval_status {
+			write!(output, " \x1B[1;31m({interval_status})\x1B[0m")?;
+		}
+		output.write_all(b"  [q]uit")?;
 
 		let (_, end_sync) = terminfo.sync()?;
 		output.write_all(end_sync)?;
@@ -205,25 +364,38 @@ fn main() -> Result<(), Error> {
 	Ok(())
 }
 
+// The intervals that the `+` and `-` keys step through. sensord clamps them to its configured `min_interval` and `max_interval`.
+const INTERVALS_MS: &[u32] = &[100, 250, 500, 1000, 2000, 5000, 10000];
+
 #[derive(Debug)]
 enum Event {
+	// sensord's name got a new owner, ie sensord (re)started
+	DaemonStarted,
 	Sensors(Option<dbus_pure::proto::Variant<'static>>),
 	Stdin(u8),
 }
 
-fn print_cpu<W>(mut writer: W, id_and_frequency: Option<(usize, f64)>, usage: f64) -> Result<(), Error> where W: Write {
-	let color = match usage {
-		usage if usage < 5. => b"0;34",
-		usage if usage < 10. => b"1;34",
-		usage if usage < 25. => b"1;32",
-		usage if usage < 50. => b"1;33",
-		usage if usage < 75. => b"0;33",
-		usage if usage < 90. => b"1;31",
-		_ => b"0;31",
-	};
+// Requests an interval from sensord. An error, eg because sensord is restarting or is too old to have `SetInterval`,
+// is shown rather than ending hwtop, and the previously requested interval is kept.
+fn set_interval(
+	daemon: &DevArnavionSensordDaemonObject,
+	dbus_client: &mut dbus_pure::Client,
+	interval_ms: u32,
+	requested_interval: &mut Option<u32>,
+	interval_status: &mut Option<String>,
+) {
+	match daemon.set_interval(dbus_client, interval_ms) {
+		Ok(interval_ms) => {
+			*requested_interval = Some(interval_ms);
+			*interval_status = None;
+		},
+		Err(err) => *interval_status = Some(format!("could not change refresh interval: {err}")),
+	}
+}
 
+fn print_cpu<W>(mut writer: W, id_and_frequency: Option<(usize, f64)>, usage: f64) -> Result<(), Error> where W: Write {
 	writer.write_all(b"\x1B[")?;
-	writer.write_all(color)?;
+	writer.write_all(cpu_usage_color(usage))?;
 	writer.write_all(b"m")?;
 
 	if let Some((id, _)) = id_and_frequency {
@@ -253,10 +425,83 @@ fn print_cpu<W>(mut writer: W, id_and_frequency: Option<(usize, f64)>, usage: f6
 	Ok(())
 }
 
+fn print_container<W>(mut writer: W, container: &sensord_common::Container) -> Result<(), Error> where W: Write {
+	writer.write_all(b"\x1B[")?;
+	writer.write_all(cpu_usage_color(container.cpu_usage))?;
+	writer.write_all(b"m")?;
+
+	writer.write_all(b"Ctr: ")?;
+	write!(writer, "{:5.1}", container.cpu_usage)?;
+	writer.write_all(b"% of ")?;
+	write!(writer, "{:.1}", container.cpu_quota)?;
+	writer.write_all(b" CPUs\x1B[0m   ")?;
+
+	#[allow(clippy::cast_precision_loss)]
+	print_bytes(&mut writer, container.memory as f64)?;
+	if container.memory_max != u64::MAX {
+		writer.write_all(b" / ")?;
+		#[allow(clippy::cast_precision_loss)]
+		print_bytes(&mut writer, container.memory_max as f64)?;
+	}
+
+	Ok(())
+}
+
+fn cpu_usage_color(usage: f64) -> &'static [u8] {
+	match usage {
+		usage if usage < 5. => b"0;34",
+		usage if usage < 10. => b"1;34",
+		usage if usage < 25. => b"1;32",
+		usage if usage < 50. => b"1;33",
+		usage if usage < 75. => b"0;33",
+		usage if usage < 90. => b"1;31",
+		_ => b"0;31",
+	}
+}
+
+fn print_vmstat<W>(mut writer: W, vmstat: &sensord_common::VmStat<'_>, initial_oom_kills: u64) -> Result<(), Error> where W: Write {
+	fn color(rate: f64, warning: f64, critical: f64) -> &'static [u8] {
+		match rate {
+			rate if rate < 0.5 => b"0;34",
+			rate if rate < warning => b"1;32",
+			rate if rate < critical => b"1;33",
+			_ => b"1;31",
+		}
+	}
+
+	// 256 pages/s is 1 MiB/s with 4 KiB pages.
+	for (label, rate, warning, critical) in [
+		(&b"Swap in: "[..], vmstat.swap_ins, 1., 256.),
+		(b"   Swap out: ", vmstat.swap_outs, 1., 256.),
+		(b"   Major faults: ", vmstat.major_page_faults, 10., 100.),
+	] {
+		writer.write_all(label)?;
+		writer.write_all(b"\x1B[")?;
+		writer.write_all(color(rate, warning, critical))?;
+		writer.write_all(b"m")?;
+		print_rate(&mut writer, rate)?;
+		writer.write_all(b"/s\x1B[0m")?;
+	}
+
+	// Stays highlighted once an OOM kill has happened, so that it isn't missed.
+	writer.write_all(b"   OOM kills: ")?;
+	if vmstat.oom_kills > initial_oom_kills {
+		write!(writer, "\x1B[1;37;41m{}\x1B[0m", vmstat.oom_kills - initial_oom_kills)?;
+	}
+	else {
+		writer.write_all(b"\x1B[0;34m0\x1B[0m")?;
+	}
+
+	Ok(())
+}
+
 fn print_temp_sensor<W>(mut writer: W, sensor: &sensord_common::TempSensor<'_>, show_sensor_names: bool) -> Result<(), Error> where W: Write {
 	let temp = sensor.value;
 
 	let color = match temp {
+		// The sensor failed to be read.
+		_ if !sensor.error.is_empty() => &b"1;37;41"[..],
+		_ if !sensor.available => &b"2"[..],
 		0. => &b"0"[..],
 		temp if temp < 30. => &b"0;34"[..],
 		temp if temp < 35. => &b"1;34"[..],
@@ -281,6 +526,11 @@ fn print_temp_sensor<W>(mut writer: W, sensor: &sensord_common::TempSensor<'_>,
 				write!(writer, "{name:^7}")?;
 			},
 
+		// The sensor's device was not found, or the sensor failed to be read.
+		(_, _) if !sensor.available => {
+			writer.write_all(b"  ---  ")?;
+		},
+
 		(_, temp) if temp > 0. => {
 			write!(writer, "{temp:5.1}")?;
 			writer.write_all(b"\xC2\xB0C")?;
@@ -306,6 +556,9 @@ fn print_fan_sensor<W>(mut writer: W, sensor: &sensord_common::FanSensor<'_>, sh
 			else {
 				write!(writer, "{name:^15}")?;
 			},
+		_ if !sensor.available => {
+			writer.write_all(b"\x1B[2m      ---      \x1B[0m")?;
+		},
 		_ => {
 			let pwm = 100. * f64::from(sensor.pwm) / 255.;
 			write!(writer, "{pwm:3.0}")?;
@@ -329,6 +582,10 @@ fn print_bat_sensor<W>(mut writer: W, sensor: &sensord_common::BatSensor<'_>, sh
 				write!(writer, "{name:^15}")?;
 			},
 
+		(_, _, _) if !sensor.available => {
+			writer.write_all(b"\x1B[2m  ---  \x1B[0m")?;
+		},
+
 		(_, capacity, charging) if capacity > 0 => {
 			writer.write_all(if charging { b"+" } else { b"-" })?;
 			write!(writer, "{capacity:4}")?;
@@ -343,6 +600,53 @@ fn print_bat_sensor<W>(mut writer: W, sensor: &sensord_common::BatSensor<'_>, sh
 	Ok(())
 }
 
+fn print_cooling_sensor<W>(mut writer: W, sensor: &sensord_common::CoolingSensor<'_>, show_sensor_names: bool) -> Result<(), Error> where W: Write {
+	match &sensor.name {
+		name if show_sensor_names =>
+			if name.len() > 15 {
+				writer.write_all(name[..14].as_bytes())?;
+				writer.write_all(b"\xE2\x80\xA6")?;
+			}
+			else {
+				write!(writer, "{name:^15}")?;
+			},
+		_ if !sensor.available => {
+			writer.write_all(b"\x1B[2m      ---      \x1B[0m")?;
+		},
+		_ => {
+			let engaged = if sensor.max_state == 0 { 0. } else { 100. * f64::from(sensor.cur_state) / f64::from(sensor.max_state) };
+			write!(writer, "{engaged:3.0}")?;
+			writer.write_all(b"% (")?;
+			write!(writer, "{:>3}/{:<4}", sensor.cur_state, sensor.max_state)?;
+			writer.write_all(b")")?;
+		},
+	}
+
+	Ok(())
+}
+
+fn print_value_sensor<W>(mut writer: W, sensor: &sensord_common::ValueSensor<'_>, show_sensor_names: bool) -> Result<(), Error> where W: Write {
+	match &sensor.name {
+		name if show_sensor_names =>
+			if name.len() > 15 {
+				writer.write_all(name[..14].as_bytes())?;
+				writer.write_all(b"\xE2\x80\xA6")?;
+			}
+			else {
+				write!(writer, "{name:^15}")?;
+			},
+		_ if !sensor.available => {
+			writer.write_all(b"\x1B[2m      ---      \x1B[0m")?;
+		},
+		_ => {
+			let value = format!("{:.1}{}", sensor.value, sensor.unit);
+			write!(writer, "{value:^15}")?;
+		},
+	}
+
+	Ok(())
+}
+
 fn print_network<W>(mut writer: W, network: &sensord_common::Network<'_>, max_network_name_width: usize, show_sensor_names: bool) -> Result<(), Error> where W: Write {
 	write!(writer, "{:>max_network_name_width$}", network.name)?;
 	writer.write_all(b": ")?;
@@ -401,6 +705,240 @@ fn print_network<W>(mut writer: W, network: &sensord_common::Network<'_>, max_ne
 	Ok(())
 }
 
+fn print_cgroup<W>(mut writer: W, cgroup: &sensord_common::Cgroup<'_>, max_cgroup_name_width: usize) -> Result<(), Error> where W: Write {
+	write!(writer, "{:>max_cgroup_name_width$}", cgroup.name)?;
+	writer.write_all(b": ")?;
+
+	if !cgroup.exists {
+		writer.write_all(b"\x1B[0;34m  N/A\x1B[0m")?;
+		return Ok(());
+	}
+
+	let color = match cgroup.cpu {
+		cpu if cpu < 5. => b"0;34",
+		cpu if cpu < 25. => b"1;32",
+		cpu if cpu < 50. => b"1;33",
+		cpu if cpu < 90. => b"0;33",
+		_ => b"1;31",
+	};
+	writer.write_all(b"\x1B[")?;
+	writer.write_all(color)?;
+	writer.write_all(b"m")?;
+	write!(writer, "{:5.1}", cgroup.cpu)?;
+	writer.write_all(b"%\x1B[0m   ")?;
+
+	#[allow(clippy::cast_precision_loss)]
+	print_bytes(&mut writer, cgroup.memory as f64)?;
+	if cgroup.memory_max == u64::MAX {
+		writer.write_all(b"              ")?;
+	}
+	else {
+		writer.write_all(b" / ")?;
+		#[allow(clippy::cast_precision_loss)]
+		print_bytes(&mut writer, cgroup.memory_max as f64)?;
+	}
+
+	writer.write_all(b"   ")?;
+	print_bytes(&mut writer, cgroup.read)?;
+	writer.write_all(b"/s read   ")?;
+	print_bytes(&mut writer, cgroup.write)?;
+	writer.write_all(b"/s write   ")?;
+	write!(writer, "{:5} tasks", cgroup.tasks)?;
+
+	Ok(())
+}
+
+fn print_processes<W>(mut writer: W, processes: &[sensord_common::Process<'_>]) -> Result<(), Error> where W: Write {
+	let max_user_width = processes.iter().map(|process| process.user.len()).max().unwrap_or_default().max("USER".len());
+
+	write!(writer, "\r\n    PID  {:max_user_width$}   CPU%       RSS  COMMAND", "USER")?;
+
+	for process in processes {
+		writer.write_all(b"\r\n")?;
+
+		let color = match process.cpu {
+			cpu if cpu < 5. => b"0;34",
+			cpu if cpu < 25. => b"1;32",
+			cpu if cpu < 50. => b"1;33",
+			cpu if cpu < 90. => b"0;33",
+			_ => b"1;31",
+		};
+
+		write!(writer, "{:7}  {:max_user_width$}  ", process.pid, process.user)?;
+		writer.write_all(b"\x1B[")?;
+		writer.write_all(color)?;
+		writer.write_all(b"m")?;
+		write!(writer, "{:5.1}", process.cpu)?;
+		writer.write_all(b"\x1B[0m  ")?;
+		#[allow(clippy::cast_precision_loss)]
+		print_bytes(&mut writer, process.rss as f64)?;
+		write!(writer, "  {}", process.name)?;
+	}
+
+	Ok(())
+}
+
+fn print_bytes<W>(mut writer: W, bytes: f64) -> Result<(), Error> where W: Write {
+	if bytes < 1024. * 999.95 {
+		write!(writer, "{:5.1} KiB", bytes / 1024.)?;
+	}
+	else if bytes < 1024. * 1024. * 999.95 {
+		write!(writer, "{:5.1} MiB", bytes / 1024. / 1024.)?;
+	}
+	else {
+		write!(writer, "{:5.1} GiB", bytes / 1024. / 1024. / 1024.)?;
+	}
+
+	Ok(())
+}
+
+fn print_interrupts<W>(mut writer: W, interrupts: &sensord_common::Interrupts<'_>) -> Result<(), Error> where W: Write {
+	writer.write_all(b"\r\n    IRQ: ")?;
+	print_rate(&mut writer, interrupts.interrupts)?;
+	writer.write_all(b"/s   SoftIRQ: ")?;
+	print_rate(&mut writer, interrupts.softirqs)?;
+	writer.write_all(b"/s   Ctx switches: ")?;
+	print_rate(&mut writer, interrupts.context_switches)?;
+	writer.write_all(b"/s")?;
+
+	let max_source_name_width =
+		interrupts.top_interrupts.iter().chain(&*interrupts.top_softirqs)
+		.map(|source| source.name.len())
+		.max()
+		.unwrap_or_default()
+		.max("SoftIRQ".len());
+
+	for source in interrupts.top_interrupts.iter().chain(&*interrupts.top_softirqs) {
+		writer.write_all(b"\r\n")?;
+
+		write!(writer, "{:>max_source_name_width$}", source.name)?;
+		writer.write_all(b": ")?;
+		print_rate(&mut writer, source.rate)?;
+		writer.write_all(b"/s  ")?;
+
+		// Only the CPUs that serviced the source are shown, since that is what matters for diagnosing IRQ affinity.
+		for (id, &rate) in source.cpus.iter().enumerate() {
+			if rate >= 0.5 {
+				write!(writer, " \x1B[1;34m{id:3}\x1B[0m:")?;
+				print_rate(&mut writer, rate)?;
+			}
+		}
+
+		if !source.description.is_empty() {
+			write!(writer, "   {}", source.description)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn print_trip_points<W>(mut writer: W, sensor_groups: &[sensord_common::SensorGroup<'_>]) -> Result<(), Error> where W: Write {
+	let sensors =
+		sensor_groups.iter()
+		.flat_map(|sensor_group| sensor_group.temps.iter().map(move |sensor| (&sensor_group.name, sensor)))
+		.filter(|(_, sensor)| !sensor.trip_points.is_empty());
+
+	let max_name_width =
+		sensors.clone()
+		.map(|(sensor_group_name, sensor)| sensor_group_name.len() + 1 + sensor.name.len())
+		.max()
+		.unwrap_or_default();
+
+	for (sensor_group_name, sensor) in sensors {
+		writer.write_all(b"\r\n")?;
+
+		let sensor_group_name_width = max_name_width - 1 - sensor.name.len();
+		write!(writer, "{sensor_group_name:>sensor_group_name_width$} {}", sensor.name)?;
+		writer.write_all(b": ")?;
+		print_temp_sensor(&mut writer, sensor, false)?;
+
+		// The next trip point is the lowest one that the zone hasn't reached yet. It's colored according to how close the zone is to it.
+		let next_trip_point =
+			sensor.trip_points.iter()
+			.filter(|trip_point| trip_point.temp > sensor.value)
+			.min_by(|a, b| a.temp.total_cmp(&b.temp));
+
+		for trip_point in &sensor.trip_points {
+			let distance = trip_point.temp - sensor.value;
+			let is_next = next_trip_point.is_some_and(|next_trip_point| std::ptr::eq(trip_point, next_trip_point));
+
+			let color = match distance {
+				distance if distance <= 0. => &b"0;31"[..],
+				_ if !is_next => &b"0"[..],
+				distance if distance < 5. => &b"1;31"[..],
+				distance if distance < 15. => &b"1;33"[..],
+				_ => &b"1;32"[..],
+			};
+
+			write!(writer, "   {}: ", trip_point.kind)?;
+			writer.write_all(b"\x1B[")?;
+			writer.write_all(color)?;
+			writer.write_all(b"m")?;
+			write!(writer, "{:5.1}", trip_point.temp)?;
+			writer.write_all(b"\xC2\xB0C")?;
+			write!(writer, " ({distance:+5.1})")?;
+			writer.write_all(b"\x1B[0m")?;
+		}
+
+		if !sensor.policy.is_empty() || !sensor.mode.is_empty() {
+			write!(writer, "   [{}, {}]", sensor.policy, sensor.mode)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn print_errors<W>(mut writer: W, message: &sensord_common::SensorsMessage<'_>) -> Result<(), Error> where W: Write {
+	let sensor_errors =
+		message.sensors.iter()
+		.flat_map(|sensor_group| {
+			let temps = sensor_group.temps.iter().map(|sensor| (&sensor.name, &sensor.error));
+			let fans = sensor_group.fans.iter().map(|sensor| (&sensor.name, &sensor.error));
+			let bats = sensor_group.bats.iter().map(|sensor| (&sensor.name, &sensor.error));
+			let cooling = sensor_group.cooling.iter().map(|sensor| (&sensor.name, &sensor.error));
+			let values = sensor_group.values.iter().map(|sensor| (&sensor.name, &sensor.error));
+			temps.chain(fans).chain(bats).chain(cooling).chain(values).map(move |(name, error)| (Some(&sensor_group.name), name, error))
+		});
+	let network_errors = message.networks.iter().map(|network| (None, &network.name, &network.error));
+	let cgroup_errors = message.cgroups.iter().map(|cgroup| (None, &cgroup.name, &cgroup.error));
+
+	let mut errors = sensor_errors.chain(network_errors).chain(cgroup_errors).filter(|(_, _, error)| !error.is_empty()).peekable();
+
+	if message.errors.is_empty() && errors.peek().is_none() {
+		return Ok(());
+	}
+
+	writer.write_all(b"\r\n")?;
+
+	for error in &message.errors {
+		write!(writer, "\r\n\x1B[1;31m{error}\x1B[0m")?;
+	}
+
+	for (sensor_group_name, name, error) in errors {
+		writer.write_all(b"\r\n\x1B[1;31m")?;
+		if let Some(sensor_group_name) = sensor_group_name {
+			write!(writer, "{sensor_group_name} ")?;
+		}
+		write!(writer, "{name}: {error}\x1B[0m")?;
+	}
+
+	Ok(())
+}
+
+fn print_rate<W>(mut writer: W, rate: f64) -> Result<(), Error> where W: Write {
+	if rate < 999.5 {
+		write!(writer, "{rate:5.0} ")?;
+	}
+	else if rate < 999_950. {
+		write!(writer, "{:5.1}K", rate / 1_000.)?;
+	}
+	else {
+		write!(writer, "{:5.1}M", rate / 1_000_000.)?;
+	}
+
+	Ok(())
+}
+
 struct Error {
 	inner: Box<dyn std::error::Error + Send + Sync>,
 }
@@ -441,3 +979,15 @@ trait OrgFreeDesktopDbusInterface {
 
 #[dbus_pure_macros::object(OrgFreeDesktopDbusInterface)]
 struct OrgFreeDesktopDbusObject;
+
+#[dbus_pure_macros::interface("dev.arnavion.sensord.Daemon")]
+trait DevArnavionSensordDaemonInterface {
+	#[name = "Subscribe"]
+	fn subscribe();
+
+	#[name = "SetInterval"]
+	fn set_interval(interval_ms: u32) -> u32;
+}
+
+#[dbus_pure_macros::object(DevArnavionSensordDaemonInterface)]
+struct DevArnavionSensordDaemonObject;
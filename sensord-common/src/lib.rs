@@ -3,11 +3,31 @@
 
 #[derive(Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
 pub struct SensorsMessage<'a> {
+	// When the message was published, in milliseconds since the Unix epoch.
+	//
+	// Every section is sampled at its own interval, and the message is published at the shortest of them with the most recent values
+	// of every section. So each section has the timestamp of when it was last sampled, like `cpus_timestamp`,
+	// which is 0 if the section hasn't been sampled yet.
+	pub timestamp: u64,
+	// The interval of the sections that don't have their own, in milliseconds. This is the configured interval,
+	// or the shortest one that a client requested with the `SetInterval` method.
+	pub interval: u32,
 	pub num_cpus: u32,
+	// Also applies to `cpu_average_usage` and `container`
+	pub cpus_timestamp: u64,
 	pub cpus: std::borrow::Cow<'a, [Cpu]>,
 	pub cpu_average_usage: f64,
+	pub container: Container,
 	pub sensors: std::borrow::Cow<'a, [SensorGroup<'a>]>,
 	pub networks: std::borrow::Cow<'a, [Network<'a>]>,
+	pub interrupts: Interrupts<'a>,
+	pub vmstat: VmStat<'a>,
+	pub processes: std::borrow::Cow<'a, [Process<'a>]>,
+	pub processes_timestamp: u64,
+	pub cgroups: std::borrow::Cow<'a, [Cgroup<'a>]>,
+	// Errors from reading sections that are not specific to one sensor, like `/proc/stat` or `/proc/vmstat`.
+	// The values of a section that failed to be read are zero.
+	pub errors: Vec<std::borrow::Cow<'a, str>>,
 }
 
 #[derive(Clone, Copy, Debug, Default, dbus_pure_macros::ToVariant, serde::Deserialize)]
@@ -16,23 +36,60 @@ pub struct Cpu {
 	pub frequency: f64,
 }
 
+#[derive(Clone, Copy, Debug, Default, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct Container {
+	// `false` if sensord is not configured to report the usage of its container, in which case the other fields are not meaningful.
+	pub enabled: bool,
+	// Percentage of `cpu_quota`
+	pub cpu_usage: f64,
+	// Number of CPUs the container is allowed to use. This is the number of CPUs in the system if the container has no CPU limit.
+	pub cpu_quota: f64,
+	pub memory: u64,
+	// `u64::MAX` if the container has no memory limit.
+	pub memory_max: u64,
+}
+
 #[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
 pub struct SensorGroup<'a> {
 	pub name: std::borrow::Cow<'a, str>,
+	// When this was last sampled. See `SensorsMessage::timestamp`
+	pub timestamp: u64,
 	pub temps: Vec<TempSensor<'a>>,
 	pub fans: Vec<FanSensor<'a>>,
 	pub bats: Vec<BatSensor<'a>>,
+	pub cooling: Vec<CoolingSensor<'a>>,
+	// Generic values that sensord doesn't know the meaning of, eg read from arbitrary files.
+	pub values: Vec<ValueSensor<'a>>,
 }
 
 #[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
 pub struct TempSensor<'a> {
 	pub name: std::borrow::Cow<'a, str>,
+	// `false` if the sensor was not found, eg because its device is marked as optional in the config and does not exist,
+	// or if its most recent read failed.
+	pub available: bool,
+	// The error from reading the sensor, or empty if the sensor was read successfully.
+	// If the sensor failed to be read, its value is the last successfully read value.
+	pub error: std::borrow::Cow<'a, str>,
 	pub value: f64,
+	// Only thermal zones have trip points, a policy and a mode. The policy and mode are empty for hwmon sensors.
+	pub trip_points: Vec<TripPoint<'a>>,
+	pub policy: std::borrow::Cow<'a, str>,
+	pub mode: std::borrow::Cow<'a, str>,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct TripPoint<'a> {
+	// eg `passive`, `active`, `hot`, `critical`
+	pub kind: std::borrow::Cow<'a, str>,
+	pub temp: f64,
 }
 
 #[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
 pub struct FanSensor<'a> {
 	pub name: std::borrow::Cow<'a, str>,
+	pub available: bool,
+	pub error: std::borrow::Cow<'a, str>,
 	pub fan: u16,
 	pub pwm: u8,
 }
@@ -40,14 +97,102 @@ pub struct FanSensor<'a> {
 #[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
 pub struct BatSensor<'a> {
 	pub name: std::borrow::Cow<'a, str>,
+	pub available: bool,
+	pub error: std::borrow::Cow<'a, str>,
 	pub capacity: u8,
 	pub charging: bool,
 }
 
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct CoolingSensor<'a> {
+	pub name: std::borrow::Cow<'a, str>,
+	pub available: bool,
+	pub error: std::borrow::Cow<'a, str>,
+	pub cur_state: u32,
+	pub max_state: u32,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct ValueSensor<'a> {
+	pub name: std::borrow::Cow<'a, str>,
+	pub available: bool,
+	pub error: std::borrow::Cow<'a, str>,
+	pub value: f64,
+	// eg `%` or `MiB/s`. Empty if the value has no unit.
+	pub unit: std::borrow::Cow<'a, str>,
+}
+
 #[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
 pub struct Network<'a> {
 	pub name: std::borrow::Cow<'a, str>,
+	// When this was last sampled. See `SensorsMessage::timestamp`
+	pub timestamp: u64,
+	pub error: std::borrow::Cow<'a, str>,
 	pub rx: f64,
 	pub tx: f64,
 	pub addresses: Vec<std::borrow::Cow<'a, str>>,
 }
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct Interrupts<'a> {
+	// When this was last sampled. See `SensorsMessage::timestamp`
+	pub timestamp: u64,
+	pub interrupts: f64,
+	pub softirqs: f64,
+	pub context_switches: f64,
+	pub top_interrupts: std::borrow::Cow<'a, [InterruptSource<'a>]>,
+	pub top_softirqs: std::borrow::Cow<'a, [InterruptSource<'a>]>,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct InterruptSource<'a> {
+	pub name: std::borrow::Cow<'a, str>,
+	pub description: std::borrow::Cow<'a, str>,
+	pub rate: f64,
+	pub cpus: Vec<f64>,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct VmStat<'a> {
+	// When this was last sampled. See `SensorsMessage::timestamp`
+	pub timestamp: u64,
+	pub swap_ins: f64,
+	pub swap_outs: f64,
+	pub page_faults: f64,
+	pub major_page_faults: f64,
+	pub pages_scanned: f64,
+	pub pages_stolen: f64,
+	pub oom_kills: u64,
+	pub thp: std::borrow::Cow<'a, [VmStatCounter<'a>]>,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct VmStatCounter<'a> {
+	pub name: std::borrow::Cow<'a, str>,
+	pub rate: f64,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct Process<'a> {
+	pub pid: u32,
+	pub name: std::borrow::Cow<'a, str>,
+	pub user: std::borrow::Cow<'a, str>,
+	pub cpu: f64,
+	pub rss: u64,
+}
+
+#[derive(Clone, Debug, dbus_pure_macros::ToVariant, serde::Deserialize)]
+pub struct Cgroup<'a> {
+	pub name: std::borrow::Cow<'a, str>,
+	// When this was last sampled. See `SensorsMessage::timestamp`
+	pub timestamp: u64,
+	pub error: std::borrow::Cow<'a, str>,
+	pub exists: bool,
+	pub cpu: f64,
+	pub memory: u64,
+	// `u64::MAX` if the cgroup has no memory limit.
+	pub memory_max: u64,
+	pub read: f64,
+	pub write: f64,
+	pub tasks: u64,
+}
@@ -0,0 +1,215 @@
+#[derive(Clone)]
+pub(crate) struct Cgroup {
+	pub(crate) now: std::time::Instant,
+	pub(crate) exists: bool,
+	pub(crate) usage_usec: u64,
+	// Number of CPUs the cgroup is allowed to use, or `None` if the cgroup has no CPU limit.
+	pub(crate) cpu_quota: Option<f64>,
+	pub(crate) memory_current: u64,
+	// `None` if the cgroup has no memory limit.
+	pub(crate) memory_max: Option<u64>,
+	pub(crate) read_bytes: u64,
+	pub(crate) written_bytes: u64,
+	pub(crate) pids_current: u64,
+}
+
+impl Cgroup {
+	pub(crate) fn update(&mut self, path: &std::path::Path, buf: &mut Vec<u8>) -> Result<(), crate::Error> {
+		self.now = std::time::Instant::now();
+
+		// The cgroup is removed when the unit it belongs to is stopped, and is recreated when the unit is started again.
+		// So the cgroup not existing is not an error.
+		self.usage_usec = 0;
+		self.exists = crate::hwmon::for_each_line_if_exists(&path.join("cpu.stat"), buf, |line| {
+			let Some(value) = line.strip_prefix(b"usage_usec ") else { return Ok(false); };
+			self.usage_usec = crate::hwmon::parse_bytes(value)?;
+			Ok(true)
+		})?;
+		if !self.exists {
+			return Ok(());
+		}
+
+		// The root cgroup does not have any of the following files.
+
+		let cpu_max_path = path.join("cpu.max");
+		self.cpu_quota = match crate::hwmon::parse_hwmon_raw(&cpu_max_path, buf)? {
+			// The file contains the quota and the period, both in microseconds, eg `200000 100000` means two CPUs.
+			Some(cpu_max) => crate::Error::with_path_context(&cpu_max_path, |_| {
+				let (quota, period) = cpu_max.split_once(' ').ok_or("malformed cpu.max")?;
+				if quota == "max" {
+					return Ok(None);
+				}
+				let quota: f64 = quota.parse()?;
+				let period: f64 = period.parse()?;
+				Ok(Some(quota / period))
+			})?,
+			None => None,
+		};
+
+		self.memory_current = crate::hwmon::parse_hwmon(&path.join("memory.current"), buf)?.unwrap_or_default();
+
+		let memory_max_path = path.join("memory.max");
+		self.memory_max = match crate::hwmon::parse_hwmon_raw(&memory_max_path, buf)? {
+			Some("max") | None => None,
+			Some(memory_max) => Some(crate::Error::with_path_context(&memory_max_path, |_| Ok(memory_max.parse()?))?),
+		};
+
+		self.read_bytes = 0;
+		self.written_bytes = 0;
+		crate::hwmon::for_each_line_if_exists(&path.join("io.stat"), buf, |line| {
+			// Each line is for one device, eg `8:16 rbytes=1459200 wbytes=314773504 rios=192 wios=353 dbytes=0 dios=0`
+			for part in line.split(|&b| b == b' ').skip(1) {
+				if let Some(value) = part.strip_prefix(b"rbytes=") {
+					self.read_bytes += crate::hwmon::parse_bytes::<u64>(value)?;
+				}
+				else if let Some(value) = part.strip_prefix(b"wbytes=") {
+					self.written_bytes += crate::hwmon::parse_bytes::<u64>(value)?;
+				}
+			}
+
+			Ok(false)
+		})?;
+
+		self.pids_current = crate::hwmon::parse_hwmon(&path.join("pids.current"), buf)?.unwrap_or_default();
+
+		Ok(())
+	}
+}
+
+// Returns the path of the cgroup whose limits apply to this process. This is the nearest cgroup, starting with the one
+// this process belongs to, that has a CPU or memory limit. The process's own cgroup usually has none, eg `system.slice/sensord.service`,
+// while the limits of a container are set on an ancestor of it. The search stops at the root of the cgroup namespace,
+// ie `/sys/fs/cgroup` as seen by this process, which is used if no cgroup has a limit. When running in a container
+// with its own cgroup namespace, that root is the container's cgroup.
+pub(crate) fn own() -> Result<std::path::PathBuf, crate::Error> {
+	let mut result = None;
+
+	let path = "/proc/self/cgroup".as_ref();
+
+	// cgroup v2 has a single line of the form `0::/path`. A system with cgroup v1 controllers mounted has more lines for those.
+	crate::hwmon::for_each_line(path, &mut vec![], |line| {
+		let Some(cgroup) = line.strip_prefix(b"0::") else { return Ok(false); };
+		let cgroup = str::from_utf8(cgroup)?;
+		result = Some(cgroup.to_owned());
+		Ok(true)
+	})?;
+
+	let cgroup = crate::Error::with_path_context(path, |_| Ok(result.ok_or("process does not belong to a cgroup v2 cgroup")?))?;
+	Ok(nearest_limited(&cgroup, has_limit))
+}
+
+fn nearest_limited(cgroup: &str, mut has_limit: impl FnMut(&std::path::Path) -> bool) -> std::path::PathBuf {
+	let root = std::path::Path::new("/sys/fs/cgroup");
+	let path = root.join(cgroup.trim_start_matches('/'));
+	path.ancestors()
+		.take_while(|path| path.starts_with(root) && *path != root)
+		.find(|path| has_limit(path))
+		.unwrap_or(root)
+		.to_owned()
+}
+
+fn has_limit(path: &std::path::Path) -> bool {
+	// `cpu.max` is of the form `quota period` and `memory.max` is a number of bytes. Either is `max` if there is no limit.
+	// Neither file exists in the root cgroup.
+	let is_limited = |file_name| std::fs::read_to_string(path.join(file_name)).is_ok_and(|value| !value.starts_with("max"));
+	is_limited("cpu.max") || is_limited("memory.max")
+}
+
+// Resolves a cgroup specified in the config to its path under `/sys/fs/cgroup`
+//
+// - A path like `system.slice/docker.service` is used as-is.
+//
+// - A systemd slice like `user-1000.slice` is expanded to its full path `user.slice/user-1000.slice`
+//
+// - Any other systemd unit like `docker.service` is searched for in the cgroup hierarchy. If it isn't found,
+//   because the unit is not running right now, `None` is returned. See `system_service_path`
+pub(crate) fn resolve(name: &str) -> Result<Option<std::path::PathBuf>, crate::Error> {
+	let root = std::path::Path::new("/sys/fs/cgroup");
+
+	if name.contains('/') {
+		return Ok(Some(root.join(name.trim_start_matches('/'))));
+	}
+
+	if let Some(slice_path) = systemd_slice_path(name) {
+		return Ok(Some(root.join(slice_path)));
+	}
+
+	let mut dirs = vec![root.to_owned()];
+	while let Some(dir) = dirs.pop() {
+		for entry in crate::std2::fs::read_dir(&dir)? {
+			let entry = entry?;
+			if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+				continue;
+			}
+
+			let path = entry.path();
+			if entry.file_name() == name {
+				return Ok(Some(path));
+			}
+
+			dirs.push(path);
+		}
+	}
+
+	Ok(None)
+}
+
+// The path that a system service that isn't running yet will have when it's started.
+pub(crate) fn system_service_path(name: &str) -> std::path::PathBuf {
+	std::path::Path::new("/sys/fs/cgroup/system.slice").join(name)
+}
+
+// Slices are nested according to the dashes in their name, eg `foo-bar-baz.slice` is at `foo.slice/foo-bar.slice/foo-bar-baz.slice`
+fn systemd_slice_path(name: &str) -> Option<std::path::PathBuf> {
+	let prefix = name.strip_suffix(".slice")?;
+	if prefix == "-" {
+		// The root slice
+		return Some(std::path::PathBuf::new());
+	}
+
+	let mut result = std::path::PathBuf::new();
+	for (i, _) in prefix.match_indices('-') {
+		result.push(format!("{}.slice", &prefix[..i]));
+	}
+	result.push(name);
+	Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn systemd_slice_path() {
+		for (name, expected) in [
+			("-.slice", Some("")),
+			("user.slice", Some("user.slice")),
+			("user-1000.slice", Some("user.slice/user-1000.slice")),
+			("foo-bar-baz.slice", Some("foo.slice/foo-bar.slice/foo-bar-baz.slice")),
+			("docker.service", None),
+		] {
+			let actual = super::systemd_slice_path(name);
+			assert_eq!(actual.as_deref(), expected.map(std::path::Path::new), "{name}");
+		}
+	}
+
+	#[test]
+	fn nearest_limited() {
+		let limited = [
+			"/sys/fs/cgroup/machine.slice/libpod-1234.scope",
+			"/sys/fs/cgroup/user.slice",
+		];
+		for (cgroup, expected) in [
+			// The process's own cgroup has a limit
+			("/machine.slice/libpod-1234.scope", "/sys/fs/cgroup/machine.slice/libpod-1234.scope"),
+			// An ancestor has a limit
+			("/machine.slice/libpod-1234.scope/container", "/sys/fs/cgroup/machine.slice/libpod-1234.scope"),
+			("/user.slice/user-1000.slice/session-1.scope", "/sys/fs/cgroup/user.slice"),
+			// No cgroup has a limit, so the root of the namespace is used
+			("/system.slice/sensord.service", "/sys/fs/cgroup"),
+			// The process is at the root of its cgroup namespace, eg in a container
+			("/", "/sys/fs/cgroup"),
+		] {
+			let actual = super::nearest_limited(cgroup, |path| limited.iter().any(|limited| path == std::path::Path::new(limited)));
+			assert_eq!(actual, std::path::Path::new(expected), "{cgroup}");
+		}
+	}
+}
@@ -0,0 +1,282 @@
+// Implements `--check-config` and `--print-resolved`, which print the sysfs paths that the config resolved to,
+// so that a config can be written without having to start the daemon and watch `hwtop` to see if the sensors are right.
+//
+// With `read_values`, every sensor is also read once and its value or error is printed next to its path.
+//
+// Returns the number of sensors that could not be read. Optional devices that weren't found are not counted.
+pub(crate) fn print(config: &crate::config::Config, read_values: bool) -> Result<usize, crate::Error> {
+	let mut stdout = std::io::stdout().lock();
+	let mut problems = 0;
+	let mut buf = vec![0_u8; 512];
+
+	print_inner(&mut stdout, config, read_values, &mut problems, &mut buf).map_err(|err| crate::Error::Other(err.into()))?;
+
+	Ok(problems)
+}
+
+fn print_inner(
+	w: &mut impl std::io::Write,
+	config: &crate::config::Config,
+	read_values: bool,
+	problems: &mut usize,
+	buf: &mut Vec<u8>,
+) -> std::io::Result<()> {
+	let sources = &config.sources;
+
+	// The file that each entry came from is only printed if there are drop-in files.
+	let has_drop_ins = sources.files.len() > 1;
+
+	if has_drop_ins {
+		writeln!(w, "config files:")?;
+		for path in &sources.files {
+			writeln!(w, "\t{}", path.display())?;
+		}
+		writeln!(w)?;
+	}
+
+	writeln!(w, "interval: {:?}, clients can request {:?} to {:?}", config.interval, config.min_interval, config.max_interval)?;
+	for (section, interval) in [("cpus", config.cpus_interval), ("interrupts", config.interrupts_interval), ("processes", config.processes_interval), ("vmstat", config.vmstat_interval)] {
+		if let Some(interval) = interval {
+			writeln!(w, "{section} interval: {interval:?}")?;
+		}
+	}
+	writeln!(w)?;
+
+	if !config.hwmon.is_empty() {
+		writeln!(w, "hwmon:")?;
+		for (name, dir) in &config.hwmon {
+			writeln!(w, "\t{name}: {}{}", Path(dir.as_deref()), Source(sources.hwmon.get(name).filter(|_| has_drop_ins)))?;
+		}
+		writeln!(w)?;
+	}
+
+	if !config.power_supply.is_empty() {
+		writeln!(w, "power_supply:")?;
+		for (name, dir) in &config.power_supply {
+			writeln!(w, "\t{name}: {}{}", Path(dir.as_deref()), Source(sources.power_supply.get(name).filter(|_| has_drop_ins)))?;
+		}
+		writeln!(w)?;
+	}
+
+	for (i, sensor_group) in config.sensors.iter().enumerate() {
+		writeln!(w, "sensor group {:?}{}{}:", sensor_group.name, Interval(sensor_group.interval), Source(sources.sensors.get(i).map(|sources| &sources.location).filter(|_| has_drop_ins)))?;
+
+		for sensor in &sensor_group.temps {
+			write!(w, "\ttemp {}: {}{}{}", Name(sensor.name.as_deref()), Path(sensor.path.as_deref()), sensor.calibration, sensor.filter)?;
+			print_value(w, read_values, problems, sensor.path.is_some(), || crate::hwmon::parse_temp_sensor(sensor.path.as_deref(), buf), |w, temp| {
+				if let Some(temp) = temp {
+					write!(w, "{:.1}°C", sensor.calibration.apply(temp))?;
+				}
+				Ok(())
+			})?;
+		}
+
+		for sensor in &sensor_group.fans {
+			write!(w, "\tfan {}: {}, {}{}{}", Name(sensor.name.as_deref()), Path(sensor.fan_path.as_deref()), Path(sensor.pwm_path.as_deref()), sensor.calibration, sensor.filter)?;
+			print_value(
+				w,
+				read_values,
+				problems,
+				sensor.fan_path.is_some(),
+				|| Ok((
+					crate::hwmon::parse_fan_sensor(sensor.fan_path.as_deref(), buf)?,
+					crate::hwmon::parse_pwm_sensor(sensor.pwm_path.as_deref(), buf)?,
+				)),
+				|w, (fan, pwm)| write!(w, "{} RPM, pwm {}", fan.map(|fan| sensor.calibration.apply_u16(fan)).unwrap_or_default(), pwm.unwrap_or_default()),
+			)?;
+		}
+
+		for sensor in &sensor_group.bats {
+			write!(w, "\tbattery {}: {}, {}{}{}", Name(sensor.name.as_deref()), Path(sensor.capacity_path.as_deref()), Path(sensor.status_path.as_deref()), sensor.calibration, sensor.filter)?;
+			print_value(
+				w,
+				read_values,
+				problems,
+				sensor.capacity_path.is_some(),
+				|| Ok((
+					crate::hwmon::parse_bat_capacity_sensor(sensor.capacity_path.as_deref(), buf)?,
+					crate::hwmon::parse_bat_status_sensor(sensor.status_path.as_deref(), buf)?,
+				)),
+				|w, (capacity, charging)| write!(w, "{}%{}", capacity.map(|capacity| sensor.calibration.apply_u8(capacity)).unwrap_or_default(), if charging.unwrap_or_default() { ", charging" } else { "" }),
+			)?;
+		}
+
+		for sensor in &sensor_group.cooling {
+			write!(w, "\tcooling device {}: {}{}", Name(sensor.name.as_deref()), Path(sensor.cur_state_path.as_deref()), sensor.filter)?;
+			print_value(
+				w,
+				read_values,
+				problems,
+				sensor.cur_state_path.is_some(),
+				|| Ok((
+					crate::hwmon::parse_cooling_sensor(sensor.cur_state_path.as_deref(), buf)?,
+					crate::hwmon::parse_cooling_sensor(sensor.max_state_path.as_deref(), buf)?,
+				)),
+				|w, (cur_state, max_state)| write!(w, "{}/{}", cur_state.unwrap_or_default(), max_state.unwrap_or_default()),
+			)?;
+		}
+
+		for sensor in &sensor_group.files {
+			write!(w, "\tfile {:?}: {}", sensor.name, sensor.path.display())?;
+			if let Some(regex) = &sensor.regex {
+				write!(w, " regex {:?}", regex.as_str())?;
+			}
+			if sensor.rate {
+				write!(w, " rate")?;
+			}
+			write!(w, "{}{}", sensor.calibration, sensor.filter)?;
+			// A rate needs two samples, so only the counter itself is shown.
+			print_value(w, read_values, problems, true, || crate::hwmon::parse_file_sensor(&sensor.path, sensor.regex.as_ref(), buf), |w, value|
+				if sensor.rate {
+					write!(w, "{value} (counter)")
+				}
+				else {
+					write!(w, "{}{}", sensor.calibration.apply(value), sensor.unit)
+				})?;
+		}
+
+		for sensor in &sensor_group.commands {
+			write!(w, "\tcommand {:?}: {:?} every {:?}, timeout {:?}", sensor.name, sensor.command, sensor.interval, sensor.timeout)?;
+			match &sensor.output {
+				crate::config::CommandOutput::Number => (),
+				crate::config::CommandOutput::Regex(regex) => write!(w, " regex {:?}", regex.as_str())?,
+				crate::config::CommandOutput::Key(key) => write!(w, " key {key:?}")?,
+				crate::config::CommandOutput::Json(path) => write!(w, " json {path:?}")?,
+			}
+			write!(w, "{}{}", sensor.calibration, sensor.filter)?;
+			print_value(w, read_values, problems, true, || crate::command::run(sensor), |w, value| write!(w, "{}{}", sensor.calibration.apply(value), sensor.unit))?;
+		}
+
+		// Derived sensors are computed from the published values of other sensors, so they have no value of their own to read here.
+		for sensor in &sensor_group.derived {
+			writeln!(w, "\tderived {:?}: {}{}{}", sensor.name, sensor.expression, if sensor.unit.is_empty() { String::new() } else { format!(" {}", sensor.unit) }, sensor.filter)?;
+		}
+
+		writeln!(w)?;
+	}
+
+	for (i, network) in config.networks.iter().enumerate() {
+		write!(w, "network {:?}{}{}: {}, {}", network.name, Interval(network.interval), Source(sources.networks.get(i).filter(|_| has_drop_ins)), network.rx_path.display(), network.tx_path.display())?;
+		print_value(
+			w,
+			read_values,
+			problems,
+			true,
+			|| Ok((
+				crate::hwmon::parse_hwmon::<u64>(&network.rx_path, buf)?,
+				crate::hwmon::parse_hwmon::<u64>(&network.tx_path, buf)?,
+			)),
+			|w, (rx, tx)| write!(w, "{} bytes received, {} bytes sent", rx.unwrap_or_default(), tx.unwrap_or_default()),
+		)?;
+	}
+
+	for (i, cgroup) in config.cgroups.iter().enumerate() {
+		write!(w, "cgroup {:?}{}{}: {}", cgroup.name, Interval(cgroup.interval), Source(sources.cgroups.get(i).filter(|_| has_drop_ins)), cgroup.path.display())?;
+		print_value(
+			w,
+			read_values,
+			problems,
+			true,
+			|| {
+				let mut value = crate::cgroup::Cgroup {
+					now: std::time::Instant::now(),
+					exists: false,
+					usage_usec: 0,
+					cpu_quota: None,
+					memory_current: 0,
+					memory_max: None,
+					read_bytes: 0,
+					written_bytes: 0,
+					pids_current: 0,
+				};
+				value.update(&cgroup.path, buf)?;
+				Ok(value)
+			},
+			|w, cgroup|
+				if cgroup.exists {
+					write!(w, "{} bytes of memory, {} tasks", cgroup.memory_current, cgroup.pids_current)
+				}
+				else {
+					write!(w, "does not exist yet")
+				},
+		)?;
+	}
+
+	if let Some(container_cgroup) = &config.container_cgroup {
+		writeln!(w, "container cgroup: {}", container_cgroup.display())?;
+	}
+
+	Ok(())
+}
+
+fn print_value<W, T>(
+	w: &mut W,
+	read_values: bool,
+	problems: &mut usize,
+	available: bool,
+	read: impl FnOnce() -> Result<T, crate::Error>,
+	print: impl FnOnce(&mut W, T) -> std::io::Result<()>,
+) -> std::io::Result<()> where W: std::io::Write {
+	if read_values && available {
+		match read() {
+			Ok(value) => {
+				write!(w, " = ")?;
+				print(w, value)?;
+			},
+
+			Err(err) => {
+				*problems += 1;
+				write!(w, " error: {}", err.to_string().trim_end().replace('\n', "; "))?;
+			},
+		}
+	}
+
+	writeln!(w)?;
+
+	Ok(())
+}
+
+struct Name<'a>(Option<&'a str>);
+
+impl std::fmt::Display for Name<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0 {
+			Some(name) => write!(f, "{name:?}"),
+			None => f.write_str("(unnamed)"),
+		}
+	}
+}
+
+struct Path<'a>(Option<&'a std::path::Path>);
+
+impl std::fmt::Display for Path<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0 {
+			Some(path) => path.display().fmt(f),
+			None => f.write_str("unavailable"),
+		}
+	}
+}
+
+// The interval of a section, which is only printed if it overrides the global interval.
+struct Interval(Option<std::time::Duration>);
+
+impl std::fmt::Display for Interval {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0 {
+			Some(interval) => write!(f, " every {interval:?}"),
+			None => Ok(()),
+		}
+	}
+}
+
+struct Source<'a>(Option<&'a crate::config::Location>);
+
+impl std::fmt::Display for Source<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.0 {
+			Some(location) => write!(f, " (from {location})"),
+			None => Ok(()),
+		}
+	}
+}
@@ -0,0 +1,326 @@
+// Tracks the clients that are subscribed to the `Sensors` signal, so that sensors are only sampled at a slow keep-alive rate
+// while nobody is listening, and the intervals that clients requested.
+//
+// Clients call the `Subscribe` and `Unsubscribe` methods of the `dev.arnavion.sensord.Daemon` interface of the `/dev/arnavion/sensord/Daemon` object,
+// and `SetInterval` with an interval in milliseconds, or 0 to withdraw their request. The standard `org.freedesktop.DBus.Peer` methods
+// are also implemented, on every path. A client that disconnects without unsubscribing, eg because it crashed,
+// is removed along with its requested interval when the bus reports that its unique name has no owner any more.
+//
+// The method calls are received on their own thread, which owns the connection that owns the daemon's name.
+// `dbus_pure::Client::recv` blocks, so the main loop sends the signals on a different connection.
+pub(crate) struct Clients {
+	inner: std::sync::Arc<(std::sync::Mutex<State>, std::sync::Condvar)>,
+}
+
+struct State {
+	// The unique names of the subscribed clients, like ":1.42"
+	subscribers: std::collections::BTreeSet<String>,
+	// The intervals that clients requested, by unique name. The shortest one is used. See `Clients::interval`
+	intervals: std::collections::BTreeMap<String, std::time::Duration>,
+	// `min_interval` and `max_interval` of the config
+	bounds: (std::time::Duration, std::time::Duration),
+	// Set if the connection failed, after which the thread stops. `crate::Error` isn't `Send`, so it's stored as a string.
+	error: Option<String>,
+}
+
+impl Clients {
+	pub(crate) fn spawn(mut dbus_client: dbus_pure::Client, config: &crate::config::Config) -> Result<Self, crate::Error> {
+		{
+			use crate::OrgFreeDesktopDbusInterface;
+
+			let obj = crate::OrgFreeDesktopDbusObject {
+				name: "org.freedesktop.DBus".into(),
+				path: dbus_pure::proto::ObjectPath("/org/freedesktop/DBus".into()),
+			};
+			let () =
+				obj.add_match(
+					&mut dbus_client,
+					"type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',member='NameOwnerChanged',arg2=''",
+				).map_err(|err| crate::Error::Other(err.into()))?;
+		}
+
+		let inner = std::sync::Arc::new((
+			std::sync::Mutex::new(State::new((config.min_interval, config.max_interval))),
+			std::sync::Condvar::new(),
+		));
+
+		let _ = std::thread::Builder::new().name("clients".to_owned()).spawn({
+			let inner = inner.clone();
+			move || {
+				let (state, condvar) = &*inner;
+
+				loop {
+					let (header, body) = match dbus_client.recv() {
+						Ok(message) => message,
+						Err(err) => {
+							state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).error = Some(format!("could not receive method calls: {err}"));
+							condvar.notify_all();
+							break;
+						},
+					};
+
+					match header.r#type {
+						dbus_pure::proto::MessageType::MethodCall { member, path } => {
+							let Some(sender) = header.fields.iter().find_map(|field| match field {
+								dbus_pure::proto::MessageHeaderField::Sender(sender) => Some(sender.clone().into_owned()),
+								_ => None,
+							}) else { continue; };
+							let interface = header.fields.iter().find_map(|field| match field {
+								dbus_pure::proto::MessageHeaderField::Interface(interface) => Some(&**interface),
+								_ => None,
+							});
+
+							let result = state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).method_call(interface, &path.0, &member, &sender, body.as_ref());
+							condvar.notify_all();
+
+							if let Err(err) = reply(&mut dbus_client, header.serial, sender, result) {
+								state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).error = Some(format!("could not reply to method call: {err}"));
+								condvar.notify_all();
+								break;
+							}
+						},
+
+						dbus_pure::proto::MessageType::Signal { interface, member, path: _ } if interface == "org.freedesktop.DBus" && member == "NameOwnerChanged" => {
+							// The match rule only matches names that lost their owner, ie clients that disconnected.
+							if let Some(dbus_pure::proto::Variant::Tuple { elements }) = body &&
+								let [dbus_pure::proto::Variant::String(name), _, dbus_pure::proto::Variant::String(new_owner)] = &*elements &&
+								new_owner.is_empty()
+							{
+								state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).disconnected(name);
+								condvar.notify_all();
+							}
+						},
+
+						_ => (),
+					}
+				}
+			}
+		}).map_err(|err| crate::Error::Other(format!("could not spawn thread to receive method calls: {err}").into()))?;
+
+		Ok(Clients { inner })
+	}
+
+	pub(crate) fn set_bounds(&self, min_interval: std::time::Duration, max_interval: std::time::Duration) {
+		let (state, _) = &*self.inner;
+		state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).bounds = (min_interval, max_interval);
+	}
+
+	// The shortest interval that any client requested, clamped to the bounds in case they changed since it was requested,
+	// or `None` if no client requested one.
+	pub(crate) fn interval(&self) -> Option<std::time::Duration> {
+		let (state, _) = &*self.inner;
+		state.lock().unwrap_or_else(std::sync::PoisonError::into_inner).interval()
+	}
+
+	pub(crate) fn has_subscribers(&self) -> Result<bool, crate::Error> {
+		let (state, _) = &*self.inner;
+		let mut state = state.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+		if let Some(err) = state.error.take() {
+			return Err(crate::Error::Other(err.into()));
+		}
+		Ok(!state.subscribers.is_empty())
+	}
+
+	// Sleeps for the given duration. If no client is subscribed, it wakes up as soon as one subscribes,
+	// so that sampling speeds up immediately instead of after the keep-alive interval.
+	pub(crate) fn sleep(&self, duration: std::time::Duration) {
+		let (state, condvar) = &*self.inner;
+		let state = state.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+		if state.subscribers.is_empty() {
+			let _ =
+				condvar.wait_timeout_while(state, duration, |state| state.subscribers.is_empty() && state.error.is_none())
+				.unwrap_or_else(std::sync::PoisonError::into_inner);
+		}
+		else {
+			drop(state);
+			std::thread::sleep(duration);
+		}
+	}
+}
+
+type MethodResult = Result<Option<dbus_pure::proto::Variant<'static>>, (&'static str, String)>;
+
+impl State {
+	fn new(bounds: (std::time::Duration, std::time::Duration)) -> Self {
+		State {
+			subscribers: Default::default(),
+			intervals: Default::default(),
+			bounds,
+			error: None,
+		}
+	}
+
+	// Returns the body of the reply, or the name and message of the error to reply with.
+	fn method_call(&mut self, interface: Option<&str>, path: &str, member: &str, sender: &str, body: Option<&dbus_pure::proto::Variant<'_>>) -> MethodResult {
+		// The interface is optional in method calls, in which case the method is looked up by name alone.
+		match (interface, member) {
+			(Some("org.freedesktop.DBus.Peer") | None, "Ping") => Ok(None),
+
+			(Some("org.freedesktop.DBus.Peer") | None, "GetMachineId") => match std::fs::read_to_string("/etc/machine-id") {
+				Ok(machine_id) => Ok(Some(dbus_pure::proto::Variant::String(machine_id.trim().to_owned().into()))),
+				Err(err) => Err(("org.freedesktop.DBus.Error.Failed", format!("could not read /etc/machine-id: {err}"))),
+			},
+
+			(Some("dev.arnavion.sensord.Daemon") | None, _) if path != "/dev/arnavion/sensord/Daemon" =>
+				Err(("org.freedesktop.DBus.Error.UnknownObject", format!("unknown object {path:?}"))),
+
+			(Some("dev.arnavion.sensord.Daemon") | None, "Subscribe") => {
+				self.subscribe(sender);
+				Ok(None)
+			},
+
+			(Some("dev.arnavion.sensord.Daemon") | None, "Unsubscribe") => {
+				self.unsubscribe(sender);
+				Ok(None)
+			},
+
+			// Returns the interval that the client will get, ie the requested interval clamped to the bounds, or 0.
+			(Some("dev.arnavion.sensord.Daemon") | None, "SetInterval") => match body {
+				Some(&dbus_pure::proto::Variant::U32(interval_ms)) => Ok(Some(dbus_pure::proto::Variant::U32(self.set_interval(sender, interval_ms)))),
+				_ => Err(("org.freedesktop.DBus.Error.InvalidArgs", "SetInterval takes an interval in milliseconds as a u32".to_owned())),
+			},
+
+			(Some("dev.arnavion.sensord.Daemon" | "org.freedesktop.DBus.Peer") | None, _) =>
+				Err(("org.freedesktop.DBus.Error.UnknownMethod", format!("unknown method {member:?}"))),
+
+			(Some(interface), _) => Err(("org.freedesktop.DBus.Error.UnknownInterface", format!("unknown interface {interface:?}"))),
+		}
+	}
+
+	fn subscribe(&mut self, sender: &str) {
+		self.subscribers.insert(sender.to_owned());
+	}
+
+	fn unsubscribe(&mut self, sender: &str) {
+		self.subscribers.remove(sender);
+		self.intervals.remove(sender);
+	}
+
+	// Returns the interval in milliseconds that the client will get, ie the requested interval clamped to the bounds, or 0.
+	fn set_interval(&mut self, sender: &str, interval_ms: u32) -> u32 {
+		if interval_ms == 0 {
+			self.intervals.remove(sender);
+			return 0;
+		}
+
+		let (min_interval, max_interval) = self.bounds;
+		let interval = std::time::Duration::from_millis(interval_ms.into()).clamp(min_interval, max_interval);
+		self.intervals.insert(sender.to_owned(), interval);
+		u32::try_from(interval.as_millis()).unwrap_or(u32::MAX)
+	}
+
+	// The client with this unique name disconnected from the bus.
+	fn disconnected(&mut self, name: &str) {
+		self.subscribers.remove(name);
+		self.intervals.remove(name);
+	}
+
+	// See `Clients::interval`
+	fn interval(&self) -> Option<std::time::Duration> {
+		let (min_interval, max_interval) = self.bounds;
+		self.intervals.values().min().map(|interval| (*interval).clamp(min_interval, max_interval))
+	}
+}
+
+fn reply(
+	dbus_client: &mut dbus_pure::Client,
+	reply_serial: u32,
+	destination: String,
+	result: MethodResult,
+) -> Result<(), dbus_pure::SendError> {
+	let (r#type, body) = match result {
+		Ok(body) => (dbus_pure::proto::MessageType::MethodReturn { reply_serial }, body),
+		Err((name, message)) => (
+			dbus_pure::proto::MessageType::Error { name: name.into(), reply_serial },
+			Some(dbus_pure::proto::Variant::String(message.into())),
+		),
+	};
+
+	let _ = dbus_client.send(
+		&mut dbus_pure::proto::MessageHeader {
+			r#type,
+			flags: dbus_pure::proto::message_flags::NO_REPLY_EXPECTED,
+			body_len: 0,
+			serial: 0,
+			fields: vec![dbus_pure::proto::MessageHeaderField::Destination(destination.into())].into(),
+		},
+		body.as_ref(),
+	)?;
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn state() {
+		let ms = std::time::Duration::from_millis;
+		let mut state = super::State::new((ms(250), ms(10000)));
+
+		state.subscribe(":1.1");
+		state.subscribe(":1.2");
+		state.subscribe(":1.1");
+		assert_eq!(state.subscribers.len(), 2);
+
+		// Requested intervals are clamped to the bounds, and the shortest one wins.
+		assert_eq!(state.set_interval(":1.1", 100), 250);
+		assert_eq!(state.set_interval(":1.2", 60000), 10000);
+		assert_eq!(state.interval(), Some(ms(250)));
+		assert_eq!(state.set_interval(":1.1", 2000), 2000);
+		assert_eq!(state.interval(), Some(ms(2000)));
+
+		// The requested intervals are clamped to the new bounds when they change.
+		state.bounds = (ms(500), ms(1000));
+		assert_eq!(state.interval(), Some(ms(1000)));
+		state.bounds = (ms(250), ms(10000));
+
+		// 0 withdraws the request.
+		assert_eq!(state.set_interval(":1.1", 0), 0);
+		assert_eq!(state.interval(), Some(ms(10000)));
+
+		state.unsubscribe(":1.2");
+		assert_eq!(state.subscribers.len(), 1);
+		assert_eq!(state.interval(), None);
+
+		// A client that disconnects without unsubscribing is removed along with its requested interval.
+		assert_eq!(state.set_interval(":1.1", 1000), 1000);
+		state.disconnected(":1.1");
+		assert!(state.subscribers.is_empty());
+		assert_eq!(state.interval(), None);
+	}
+
+	#[test]
+	fn method_call() {
+		let ms = std::time::Duration::from_millis;
+		let mut state = super::State::new((ms(250), ms(10000)));
+
+		let mut call = |interface, path, member, body: Option<dbus_pure::proto::Variant<'_>>| {
+			match state.method_call(interface, path, member, ":1.1", body.as_ref()) {
+				Ok(Some(dbus_pure::proto::Variant::U32(value))) => Ok(Some(value)),
+				Ok(_) => Ok(None),
+				Err((name, _)) => Err(name),
+			}
+		};
+
+		let daemon = Some("dev.arnavion.sensord.Daemon");
+		let path = "/dev/arnavion/sensord/Daemon";
+
+		assert_eq!(call(daemon, path, "Subscribe", None), Ok(None));
+		assert_eq!(call(daemon, path, "SetInterval", Some(dbus_pure::proto::Variant::U32(100))), Ok(Some(250)));
+		assert_eq!(call(daemon, path, "SetInterval", None), Err("org.freedesktop.DBus.Error.InvalidArgs"));
+		// The interface is optional.
+		assert_eq!(call(None, path, "Unsubscribe", None), Ok(None));
+
+		// Peer methods are implemented on every path.
+		assert_eq!(call(Some("org.freedesktop.DBus.Peer"), path, "Ping", None), Ok(None));
+		assert_eq!(call(Some("org.freedesktop.DBus.Peer"), "/", "Ping", None), Ok(None));
+
+		assert_eq!(call(daemon, "/", "Subscribe", None), Err("org.freedesktop.DBus.Error.UnknownObject"));
+		assert_eq!(call(daemon, path, "Frobnicate", None), Err("org.freedesktop.DBus.Error.UnknownMethod"));
+		assert_eq!(call(Some("org.freedesktop.DBus.Peer"), path, "Subscribe", None), Err("org.freedesktop.DBus.Error.UnknownMethod"));
+		assert_eq!(call(Some("org.freedesktop.DBus.Properties"), path, "GetAll", None), Err("org.freedesktop.DBus.Error.UnknownInterface"));
+
+		assert!(state.subscribers.is_empty());
+		assert!(state.intervals.is_empty());
+	}
+}
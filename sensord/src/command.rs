@@ -0,0 +1,515 @@
+// Runs the commands of command sensors. Each command runs on its own thread at its own interval,
+// so that a command that is slow or hangs never delays the sampling of everything else.
+//
+// The thread of a command stops when its `Sensor` is dropped, after the currently running command (if any) exits or times out.
+pub(crate) struct Sensor {
+	latest: std::sync::Arc<std::sync::Mutex<Latest>>,
+	_stop: std::sync::mpsc::Sender<()>,
+}
+
+#[derive(Default)]
+struct Latest {
+	value: Option<f64>,
+	error: String,
+}
+
+// Commands whose output is larger than this are assumed to be misbehaving.
+const MAX_OUTPUT_LEN: usize = 1024 * 1024;
+
+impl Sensor {
+	pub(crate) fn spawn(name: String, sensor: &crate::config::CommandSensor) -> Self {
+		let latest: std::sync::Arc<std::sync::Mutex<Latest>> = Default::default();
+		let (stop_sender, stop) = std::sync::mpsc::channel();
+
+		let thread = {
+			let latest = latest.clone();
+			let sensor = sensor.clone();
+			std::thread::Builder::new().name(format!("command {}", sensor.name)).spawn(move || {
+				// A command that fails repeatedly is run less often, and its errors are only logged when they change.
+				let mut health: crate::health::Health = Default::default();
+
+				// The value is calibrated and filtered here rather than when it's published,
+				// since the same value is published every sample until the command is run again.
+				let mut filter_state: crate::filter::State = Default::default();
+
+				loop {
+					let value = health.sample(&name, || run(&sensor));
+					let value = value.map(|value| filter_state.apply(&sensor.filter, sensor.calibration.apply(value)));
+
+					{
+						let mut latest = latest.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+						match value {
+							Some(Ok(value)) => {
+								latest.value = Some(value);
+								latest.error.clear();
+							},
+							Some(Err(err)) => latest.error = err,
+							None => health.error().clone_into(&mut latest.error),
+						}
+					}
+
+					match stop.recv_timeout(sensor.interval) {
+						Err(std::sync::mpsc::RecvTimeoutError::Timeout) => (),
+						Ok(()) | Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+					}
+				}
+			})
+		};
+		if let Err(err) = thread {
+			latest.lock().unwrap_or_else(std::sync::PoisonError::into_inner).error = format!("could not spawn thread to run command: {err}");
+		}
+
+		Sensor {
+			latest,
+			_stop: stop_sender,
+		}
+	}
+
+	// The most recent calibrated and filtered value, and the error of the most recent run or an empty string if it succeeded.
+	//
+	// The value is `None` until the command succeeds for the first time. If the command fails, the value is the last successfully parsed value.
+	pub(crate) fn latest(&self) -> (Option<f64>, String) {
+		let latest = self.latest.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+		(latest.value, latest.error.clone())
+	}
+}
+
+// Runs the command once and parses the value from its output.
+pub(crate) fn run(sensor: &crate::config::CommandSensor) -> Result<f64, crate::Error> {
+	let stdout = output(&sensor.command, sensor.timeout)?;
+	parse(&sensor.output, &stdout).map_err(crate::Error::Other)
+}
+
+fn output(command: &[String], timeout: std::time::Duration) -> Result<Vec<u8>, crate::Error> {
+	let (program, args) = command.split_first().ok_or_else(|| crate::Error::Other("command is empty".into()))?;
+	let mut command = std::process::Command::new(program);
+	command
+		.args(args)
+		.stdin(std::process::Stdio::null())
+		.stdout(std::process::Stdio::piped())
+		.stderr(std::process::Stdio::piped());
+	// The command runs in its own process group, so that a command that timed out can be killed along with any processes it started,
+	// like the children of a shell script, which would otherwise keep running and keep the pipes open.
+	std::os::unix::process::CommandExt::process_group(&mut command, 0);
+	let mut child = command.spawn().map_err(|err| crate::Error::Other(format!("could not run {program:?}: {err}").into()))?;
+
+	let result = read_output(&mut child, timeout);
+	if result.is_err() {
+		// The child may have already exited, in which case only the processes it left behind are killed, if any.
+		// It hasn't been waited for yet, so its pid is still its process group's ID.
+		if let Ok(pgid) = libc::pid_t::try_from(child.id()) {
+			unsafe { libc::kill(-pgid, libc::SIGKILL); }
+		}
+		let _ = child.wait();
+	}
+	let (stdout, stderr, status) = result.map_err(|err| crate::Error::Other(err.into()))?;
+
+	if !status.success() {
+		let stderr = String::from_utf8_lossy(&stderr);
+		let stderr = stderr.trim();
+		return Err(crate::Error::Other(
+			if stderr.is_empty() { format!("command failed with {status}") } else { format!("command failed with {status}: {stderr}") }.into(),
+		));
+	}
+
+	Ok(stdout)
+}
+
+// Reads stdout and stderr until both are closed, then waits for the child to exit. Fails if that takes longer than the timeout.
+fn read_output(child: &mut std::process::Child, timeout: std::time::Duration) -> Result<(Vec<u8>, Vec<u8>, std::process::ExitStatus), String> {
+	let deadline = std::time::Instant::now() + timeout;
+	let timed_out = || format!("command timed out after {timeout:?}");
+
+	let mut stdout = vec![];
+	let mut stderr = vec![];
+	let mut pipes = [
+		(child.stdout.take().map(|pipe| std::fs::File::from(std::os::fd::OwnedFd::from(pipe))), &mut stdout),
+		(child.stderr.take().map(|pipe| std::fs::File::from(std::os::fd::OwnedFd::from(pipe))), &mut stderr),
+	];
+	let mut chunk = [0_u8; 4096];
+
+	while pipes.iter().any(|(pipe, _)| pipe.is_some()) {
+		let mut fds: Vec<_> =
+			pipes.iter()
+			.filter_map(|(pipe, _)| pipe.as_ref())
+			.map(|pipe| libc::pollfd { fd: std::os::fd::AsRawFd::as_raw_fd(pipe), events: libc::POLLIN, revents: 0 })
+			.collect();
+
+		let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) else { return Err(timed_out()); };
+		let remaining = libc::c_int::try_from(remaining.as_millis()).unwrap_or(libc::c_int::MAX);
+		#[allow(clippy::cast_possible_truncation)]
+		let ready = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, remaining) };
+		match ready {
+			-1 => {
+				let err = std::io::Error::last_os_error();
+				if err.raw_os_error() == Some(libc::EINTR) {
+					continue;
+				}
+				return Err(format!("could not poll command output: {err}"));
+			},
+			0 => return Err(timed_out()),
+			_ => (),
+		}
+
+		let mut fds = fds.into_iter();
+		for (pipe, buf) in &mut pipes {
+			let Some(reader) = pipe else { continue; };
+			let Some(fd) = fds.next() else { break; };
+			if fd.revents == 0 {
+				continue;
+			}
+
+			match std::io::Read::read(reader, &mut chunk) {
+				Ok(0) => *pipe = None,
+				Ok(read) => {
+					if buf.len() + read > MAX_OUTPUT_LEN {
+						return Err(format!("command output is larger than {MAX_OUTPUT_LEN} bytes"));
+					}
+					buf.extend_from_slice(&chunk[..read]);
+				},
+				Err(err) if err.kind() == std::io::ErrorKind::Interrupted => (),
+				Err(err) => return Err(format!("could not read command output: {err}")),
+			}
+		}
+	}
+
+	// The command closed its output, but it might not have exited, eg if it forked a child that is still running.
+	loop {
+		match child.try_wait() {
+			Ok(Some(status)) => return Ok((stdout, stderr, status)),
+			Ok(None) if std::time::Instant::now() < deadline => std::thread::sleep(std::time::Duration::from_millis(10)),
+			Ok(None) => return Err(timed_out()),
+			Err(err) => return Err(format!("could not wait for command: {err}")),
+		}
+	}
+}
+
+fn parse(output: &crate::config::CommandOutput, stdout: &[u8]) -> Result<f64, Box<dyn std::error::Error>> {
+	match output {
+		crate::config::CommandOutput::Number => Ok(crate::hwmon::parse_bytes(stdout.trim_ascii())?),
+
+		crate::config::CommandOutput::Regex(regex) => {
+			for line in stdout.split(|&b| b == b'\n') {
+				if let Some(value) = crate::hwmon::parse_regex_capture(regex, line)? {
+					return Ok(value);
+				}
+			}
+			Err("no line of the output matches the regex".into())
+		},
+
+		crate::config::CommandOutput::Key(key) => {
+			let stdout = str::from_utf8(stdout)?;
+			for line in stdout.lines() {
+				let Some((line_key, value)) = line.split_once(['=', ':']) else { continue; };
+				if line_key.trim() != key {
+					continue;
+				}
+				// The value may be followed by a unit, with or without a space in between, eg `35 C` or `3.5W`
+				let value = value.trim_start();
+				let len = value.find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))).unwrap_or(value.len());
+				let value = value[..len].trim_end_matches(['e', 'E']);
+				return value.parse().map_err(|_| format!("{key:?} is not a number").into());
+			}
+			Err(format!("no line of the output has the key {key:?}").into())
+		},
+
+		crate::config::CommandOutput::Json(path) => {
+			let stdout = str::from_utf8(stdout)?;
+			Ok(json_value(stdout, path)?)
+		},
+	}
+}
+
+// Finds the number at the given path in the JSON document, where the path is a list of object keys and array indices separated by `.`
+// An empty path refers to the whole document. A string that contains a number is also accepted, and `true` and `false` are 1 and 0.
+//
+// Only as much of the document is parsed as is needed to find the value.
+fn json_value(json: &str, path: &str) -> Result<f64, String> {
+	let mut parser = JsonParser { json: json.as_bytes(), pos: 0 };
+
+	let segments = if path.is_empty() { None } else { Some(path.split('.')) };
+	for (i, segment) in segments.into_iter().flatten().enumerate() {
+		let parent = path.split('.').take(i).collect::<Vec<_>>().join(".");
+		let not_found = || format!("JSON output has no {:?}", path.split('.').take(i + 1).collect::<Vec<_>>().join("."));
+
+		parser.skip_whitespace();
+		match parser.peek() {
+			Some(b'{') => {
+				parser.pos += 1;
+				loop {
+					parser.skip_whitespace();
+					match parser.peek() {
+						Some(b'}') => return Err(not_found()),
+						Some(b',') => {
+							parser.pos += 1;
+							continue;
+						},
+						_ => (),
+					}
+
+					let key = parser.string()?;
+					parser.skip_whitespace();
+					parser.expect(b':')?;
+					if key == segment {
+						break;
+					}
+					parser.skip_value()?;
+				}
+			},
+
+			Some(b'[') => {
+				let index: usize = segment.parse().map_err(|_| format!("JSON output {parent:?} is an array, but {segment:?} is not an index"))?;
+				parser.pos += 1;
+				for _ in 0..index {
+					parser.skip_whitespace();
+					if parser.peek() == Some(b']') {
+						return Err(not_found());
+					}
+					parser.skip_value()?;
+					parser.skip_whitespace();
+					parser.expect(b',').map_err(|_| not_found())?;
+				}
+				parser.skip_whitespace();
+				if parser.peek() == Some(b']') {
+					return Err(not_found());
+				}
+			},
+
+			_ => return Err(format!("JSON output {parent:?} is not an object or array")),
+		}
+	}
+
+	parser.skip_whitespace();
+	let start = parser.pos;
+	match parser.peek() {
+		Some(b'"') => {
+			let value = parser.string()?;
+			value.trim().parse().map_err(|_| format!("JSON output {path:?} is the string {value:?}, which is not a number"))
+		},
+		Some(b't') if parser.json[start..].starts_with(b"true") => Ok(1.),
+		Some(b'f') if parser.json[start..].starts_with(b"false") => Ok(0.),
+		Some(b'-' | b'0'..=b'9') => {
+			parser.skip_value()?;
+			let value = str::from_utf8(&parser.json[start..parser.pos]).map_err(|err| err.to_string())?;
+			value.parse().map_err(|_| format!("JSON output {path:?} is not a valid number"))
+		},
+		_ => Err(format!("JSON output {path:?} is not a number")),
+	}
+}
+
+struct JsonParser<'a> {
+	json: &'a [u8],
+	pos: usize,
+}
+
+impl JsonParser<'_> {
+	fn peek(&self) -> Option<u8> {
+		self.json.get(self.pos).copied()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+			self.pos += 1;
+		}
+	}
+
+	fn expect(&mut self, expected: u8) -> Result<(), String> {
+		if self.peek() == Some(expected) {
+			self.pos += 1;
+			Ok(())
+		}
+		else {
+			Err(format!("invalid JSON output: expected {:?} at byte {}", char::from(expected), self.pos))
+		}
+	}
+
+	fn string(&mut self) -> Result<String, String> {
+		self.expect(b'"')?;
+
+		let mut result = vec![];
+		loop {
+			let b = self.peek().ok_or("invalid JSON output: unterminated string")?;
+			self.pos += 1;
+			match b {
+				b'"' => break,
+				b'\\' => {
+					let escaped = self.peek().ok_or("invalid JSON output: unterminated string")?;
+					self.pos += 1;
+					let unescaped = match escaped {
+						b'b' => b'\x08',
+						b'f' => b'\x0C',
+						b'n' => b'\n',
+						b'r' => b'\r',
+						b't' => b'\t',
+						b'u' => {
+							let hex = self.json.get(self.pos..(self.pos + 4)).ok_or("invalid JSON output: truncated escape")?;
+							self.pos += 4;
+							let c = str::from_utf8(hex).ok().and_then(|hex| u32::from_str_radix(hex, 16).ok()).ok_or("invalid JSON output: invalid escape")?;
+							// Surrogate pairs aren't combined. Keys and numbers don't need them.
+							let c = char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+							result.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+							continue;
+						},
+						b => b,
+					};
+					result.push(unescaped);
+				},
+				b => result.push(b),
+			}
+		}
+
+		String::from_utf8(result).map_err(|err| err.to_string())
+	}
+
+	fn skip_value(&mut self) -> Result<(), String> {
+		self.skip_whitespace();
+		match self.peek() {
+			Some(b'"') => {
+				self.string()?;
+			},
+
+			Some(open @ (b'{' | b'[')) => {
+				let close = if open == b'{' { b'}' } else { b']' };
+				self.pos += 1;
+				loop {
+					self.skip_whitespace();
+					match self.peek() {
+						Some(b) if b == close => {
+							self.pos += 1;
+							break;
+						},
+						Some(b',' | b':') => self.pos += 1,
+						Some(_) => self.skip_value()?,
+						None => return Err("invalid JSON output: unterminated object or array".to_owned()),
+					}
+				}
+			},
+
+			Some(_) => {
+				let start = self.pos;
+				while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z')) {
+					self.pos += 1;
+				}
+				if self.pos == start {
+					return Err(format!("invalid JSON output: unexpected character at byte {start}"));
+				}
+			},
+
+			None => return Err("invalid JSON output: unexpected end".to_owned()),
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn parse() {
+		let regex = regex::bytes::Regex::new(r"^temperature +: ([0-9]+) C").unwrap();
+		let output = b"Smart Log for NVME device:nvme0 namespace-id:ffffffff\ncritical_warning : 0\ntemperature : 35 C (308 Kelvin)\n";
+		for (parse, stdout, expected) in [
+			(crate::config::CommandOutput::Number, &b" 42.5\n"[..], 42.5),
+			(crate::config::CommandOutput::Regex(regex), output, 35.),
+			(crate::config::CommandOutput::Key("temperature".to_owned()), output, 35.),
+			(crate::config::CommandOutput::Key("power".to_owned()), b"voltage=12.1\npower=3.5W\npower = 4\n", 3.5),
+		] {
+			let actual = super::parse(&parse, stdout).unwrap();
+			assert!((actual - expected).abs() < f64::EPSILON, "{parse:?} {actual}");
+		}
+
+		let err = super::parse(&crate::config::CommandOutput::Key("humidity".to_owned()), output).unwrap_err().to_string();
+		assert_eq!(err, r#"no line of the output has the key "humidity""#);
+	}
+
+	#[test]
+	fn json_value() {
+		let json = r#"{
+			"name": "nvme0",
+			"escaped \"key\"": [1, 2],
+			"sensors": [{ "temp": 308, "label": "Composite" }, { "temp": 3.1e2, "label": "Sensor 1", "nested": { "x": [[], {}] } }],
+			"critical": false,
+			"power": " 4.5 "
+		}"#;
+		for (path, expected) in [
+			("sensors.0.temp", 308.),
+			("sensors.1.temp", 310.),
+			("critical", 0.),
+			("power", 4.5),
+			("escaped \"key\".1", 2.),
+		] {
+			let actual = super::json_value(json, path).unwrap();
+			assert!((actual - expected).abs() < f64::EPSILON, "{path:?} {actual}");
+		}
+		assert!((super::json_value(" 7 ", "").unwrap() - 7.).abs() < f64::EPSILON);
+
+		for (path, expected) in [
+			("sensors.2.temp", r#"JSON output has no "sensors.2""#),
+			("sensors.first", r#"JSON output "sensors" is an array, but "first" is not an index"#),
+			("name", r#"JSON output "name" is the string "nvme0", which is not a number"#),
+			("name.x", r#"JSON output "name" is not an object or array"#),
+			("fans", r#"JSON output has no "fans""#),
+		] {
+			assert_eq!(super::json_value(json, path).unwrap_err(), expected);
+		}
+	}
+
+	#[test]
+	fn run() {
+		let sensor = |command: &str, timeout_ms| crate::config::CommandSensor {
+			command: vec!["sh".to_owned(), "-c".to_owned(), command.to_owned()],
+			interval: std::time::Duration::from_secs(1),
+			timeout: std::time::Duration::from_millis(timeout_ms),
+			output: crate::config::CommandOutput::Number,
+			unit: String::new(),
+			name: "test".to_owned(),
+			calibration: Default::default(),
+			filter: Default::default(),
+		};
+
+		assert!((super::run(&sensor("echo 12", 5000)).unwrap() - 12.).abs() < f64::EPSILON);
+
+		let err = super::run(&sensor("echo oops >&2; exit 3", 5000)).unwrap_err().to_string();
+		assert_eq!(err, "command failed with exit status: 3: oops\n");
+
+		let start = std::time::Instant::now();
+		let err = super::run(&sensor("sleep 10", 100)).unwrap_err().to_string();
+		assert!(err.starts_with("command timed out"), "{err:?}");
+		assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+		// The processes that the command started are killed along with it.
+		let pid_path = std::env::temp_dir().join(format!("sensord-command-test-{}", std::process::id()));
+		let err = super::run(&sensor(&format!("sleep 10 & echo $! > {}; wait", pid_path.display()), 200)).unwrap_err().to_string();
+		assert!(err.starts_with("command timed out"), "{err:?}");
+		let pid = std::fs::read_to_string(&pid_path).unwrap();
+		std::fs::remove_file(&pid_path).unwrap();
+		let start = std::time::Instant::now();
+		// The killed process is gone once it's reaped, or a zombie if whatever it was reparented to doesn't reap it.
+		while let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim())) && !stat.contains(") Z ") {
+			assert!(start.elapsed() < std::time::Duration::from_secs(5), "{stat:?}");
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+	}
+
+	#[test]
+	fn spawn() {
+		let sensor = crate::config::CommandSensor {
+			command: vec!["echo".to_owned(), "5".to_owned()],
+			interval: std::time::Duration::from_millis(10),
+			timeout: std::time::Duration::from_secs(5),
+			output: crate::config::CommandOutput::Number,
+			unit: String::new(),
+			name: "test".to_owned(),
+			calibration: crate::config::Calibration { scale: 2., ..Default::default() },
+			filter: crate::config::Filter { valid_range: Some((0., 100.)), ..Default::default() },
+		};
+		let sensor = super::Sensor::spawn("test".to_owned(), &sensor);
+
+		let start = std::time::Instant::now();
+		while sensor.latest().0.is_none() {
+			assert!(start.elapsed() < std::time::Duration::from_secs(5));
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+		assert_eq!(sensor.latest(), (Some(10.), String::new()));
+	}
+}
@@ -1,30 +1,102 @@
 #[derive(Debug)]
 pub(crate) struct Config {
+	// The interval of every section that doesn't set its own, unless a client requested a different one. See `crate::clients::Clients::interval`
 	pub(crate) interval: std::time::Duration,
+	// The bounds of the intervals that clients can request.
+	pub(crate) min_interval: std::time::Duration,
+	pub(crate) max_interval: std::time::Duration,
+	// The intervals of `[cpus]` (which includes the container), `[interrupts]`, `[processes]` and `[vmstat]`, or `None` if they use the global interval.
+	// Sensor groups, networks and cgroups have their own intervals.
+	pub(crate) cpus_interval: Option<std::time::Duration>,
+	pub(crate) interrupts_interval: Option<std::time::Duration>,
+	pub(crate) processes_interval: Option<std::time::Duration>,
+	pub(crate) vmstat_interval: Option<std::time::Duration>,
 	pub(crate) cpus: Cpus,
+	// The resolved directories of the hwmon and power_supply devices, or `None` for optional devices that weren't found.
+	pub(crate) hwmon: std::collections::BTreeMap<String, Option<std::path::PathBuf>>,
+	pub(crate) power_supply: std::collections::BTreeMap<String, Option<std::path::PathBuf>>,
 	pub(crate) sensors: Vec<SensorGroup>,
+	// The thermal zone of every temp sensor that is a thermal zone, in order, or `None` for optional ones that weren't found.
+	// See `Files::resolve_devices`
+	pub(crate) thermal_zones: Vec<Option<u32>>,
 	pub(crate) networks: Vec<Network>,
+	pub(crate) interrupts: Interrupts,
+	pub(crate) processes: Processes,
+	pub(crate) cgroups: Vec<Cgroup>,
+	pub(crate) container_cgroup: Option<std::path::PathBuf>,
+	pub(crate) sources: Sources,
 }
 
 #[derive(Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 pub(crate) struct Cpus {
 	#[serde(default)]
 	pub(crate) use_sysfs: bool,
+	#[serde(default)]
+	pub(crate) container: bool,
+	interval: Option<Interval>,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Interrupts {
+	#[serde(default)]
+	pub(crate) top: usize,
+	interval: Option<Interval>,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Processes {
+	#[serde(default)]
+	pub(crate) top: usize,
+	interval: Option<Interval>,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VmStat {
+	interval: Option<Interval>,
+}
+
+// The interval of a section that overrides the global `interval`, in seconds.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize)]
+#[serde(try_from = "f64")]
+struct Interval(std::time::Duration);
+
+impl TryFrom<f64> for Interval {
+	type Error = String;
+
+	fn try_from(secs: f64) -> Result<Self, Self::Error> {
+		match std::time::Duration::try_from_secs_f64(secs) {
+			Ok(duration) if !duration.is_zero() => Ok(Interval(duration)),
+			_ => Err("`interval` must be a positive number of seconds".to_owned()),
+		}
+	}
 }
 
 #[derive(Debug)]
 pub(crate) struct SensorGroup {
 	pub(crate) name: String,
+	pub(crate) interval: Option<std::time::Duration>,
 	pub(crate) temps: Vec<TempSensor>,
 	pub(crate) fans: Vec<FanSensor>,
 	pub(crate) bats: Vec<BatSensor>,
+	pub(crate) cooling: Vec<CoolingSensor>,
+	pub(crate) files: Vec<FileSensor>,
+	pub(crate) commands: Vec<CommandSensor>,
+	pub(crate) derived: Vec<DerivedSensor>,
 }
 
 #[derive(Debug)]
 pub(crate) struct TempSensor {
 	pub(crate) path: Option<std::path::PathBuf>,
-	pub(crate) offset: f64,
+	pub(crate) calibration: Calibration,
+	pub(crate) filter: Filter,
 	pub(crate) name: Option<String>,
+	// The directory of the thermal zone, for sensors that are thermal zones. Their trip points, policy and mode can be changed at runtime,
+	// eg by thermald, so they're read along with the temp. See `crate::hwmon::parse_thermal_zone`
+	pub(crate) thermal_zone: Option<std::path::PathBuf>,
 }
 
 #[derive(Debug)]
@@ -32,213 +104,752 @@ pub(crate) struct FanSensor {
 	pub(crate) fan_path: Option<std::path::PathBuf>,
 	pub(crate) pwm_path: Option<std::path::PathBuf>,
 	pub(crate) name: Option<String>,
+	// Applies to the fan speed, not the PWM duty cycle
+	pub(crate) calibration: Calibration,
+	pub(crate) filter: Filter,
 }
 
 #[derive(Debug)]
 pub(crate) struct BatSensor {
-	pub(crate) capacity_path: std::path::PathBuf,
-	pub(crate) status_path: std::path::PathBuf,
+	pub(crate) capacity_path: Option<std::path::PathBuf>,
+	pub(crate) status_path: Option<std::path::PathBuf>,
 	pub(crate) name: Option<String>,
+	pub(crate) calibration: Calibration,
+	pub(crate) filter: Filter,
+}
+
+#[derive(Debug)]
+pub(crate) struct CoolingSensor {
+	pub(crate) cur_state_path: Option<std::path::PathBuf>,
+	// The max state can change at runtime too, eg for cooling devices that are CPU frequency limits when CPUs are offlined.
+	pub(crate) max_state_path: Option<std::path::PathBuf>,
+	pub(crate) name: Option<String>,
+	pub(crate) filter: Filter,
+}
+
+// A value read from an arbitrary file. The file is read every sample.
+//
+// Without a regex the value is the first line of the file. With a regex, it's the first capture group of the first line that matches,
+// or the whole match if the regex has no capture groups.
+#[derive(Debug)]
+pub(crate) struct FileSensor {
+	pub(crate) path: std::path::PathBuf,
+	pub(crate) regex: Option<regex::bytes::Regex>,
+	// If set, the value is a counter and the rate at which it increases per second is published instead.
+	pub(crate) rate: bool,
+	pub(crate) unit: String,
+	pub(crate) name: String,
+	pub(crate) calibration: Calibration,
+	pub(crate) filter: Filter,
+}
+
+// A value parsed from the output of a command. The command is run on its own thread every `interval`. See `crate::command`
+#[derive(Clone, Debug)]
+pub(crate) struct CommandSensor {
+	// The program and its arguments. The command is not run through a shell.
+	pub(crate) command: Vec<String>,
+	pub(crate) interval: std::time::Duration,
+	// The command is killed if it hasn't exited after this long.
+	pub(crate) timeout: std::time::Duration,
+	pub(crate) output: CommandOutput,
+	pub(crate) unit: String,
+	pub(crate) name: String,
+	pub(crate) calibration: Calibration,
+	pub(crate) filter: Filter,
+}
+
+// How the value is parsed from the command's stdout.
+#[derive(Clone, Debug)]
+pub(crate) enum CommandOutput {
+	// The whole output is the value.
+	Number,
+	// The first capture group of the first line that matches, or the whole match if the regex has no capture groups.
+	Regex(regex::bytes::Regex),
+	// The number after `key=` or `key:` on the line that starts with the key. The number may be followed by a unit.
+	Key(String),
+	// The value at a path of object keys and array indices separated by `.`, eg `sensors.0.temp`
+	Json(String),
+}
+
+// A value computed from the published values of other sensors after every sample. See `crate::derived`
+#[derive(Debug)]
+pub(crate) struct DerivedSensor {
+	pub(crate) expression: crate::derived::Expression<crate::derived::SensorRef>,
+	pub(crate) unit: String,
+	pub(crate) name: String,
+	pub(crate) filter: Filter,
+}
+
+// Corrects a sensor's value to `value * scale + offset` before it's published, eg for fans that report half their actual speed.
+//
+// With `invert`, the value is negated before being scaled, so that eg a sensor that reports the distance to some maximum
+// can be turned into the actual value by also setting `offset` to that maximum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Calibration {
+	pub(crate) scale: f64,
+	pub(crate) offset: f64,
+	pub(crate) invert: bool,
+}
+
+impl Calibration {
+	fn new(scale: Option<f64>, offset: Option<f64>, invert: bool) -> Result<Self, String> {
+		let scale = scale.unwrap_or(1.);
+		let offset = offset.unwrap_or_default();
+		if !scale.is_finite() || !offset.is_finite() {
+			return Err("`scale` and `offset` must be finite numbers".to_owned());
+		}
+		Ok(Calibration { scale, offset, invert })
+	}
+
+	pub(crate) fn apply(self, value: f64) -> f64 {
+		let value = if self.invert { -value } else { value };
+		value * self.scale + self.offset
+	}
+
+	// Integer values are rounded, and saturate at the bounds of their type.
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	pub(crate) fn apply_u8(self, value: u8) -> u8 {
+		self.apply(value.into()).round() as u8
+	}
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	pub(crate) fn apply_u16(self, value: u16) -> u16 {
+		self.apply(value.into()).round() as u16
+	}
+}
+
+impl Default for Calibration {
+	fn default() -> Self {
+		Calibration { scale: 1., offset: 0., invert: false }
+	}
+}
+
+// Prints the settings that differ from the default, each preceded by a space, or nothing at all.
+impl std::fmt::Display for Calibration {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Calibration { scale, offset, invert } = *self;
+		if invert {
+			f.write_str(" inverted")?;
+		}
+		if (scale - 1.).abs() > f64::EPSILON {
+			write!(f, " scale {scale}")?;
+		}
+		if offset != 0. {
+			write!(f, " offset {offset:+}")?;
+		}
+		Ok(())
+	}
+}
+
+// Discards and smooths noisy readings of a sensor after it's calibrated, eg a chip that occasionally reports -128 °C
+// or a fan that reads 0 RPM for a single sample. See `crate::filter::State`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Filter {
+	// A reading outside this range is reported as an error instead of being published, and the sensor keeps its previous value.
+	pub(crate) valid_range: Option<(f64, f64)>,
+	pub(crate) smoothing: Smoothing,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Smoothing {
+	#[default]
+	None,
+	// An exponential moving average, where each reading has this weight and the previous average has the rest.
+	Ema(f64),
+	// The median of this many of the most recent valid readings.
+	Median(usize),
+}
+
+impl Filter {
+	fn new(valid_range: Option<[f64; 2]>, ema: Option<f64>, median: Option<usize>) -> Result<Self, String> {
+		let valid_range = match valid_range {
+			Some([min, max]) if min.is_nan() || max.is_nan() || min > max => return Err("`valid_range` must be `[min, max]` with `min` <= `max`".to_owned()),
+			valid_range => valid_range.map(|[min, max]| (min, max)),
+		};
+
+		let smoothing = match (ema, median) {
+			(None, None) => Smoothing::None,
+			(Some(ema), None) if ema > 0. && ema <= 1. => Smoothing::Ema(ema),
+			(Some(_), None) => return Err("`ema` must be greater than 0 and at most 1".to_owned()),
+			(None, Some(median)) if median > 0 => Smoothing::Median(median),
+			(None, Some(_)) => return Err("`median` must be at least 1".to_owned()),
+			(Some(_), Some(_)) => return Err("sensor can only have one of `ema` or `median`".to_owned()),
+		};
+
+		Ok(Filter { valid_range, smoothing })
+	}
+}
+
+// Prints the settings that are set, each preceded by a space, or nothing at all.
+impl std::fmt::Display for Filter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Filter { valid_range, smoothing } = *self;
+		if let Some((min, max)) = valid_range {
+			write!(f, " valid range [{min}, {max}]")?;
+		}
+		match smoothing {
+			Smoothing::None => Ok(()),
+			Smoothing::Ema(weight) => write!(f, " ema {weight}"),
+			Smoothing::Median(len) => write!(f, " median of {len}"),
+		}
+	}
 }
 
 #[derive(Debug)]
 pub(crate) struct Network {
 	pub(crate) name: String,
+	pub(crate) interval: Option<std::time::Duration>,
 	pub(crate) rx_path: std::path::PathBuf,
 	pub(crate) tx_path: std::path::PathBuf,
 }
 
-impl<'de> serde::Deserialize<'de> for Config {
-	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: serde::Deserializer<'de> {
-		let InnerConfig { interval, cpus, hwmon, power_supply, sensors, networks } = serde::Deserialize::deserialize(deserializer)?;
+#[derive(Debug)]
+pub(crate) struct Cgroup {
+	pub(crate) name: String,
+	pub(crate) interval: Option<std::time::Duration>,
+	pub(crate) path: std::path::PathBuf,
+}
 
-		let interval = interval.unwrap_or(1.);
-		#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-		let interval = std::time::Duration::from_millis((interval * 1000.) as u64);
+// Where each entry of the config came from, so that resolution errors can point to the entry,
+// and so that `--check-config` can report the file of each entry when drop-in files are used.
+#[derive(Debug, Default)]
+pub(crate) struct Sources {
+	pub(crate) files: Vec<std::path::PathBuf>,
+	pub(crate) hwmon: std::collections::BTreeMap<String, Location>,
+	pub(crate) power_supply: std::collections::BTreeMap<String, Location>,
+	pub(crate) sensors: Vec<SensorGroupSources>,
+	pub(crate) networks: Vec<Location>,
+	pub(crate) cgroups: Vec<Location>,
+	// The top-level settings that were set to something other than their default, like `interval` and `[cpus]`
+	settings: std::collections::BTreeMap<&'static str, std::path::PathBuf>,
+}
 
-		let hwmon_nodes: Result<Vec<_>, crate::Error> =
-			crate::std2::fs::read_dir("/sys/class/hwmon".as_ref())
-			.map_err(serde::de::Error::custom)?
-			.filter_map(|dir| {
-				let dir = match dir {
-					Ok(dir) => dir,
-					Err(err) => return Some(Err(err)),
-				};
-				let dir = dir.path();
+#[derive(Debug)]
+pub(crate) struct SensorGroupSources {
+	pub(crate) location: Location,
+	temps: Vec<Location>,
+	fans: Vec<Location>,
+	bats: Vec<Location>,
+	cooling: Vec<Location>,
+	files: Vec<Location>,
+	commands: Vec<Location>,
+	derived: Vec<Location>,
+}
 
-				let name_file = dir.join("name");
-				let Ok(mut name) = std::fs::read_to_string(name_file) else { return None; };
-				if name.pop() != Some('\n') {
-					return None;
-				}
+#[derive(Clone, Debug)]
+pub(crate) struct Location {
+	pub(crate) path: std::path::PathBuf,
+	line: usize,
+	column: usize,
+}
 
-				let dir = match crate::std2::fs::canonicalize(&dir) {
-					Ok(dir) => dir,
-					Err(err) => return Some(Err(err)),
-				};
+impl Location {
+	fn new(path: &std::path::Path, contents: &str, span: &std::ops::Range<usize>) -> Self {
+		let before = &contents[..span.start];
+		let line = before.matches('\n').count() + 1;
+		let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+		Location { path: path.to_owned(), line, column }
+	}
 
-				Some(Ok((name, dir)))
-			})
-			.collect();
-		let mut hwmon_nodes = hwmon_nodes.map_err(serde::de::Error::custom)?;
+	// An error about the entry at this location. The context is what the entry is, eg `[[sensor]] group "CPU"`.
+	fn error(&self, context: impl std::fmt::Display, message: impl std::fmt::Display) -> crate::Error {
+		crate::Error::Path(format!("line {}, column {}: {context}: {message}", self.line, self.column).into(), self.path.clone())
+	}
 
-		let hwmon: Result<std::collections::BTreeMap<_, _>, crate::Error> =
-			hwmon.into_iter()
-			.map(|(hwmon_name, hwmon)| {
-				let i = match hwmon {
-					Hwmon::Name(dev_name) =>
-						hwmon_nodes.iter()
-						.position(|(hwmon_node_name, _)| *hwmon_node_name == dev_name)
-						.ok_or_else(|| crate::Error::Other(format!("could not find hwmon named {dev_name}").into()))?,
-
-					Hwmon::Path(dev_path) =>
-						hwmon_nodes.iter()
-						.position(|(_, hwmon_node_dir)| hwmon_node_dir.starts_with(&dev_path))
-						.ok_or_else(|| crate::Error::Other(format!("could not find hwmon path under {}", dev_path.display()).into()))?,
-				};
-				let (_, hwmon_node_dir) = hwmon_nodes.swap_remove(i);
-				Ok((hwmon_name, hwmon_node_dir))
-			})
-			.collect();
-		let hwmon = hwmon.map_err(serde::de::Error::custom)?;
+	// Adds this location and the given context to an error that occurred while resolving the entry at this location.
+	fn with_context<T>(&self, context: impl std::fmt::Display, f: impl FnOnce() -> Result<T, crate::Error>) -> Result<T, crate::Error> {
+		f().map_err(|err| self.error(context, err.to_string().trim_end()))
+	}
 
-		let power_supply: Result<std::collections::BTreeMap<_, _>, crate::Error> =
-			power_supply.into_iter()
-			.map(|(power_supply_name, dev_name)| {
-				let dir = std::path::Path::new("/sys/class/power_supply").join(dev_name);
-				let dir = crate::std2::fs::canonicalize(&dir)?;
-				Ok((power_supply_name, dir))
+	fn warn(&self, context: impl std::fmt::Display, message: impl std::fmt::Display) {
+		eprintln!("warning: {self}: {context}: {message}");
+	}
+}
+
+impl std::fmt::Display for Location {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}:{}:{}", self.path.display(), self.line, self.column)
+	}
+}
+
+// Parsed from each file alongside `InnerConfig` only to find where each entry is in the file.
+#[derive(serde::Deserialize)]
+struct Spans {
+	#[serde(default)]
+	hwmon: std::collections::BTreeMap<String, toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	power_supply: std::collections::BTreeMap<String, toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default, rename = "sensor")]
+	sensors: Vec<toml::Spanned<SensorGroupSpans>>,
+	#[serde(default)]
+	networks: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	cgroups: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+}
+
+#[derive(serde::Deserialize)]
+struct SensorGroupSpans {
+	#[serde(default)]
+	temps: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	fans: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	bats: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	cooling: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	files: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	commands: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+	#[serde(default)]
+	derived: Vec<toml::Spanned<serde::de::IgnoredAny>>,
+}
+
+// The contents of the main config file followed by the drop-in files in the `config.d` directory next to it, in lexical order.
+//
+// The files are kept around so that the config can be re-resolved when devices are added or removed.
+pub(crate) struct Files(Vec<(std::path::PathBuf, String)>);
+
+impl Files {
+	pub(crate) fn read(path: &std::path::Path) -> Result<Self, crate::Error> {
+		let mut paths = vec![path.to_owned()];
+
+		let drop_in_dir = path.parent().unwrap_or_else(|| "/".as_ref()).join("config.d");
+		if drop_in_dir.is_dir() {
+			let mut drop_in_paths = vec![];
+			for entry in crate::std2::fs::read_dir(&drop_in_dir)? {
+				let path = entry?.path();
+				if path.extension().is_some_and(|extension| extension == "toml") {
+					drop_in_paths.push(path);
+				}
+			}
+			drop_in_paths.sort();
+			paths.extend(drop_in_paths);
+		}
+
+		let files: Result<_, crate::Error> =
+			paths.into_iter()
+			.map(|path| {
+				let contents = crate::Error::with_path_context(&path, |path| Ok(std::fs::read_to_string(path)?))?;
+				Ok((path, contents))
 			})
 			.collect();
-		let power_supply = power_supply.map_err(serde::de::Error::custom)?;
-
-		let sensors: Result<_, crate::Error> =
-			sensors.into_iter()
-			.map(|InnerSensorGroup { name, temps, fans, bats }| {
-				let temps: Result<_, crate::Error> =
-					temps.into_iter()
-					.map(|InnerTempSensor { spec, offset, name }| match spec {
-						InnerTempSensorSpec::Hwmon { hwmon: sensor_hwmon, num_or_label } => {
-							let hwmon = hwmon.get(&sensor_hwmon).ok_or_else(|| crate::Error::Other(format!("hwmon {sensor_hwmon:?} is not defined").into()))?;
+		Ok(Files(files?))
+	}
 
-							let num = match num_or_label {
-								HwmonNumOrLabel::Num(num) => Some(num),
+	pub(crate) fn resolve(&self) -> Result<Config, crate::Error> {
+		let (config, sources) = self.merge()?;
+		Config::resolve(config, sources)
+	}
 
-								HwmonNumOrLabel::Label(expected_label) => {
-									let mut num = None;
+	// Resolves only the parts of the config that refer to hwmon, power_supply and thermal devices, for when devices are added or removed.
+	// In particular the cgroups are not resolved again, since that searches the whole cgroup hierarchy.
+	//
+	// If `thermal_zones` is set, ie no thermal devices changed, the thermal zones of temp sensors are not resolved again either,
+	// and the given ones from the previous `Config::thermal_zones` or `Devices::thermal_zones` are used instead.
+	pub(crate) fn resolve_devices(&self, thermal_zones: Option<&[Option<u32>]>) -> Result<Devices, crate::Error> {
+		let (InnerConfig { interval, hwmon, power_supply, sensors, .. }, sources) = self.merge()?;
+		Devices::resolve(hwmon, power_supply, sensors, interval_or_default(interval), &sources, thermal_zones)
+	}
 
-									for entry in crate::std2::fs::read_dir(hwmon)? {
-										let entry = entry?.path();
+	// Resolves only the network interfaces, for when they are added, removed or renamed.
+	pub(crate) fn resolve_networks(&self) -> Result<Vec<Network>, crate::Error> {
+		let (InnerConfig { networks, .. }, _) = self.merge()?;
+		Ok(resolve_networks(networks))
+	}
 
-										let Some(entry_file_name) = entry.file_name().and_then(std::ffi::OsStr::to_str) else { continue; };
-										if !entry_file_name.starts_with("temp") || !entry_file_name.ends_with("_label") {
-											continue;
-										}
+	// Parses a single config file without resolving it, eg to check that a config generated by `crate::discover` matches the schema.
+	#[cfg(test)]
+	pub(crate) fn parse(path: &std::path::Path, contents: &str) -> Result<(), crate::Error> {
+		Files(vec![(path.to_owned(), contents.to_owned())]).merge()?;
+		Ok(())
+	}
 
-										let Ok(mut actual_label) = std::fs::read_to_string(&entry) else { continue; };
-										if actual_label.pop() != Some('\n') || actual_label != expected_label {
-											continue;
-										}
+	// The `hwmon` and `power_supply` maps of all files are merged, and their `[[sensor]]` groups, `networks` and `cgroups` are appended
+	// in the order of the files. The other settings can only be set in one file.
+	fn merge(&self) -> Result<(InnerConfig, Sources), crate::Error> {
+		let mut merged: InnerConfig = Default::default();
+		let mut sources: Sources = Default::default();
 
-										let actual_num = &entry_file_name[("temp".len())..(entry_file_name.len() - "_label".len())];
-										let Ok(actual_num) = actual_num.parse() else { continue; };
+		for (path, contents) in &self.0 {
+			let InnerConfig { interval, min_interval, max_interval, cpus, hwmon, power_supply, sensors, networks, interrupts, processes, vmstat, cgroups } =
+				crate::Error::with_path_context(path, |_| Ok(toml::from_str(contents)?))?;
+			let spans: Spans = crate::Error::with_path_context(path, |_| Ok(toml::from_str(contents)?))?;
 
-										num = Some(actual_num);
-										break;
-									}
+			let location = |span: std::ops::Range<usize>| Location::new(path, contents, &span);
+			let locations = |spans: &[toml::Spanned<_>]| spans.iter().map(|span| location(span.span())).collect::<Vec<_>>();
 
-									num
-								},
-							};
+			sources.files.push(path.clone());
 
-							let name = name.or_else(|| num.and_then(|num| {
-								let label_path = hwmon.join(format!("temp{num}_label"));
-								let mut label = std::fs::read_to_string(label_path).ok()?;
-								(label.pop() == Some('\n')).then_some(label)
-							}));
+			for (name, hwmon) in hwmon {
+				let location = location(spans.hwmon[&name].span());
+				if let Some(previous_location) = sources.hwmon.get(&name) {
+					return Err(location.error(format_args!("hwmon {name:?}"), format_args!("already defined at {previous_location}")));
+				}
+				sources.hwmon.insert(name.clone(), location);
+				merged.hwmon.insert(name, hwmon);
+			}
+
+			for (name, power_supply) in power_supply {
+				let location = location(spans.power_supply[&name].span());
+				if let Some(previous_location) = sources.power_supply.get(&name) {
+					return Err(location.error(format_args!("power_supply {name:?}"), format_args!("already defined at {previous_location}")));
+				}
+				sources.power_supply.insert(name.clone(), location);
+				merged.power_supply.insert(name, power_supply);
+			}
+
+			sources.sensors.extend(
+				spans.sensors.iter()
+				.map(|sensor_group| SensorGroupSources {
+					location: location(sensor_group.span()),
+					temps: locations(&sensor_group.get_ref().temps),
+					fans: locations(&sensor_group.get_ref().fans),
+					bats: locations(&sensor_group.get_ref().bats),
+					cooling: locations(&sensor_group.get_ref().cooling),
+					files: locations(&sensor_group.get_ref().files),
+					commands: locations(&sensor_group.get_ref().commands),
+					derived: locations(&sensor_group.get_ref().derived),
+				}));
+			merged.sensors.extend(sensors);
+
+			sources.networks.extend(locations(&spans.networks));
+			merged.networks.extend(networks);
+
+			sources.cgroups.extend(locations(&spans.cgroups));
+			merged.cgroups.extend(cgroups);
+
+			let conflict = |message: String| crate::Error::Path(message.into(), path.clone());
+
+			let mut set = |setting: &'static str, is_set: bool| {
+				if !is_set {
+					return Ok(());
+				}
+				if let Some(previous_path) = sources.settings.get(setting) {
+					return Err(conflict(format!("{setting} is already set in {}", previous_path.display())));
+				}
+				sources.settings.insert(setting, path.clone());
+				Ok(())
+			};
+			set("interval", interval.is_some())?;
+			set("min_interval", min_interval.is_some())?;
+			set("max_interval", max_interval.is_some())?;
+			set("[cpus]", cpus != Default::default())?;
+			set("[interrupts]", interrupts != Default::default())?;
+			set("[processes]", processes != Default::default())?;
+			set("[vmstat]", vmstat != Default::default())?;
+			if let Some(interval) = interval {
+				merged.interval = Some(interval);
+			}
+			if let Some(min_interval) = min_interval {
+				merged.min_interval = Some(min_interval);
+			}
+			if let Some(max_interval) = max_interval {
+				merged.max_interval = Some(max_interval);
+			}
+			if cpus != Default::default() {
+				merged.cpus = cpus;
+			}
+			if interrupts != Default::default() {
+				merged.interrupts = interrupts;
+			}
+			if processes != Default::default() {
+				merged.processes = processes;
+			}
+			if vmstat != Default::default() {
+				merged.vmstat = vmstat;
+			}
+		}
+
+		Ok((merged, sources))
+	}
+}
 
-							Ok(TempSensor {
-								path: num.map(|num| hwmon.join(format!("temp{num}_input"))),
-								offset: offset.unwrap_or_default(),
-								name,
-							})
-						},
+impl Config {
+	fn resolve(config: InnerConfig, sources: Sources) -> Result<Self, crate::Error> {
+		let InnerConfig { interval, min_interval, max_interval, cpus, hwmon, power_supply, sensors, networks, interrupts, processes, vmstat, cgroups } = config;
+
+		let interval = interval_or_default(interval);
+
+		let Devices { hwmon, power_supply, sensors, thermal_zones } = Devices::resolve(hwmon, power_supply, sensors, interval, &sources, None)?;
+		let networks = resolve_networks(networks);
+
+		let cgroups: Result<Vec<_>, crate::Error> =
+			cgroups.into_iter()
+			.zip(&sources.cgroups)
+			.map(|(InnerListEntry { name: cgroup, interval: cgroup_interval }, location)| {
+				let path = location.with_context(format_args!("cgroup {cgroup:?}"), || crate::cgroup::resolve(&cgroup))?;
+				let path = path.unwrap_or_else(|| {
+					// The unit is not running right now. Assume it's a system service that will be started later.
+					let path = crate::cgroup::system_service_path(&cgroup);
+					location.warn(format_args!("cgroup {cgroup:?}"), format_args!("could not find the cgroup, assuming it is {}", path.display()));
+					path
+				});
+				Ok(Cgroup {
+					name: cgroup,
+					interval: cgroup_interval.map(|Interval(interval)| interval),
+					path,
+				})
+			})
+			.collect();
+		let cgroups = cgroups?;
 
-						InnerTempSensorSpec::Thermal { thermal_zone } => {
-							let mut thermal = std::path::Path::new("/sys/class/thermal").to_owned();
-							thermal.push(format!("thermal_zone{thermal_zone}"));
+		let container_cgroup = if cpus.container { Some(crate::cgroup::own()?) } else { None };
 
-							let name = name.or_else(|| {
-								let label_path = thermal.join("type");
-								std::fs::read_to_string(label_path).ok().map(|label| label.trim().to_owned())
-							});
+		let min_interval = min_interval.map_or(DEFAULT_MIN_INTERVAL, |Interval(interval)| interval);
+		let max_interval = max_interval.map_or(DEFAULT_MAX_INTERVAL, |Interval(interval)| interval);
+		if min_interval > max_interval {
+			return Err(crate::Error::Other("`min_interval` must not be greater than `max_interval`".into()));
+		}
 
-							thermal.push("temp");
+		Ok(Config {
+			interval,
+			min_interval,
+			max_interval,
+			cpus_interval: cpus.interval.map(|Interval(interval)| interval),
+			interrupts_interval: interrupts.interval.map(|Interval(interval)| interval),
+			processes_interval: processes.interval.map(|Interval(interval)| interval),
+			vmstat_interval: vmstat.interval.map(|Interval(interval)| interval),
+			cpus,
+			hwmon,
+			power_supply,
+			sensors,
+			thermal_zones,
+			networks,
+			interrupts,
+			processes,
+			cgroups,
+			container_cgroup,
+			sources,
+		})
+	}
+}
 
-							Ok(TempSensor {
-								path: Some(thermal),
-								offset: offset.unwrap_or_default(),
-								name,
-							})
-						},
-					})
-					.collect();
-				let temps = temps?;
+// The parts of the config that refer to hwmon, power_supply and thermal devices, which are re-resolved when devices are added or removed.
+// See `Files::resolve_devices`
+pub(crate) struct Devices {
+	pub(crate) hwmon: std::collections::BTreeMap<String, Option<std::path::PathBuf>>,
+	pub(crate) power_supply: std::collections::BTreeMap<String, Option<std::path::PathBuf>>,
+	pub(crate) sensors: Vec<SensorGroup>,
+	pub(crate) thermal_zones: Vec<Option<u32>>,
+}
 
-				let fans: Result<_, crate::Error> =
-					fans.into_iter()
-					.map(|InnerFanSensor { hwmon: sensor_hwmon, num_or_label, name }| {
-						let hwmon = hwmon.get(&sensor_hwmon).ok_or_else(|| crate::Error::Other(format!("hwmon {sensor_hwmon:?} is not defined").into()))?;
+impl Devices {
+	fn resolve(
+		hwmon: std::collections::BTreeMap<String, Hwmon>,
+		power_supply: std::collections::BTreeMap<String, InnerPowerSupply>,
+		sensors: Vec<InnerSensorGroup>,
+		interval: std::time::Duration,
+		sources: &Sources,
+		previous_thermal_zones: Option<&[Option<u32>]>,
+	) -> Result<Self, crate::Error> {
+		// A kernel without hwmon support has no `/sys/class/hwmon` at all, which is the same as having no hwmon devices.
+		let hwmon_class_dir = std::path::Path::new("/sys/class/hwmon");
+		let hwmon_nodes: Result<Vec<_>, crate::Error> =
+			if hwmon.is_empty() || !hwmon_class_dir.exists() {
+				Ok(vec![])
+			}
+			else {
+				crate::std2::fs::read_dir(hwmon_class_dir)
+				?
+				.filter_map(|dir| {
+					let dir = match dir {
+						Ok(dir) => dir,
+						Err(err) => return Some(Err(err)),
+					};
+					let dir = dir.path();
+
+					let num: u32 = dir.file_name()?.to_str()?.strip_prefix("hwmon")?.parse().ok()?;
+
+					let name_file = dir.join("name");
+					let Ok(mut name) = std::fs::read_to_string(name_file) else { return None; };
+					if name.pop() != Some('\n') {
+						return None;
+					}
+
+					let dir = match crate::std2::fs::canonicalize(&dir) {
+						Ok(dir) => dir,
+						Err(err) => return Some(Err(err)),
+					};
+
+					Some(Ok((num, name, dir)))
+				})
+				.collect()
+			};
+		let hwmon_nodes = hwmon_nodes?;
 
-						let num = match num_or_label {
-							HwmonNumOrLabel::Num(num) => Some(num),
+		let hwmon: Result<std::collections::BTreeMap<_, _>, crate::Error> =
+			match_hwmon_nodes(hwmon, hwmon_nodes, "/sys/devices".as_ref())
+			.into_iter()
+			.map(|(hwmon_name, hwmon, hwmon_node_dir)| {
+				let location = &sources.hwmon[&hwmon_name];
+				if hwmon.num_properties() == 0 {
+					return Err(location.error(format_args!("hwmon {hwmon_name:?}"), "does not specify any property to match the device by"));
+				}
 
-							HwmonNumOrLabel::Label(expected_label) => {
-								let mut num = None;
+				let hwmon_node_dir = hwmon_node_dir.ok_or_else(|| location.error(format_args!("hwmon {hwmon_name:?}"), format_args!("could not find unused hwmon device matching {hwmon}")));
+				let hwmon_node_dir = optional(hwmon_node_dir, hwmon.optional)?;
+				Ok((hwmon_name, hwmon_node_dir))
+			})
+			.collect();
+		let hwmon = hwmon?;
 
-								for entry in crate::std2::fs::read_dir(hwmon)? {
-									let entry = entry?.path();
+		let power_supply: Result<std::collections::BTreeMap<_, _>, crate::Error> =
+			power_supply.into_iter()
+			.map(|(power_supply_name, power_supply)| {
+				let (dev_name, is_optional) = match power_supply {
+					InnerPowerSupply::Name(dev_name) => (dev_name, false),
+					InnerPowerSupply::Spec { dev_name, optional } => (dev_name, optional),
+				};
+				let dir = std::path::Path::new("/sys/class/power_supply").join(dev_name);
+				let dir = sources.power_supply[&power_supply_name].with_context(
+					format_args!("power_supply {power_supply_name:?}"),
+					|| crate::std2::fs::canonicalize(&dir),
+				);
+				let dir = optional(dir, is_optional)?;
+				Ok((power_supply_name, dir))
+			})
+			.collect();
+		let power_supply = power_supply?;
+
+		// Thermal zones that are looked up by type are mapped to distinct devices, like hwmon devices looked up by name.
+		// See `resolve_thermal_types`
+		let mut thermal_types = {
+			let temps = || sensors.iter().flat_map(|sensor_group| &sensor_group.temps).map(|sensor| &sensor.spec);
+			let lookups: Vec<_> =
+				temps()
+				.filter_map(|spec| match spec {
+					InnerTempSensorSpec::ThermalType { thermal_type, index } => Some((&**thermal_type, *index)),
+					_ => None,
+				})
+				.collect();
+			if lookups.is_empty() || previous_thermal_zones.is_some() {
+				vec![]
+			}
+			else {
+				let nums: Vec<_> =
+					temps()
+					.filter_map(|spec| match spec {
+						InnerTempSensorSpec::Thermal { thermal_zone } => Some(u32::from(*thermal_zone)),
+						_ => None,
+					})
+					.collect();
+				resolve_thermal_types(&thermal_zones()?, &nums, &lookups)
+			}
+		}.into_iter();
 
-									let Some(entry_file_name) = entry.file_name().and_then(std::ffi::OsStr::to_str) else { continue; };
-									if !entry_file_name.starts_with("fan") || !entry_file_name.ends_with("_label") {
-										continue;
-									}
+		// The thermal zone of every temp sensor that is a thermal zone, in order.
+		let mut previous_thermal_zones = previous_thermal_zones.map(|thermal_zones| thermal_zones.iter().copied());
+		let mut thermal_zones = vec![];
 
-									let Ok(mut actual_label) = std::fs::read_to_string(&entry) else { continue; };
-									if actual_label.pop() != Some('\n') || actual_label != expected_label {
-										continue;
-									}
+		let sensors: Result<Vec<_>, crate::Error> =
+			sensors.into_iter()
+			.zip(&sources.sensors)
+			.map(|(InnerSensorGroup { name: group_name, interval: group_interval, temps, fans, bats, cooling, files, commands, derived }, group_sources)| {
+				let context = || format!("[[sensor]] group {group_name:?}");
 
-									let actual_num = &entry_file_name[("fan".len())..(entry_file_name.len() - "_label".len())];
-									let Ok(actual_num) = actual_num.parse() else { continue; };
+				let temps: Result<Vec<Vec<_>>, crate::Error> =
+					temps.into_iter()
+					.zip(&group_sources.temps)
+					.map(|(InnerTempSensor { spec, calibration, filter, name, optional: is_optional }, location)| location.with_context(context(), || match spec {
+						InnerTempSensorSpec::Hwmon { hwmon: sensor_hwmon, num_or_label } => {
+							let hwmon = hwmon.get(&sensor_hwmon).ok_or_else(|| crate::Error::Other(format!("hwmon {sensor_hwmon:?} is not defined").into()))?;
+							let hwmon = hwmon.as_deref();
+
+							let channels = hwmon_channels(hwmon, "temp", &num_or_label)?;
+							warn_missing_channels(location, context(), &sensor_hwmon, hwmon, "temp", &num_or_label, &channels);
+
+							Ok(
+								channels.into_iter()
+								.map(|HwmonChannel { num, label }| TempSensor {
+									path: hwmon.zip(num).map(|(hwmon, num)| hwmon.join(format!("temp{num}_input"))),
+									calibration,
+									filter,
+									name: name.clone().or(label),
+									thermal_zone: None,
+								})
+								.collect(),
+							)
+						},
 
-									num = Some(actual_num);
-									break;
+						InnerTempSensorSpec::Thermal { thermal_zone } => {
+							let thermal_zone_name = format!("thermal_zone{thermal_zone}");
+							let thermal_zone =
+								if let Some(previous_thermal_zones) = &mut previous_thermal_zones {
+									previous_thermal_zones.next().flatten()
 								}
+								else {
+									let thermal_zone =
+										if std::path::Path::new("/sys/class/thermal").join(&thermal_zone_name).exists() {
+											Ok(thermal_zone.into())
+										}
+										else {
+											Err(crate::Error::Other(format!("thermal zone {thermal_zone} does not exist").into()))
+										};
+									optional(thermal_zone, is_optional)?
+								};
+							thermal_zones.push(thermal_zone);
+							let name = if thermal_zone.is_some() { name } else { name.or(Some(thermal_zone_name)) };
+							Ok(vec![thermal_temp_sensor(thermal_zone, calibration, filter, name)])
+						},
 
-								num
-							},
-						};
-
-						let name = name.or_else(|| num.and_then(|num| {
-							let label_path = hwmon.join(format!("fan{num}_label"));
-							std::fs::read_to_string(label_path).ok().map(|label| label.trim().to_owned())
-						}));
+						InnerTempSensorSpec::ThermalType { thermal_type, index: _ } => {
+							let thermal_zone =
+								if let Some(previous_thermal_zones) = &mut previous_thermal_zones {
+									previous_thermal_zones.next().flatten()
+								}
+								else {
+									let thermal_zone = thermal_types.next().unwrap_or_else(|| Err("thermal zone was not resolved".to_owned()));
+									optional(thermal_zone.map_err(|err| crate::Error::Other(err.into())), is_optional)?
+								};
+							thermal_zones.push(thermal_zone);
+							Ok(vec![thermal_temp_sensor(thermal_zone, calibration, filter, name.or(Some(thermal_type)))])
+						},
+					}))
+					.collect();
+				let temps: Vec<_> = temps?.into_iter().flatten().collect();
 
-						Ok(FanSensor {
-							fan_path: num.map(|num| hwmon.join(format!("fan{num}_input"))),
-							pwm_path: num.map(|num| hwmon.join(format!("pwm{num}"))),
-							name,
-						})
-					})
+				let fans: Result<Vec<Vec<_>>, crate::Error> =
+					fans.into_iter()
+					.zip(&group_sources.fans)
+					.map(|(InnerFanSensor { hwmon: sensor_hwmon, num_or_label, name, calibration, filter }, location)| location.with_context(context(), || {
+						let hwmon = hwmon.get(&sensor_hwmon).ok_or_else(|| crate::Error::Other(format!("hwmon {sensor_hwmon:?} is not defined").into()))?;
+						let hwmon = hwmon.as_deref();
+
+						let channels = hwmon_channels(hwmon, "fan", &num_or_label)?;
+						warn_missing_channels(location, context(), &sensor_hwmon, hwmon, "fan", &num_or_label, &channels);
+
+						Ok(
+							channels.into_iter()
+							.map(|HwmonChannel { num, label }| FanSensor {
+								fan_path: hwmon.zip(num).map(|(hwmon, num)| hwmon.join(format!("fan{num}_input"))),
+								pwm_path: hwmon.zip(num).map(|(hwmon, num)| hwmon.join(format!("pwm{num}"))),
+								name: name.clone().or(label),
+								calibration,
+								filter,
+							})
+							.collect(),
+						)
+					}))
 					.collect();
-				let fans = fans?;
+				let fans: Vec<_> = fans?.into_iter().flatten().collect();
 
 				let bats: Result<_, crate::Error> =
 					bats.into_iter()
-					.map(|bat| match bat {
-						InnerBatSensor::Hwmon(sensor_hwmon) => {
+					.zip(&group_sources.bats)
+					.map(|(InnerBatSensor { device, calibration, filter }, location)| location.with_context(context(), || match device {
+						InnerBatSensorDevice::Hwmon(sensor_hwmon) => {
 							let hwmon = hwmon.get(&sensor_hwmon).ok_or_else(|| crate::Error::Other(format!("hwmon {sensor_hwmon:?} is not defined").into()))?;
+							let Some(hwmon) = hwmon else {
+								return Ok(BatSensor {
+									capacity_path: None,
+									status_path: None,
+									name: Some(sensor_hwmon),
+									calibration,
+									filter,
+								});
+							};
 
 							let mut device_path = hwmon.clone();
 							device_path.push("device");
@@ -257,16 +868,27 @@ impl<'de> serde::Deserialize<'de> for Config {
 							};
 
 							Ok(BatSensor {
-								capacity_path,
-								status_path,
+								capacity_path: Some(capacity_path),
+								status_path: Some(status_path),
 								name,
+								calibration,
+								filter,
 							})
 						},
 
-						InnerBatSensor::PowerSupply(sensor_power_supply) => {
+						InnerBatSensorDevice::PowerSupply(sensor_power_supply) => {
 							let power_supply =
 								power_supply.get(&sensor_power_supply)
 								.ok_or_else(|| crate::Error::Other(format!("power_supply {sensor_power_supply:?} is not defined").into()))?;
+							let Some(power_supply) = power_supply else {
+								return Ok(BatSensor {
+									capacity_path: None,
+									status_path: None,
+									name: Some(sensor_power_supply),
+									calibration,
+									filter,
+								});
+							};
 
 							let capacity_path = power_supply.join("capacity");
 
@@ -278,124 +900,1282 @@ impl<'de> serde::Deserialize<'de> for Config {
 								std::fs::read_to_string(name_path).ok().map(|name| name.trim().to_owned())
 							};
 
-							Ok(BatSensor {
-								capacity_path,
-								status_path,
-								name,
-							})
-						},
-					})
-					.collect();
-				let bats = bats?;
+							Ok(BatSensor {
+								capacity_path: Some(capacity_path),
+								status_path: Some(status_path),
+								name,
+								calibration,
+								filter,
+							})
+						},
+					}))
+					.collect();
+				let bats = bats?;
+
+				let cooling: Result<_, crate::Error> =
+					cooling.into_iter()
+					.zip(&group_sources.cooling)
+					.map(|(InnerCoolingSensor { cooling_device, name, optional: is_optional, filter }, location)| location.with_context(context(), || {
+						let mut cooling_device_path = std::path::Path::new("/sys/class/thermal").to_owned();
+						cooling_device_path.push(format!("cooling_device{cooling_device}"));
+
+						let name = name.or_else(|| {
+							let type_path = cooling_device_path.join("type");
+							std::fs::read_to_string(type_path).ok().map(|r#type| r#type.trim().to_owned())
+						});
+
+						// The max state is read here only to check that the cooling device exists.
+						let max_state_path = cooling_device_path.join("max_state");
+						let max_state = crate::Error::with_path_context(&max_state_path, |max_state_path| {
+							let max_state = std::fs::read_to_string(max_state_path)?;
+							Ok(max_state.trim().parse::<u32>()?)
+						});
+						let max_state = optional(max_state, is_optional)?;
+
+						Ok(CoolingSensor {
+							cur_state_path: max_state.is_some().then(|| cooling_device_path.join("cur_state")),
+							max_state_path: max_state.is_some().then_some(max_state_path),
+							name: name.or_else(|| Some(format!("cooling_device{cooling_device}"))),
+							filter,
+						})
+					}))
+					.collect();
+				let cooling = cooling?;
+
+				let files: Result<_, crate::Error> =
+					files.into_iter()
+					.zip(&group_sources.files)
+					.map(|(InnerFileSensor { path, regex, rate, unit, name, calibration, filter }, location)| location.with_context(context(), || {
+						let regex =
+							regex.map(|regex| regex::bytes::Regex::new(&regex).map_err(|err| crate::Error::Other(format!("invalid regex: {err}").into())))
+							.transpose()?;
+
+						let name = name.unwrap_or_else(|| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned());
+
+						Ok(FileSensor {
+							path,
+							regex,
+							rate,
+							unit: unit.unwrap_or_default(),
+							name,
+							calibration,
+							filter,
+						})
+					}))
+					.collect();
+				let files = files?;
+
+				let commands: Result<_, crate::Error> =
+					commands.into_iter()
+					.zip(&group_sources.commands)
+					.map(|(InnerCommandSensor { command, interval: command_interval, timeout, output, unit, name, calibration, filter }, location)| location.with_context(context(), || {
+						let output = match output {
+							InnerCommandOutput::Number => CommandOutput::Number,
+							InnerCommandOutput::Regex(regex) =>
+								CommandOutput::Regex(regex::bytes::Regex::new(&regex).map_err(|err| crate::Error::Other(format!("invalid regex: {err}").into()))?),
+							InnerCommandOutput::Key(key) => CommandOutput::Key(key),
+							InnerCommandOutput::Json(path) => CommandOutput::Json(path),
+						};
+
+						let name = name.unwrap_or_else(|| {
+							let program = std::path::Path::new(&command[0]);
+							program.file_name().unwrap_or(program.as_os_str()).to_string_lossy().into_owned()
+						});
+
+						Ok(CommandSensor {
+							command,
+							interval: command_interval.map_or(interval, |Interval(interval)| interval),
+							timeout: timeout.map_or(DEFAULT_COMMAND_TIMEOUT, |Interval(timeout)| timeout),
+							output,
+							unit: unit.unwrap_or_default(),
+							name,
+							calibration,
+							filter,
+						})
+					}))
+					.collect();
+				let commands = commands?;
+
+				let sensor_group = SensorGroup {
+					name: group_name,
+					interval: group_interval.map(|Interval(interval)| interval),
+					temps,
+					fans,
+					bats,
+					cooling,
+					files,
+					commands,
+					derived: vec![],
+				};
+				Ok((sensor_group, derived))
+			})
+			.collect();
+		let (mut sensors, derived): (Vec<_>, Vec<_>) = sensors?.into_iter().unzip();
+
+		// Derived sensors are resolved after all other sensors, since they can refer to sensors of any group.
+		// Since they're resolved in order, they can only refer to derived sensors that are defined before them.
+		for (group_index, (derived, group_sources)) in derived.into_iter().zip(&sources.sensors).enumerate() {
+			let group_name = sensors[group_index].name.clone();
+			for (InnerDerivedSensor { expression, unit, name, filter }, location) in derived.into_iter().zip(&group_sources.derived) {
+				let expression = location.with_context(format_args!("[[sensor]] group {group_name:?}"), || {
+					expression.resolve(|reference| Ok(sensor_refs(&sensors, group_index, reference)))
+					.map_err(|err| crate::Error::Other(err.into()))
+				})?;
+				sensors[group_index].derived.push(DerivedSensor {
+					expression,
+					unit: unit.unwrap_or_default(),
+					name,
+					filter,
+				});
+			}
+		}
+
+		Ok(Devices { hwmon, power_supply, sensors, thermal_zones })
+	}
+}
+
+fn resolve_networks(networks: Vec<InnerListEntry>) -> Vec<Network> {
+	networks.into_iter()
+	.map(|InnerListEntry { name: network, interval: network_interval }| {
+		let mut dir: std::path::PathBuf = "/sys/class/net".into();
+		dir.push(&network);
+		let mut dir = std::fs::canonicalize(&dir).unwrap_or(dir);
+		dir.push("statistics");
+		let rx_path = dir.join("rx_bytes");
+		let tx_path = dir.join("tx_bytes");
+		Network {
+			name: network,
+			interval: network_interval.map(|Interval(interval)| interval),
+			rx_path,
+			tx_path,
+		}
+	})
+	.collect()
+}
+
+impl Config {
+	// The shortest interval of all sections, at which the message is published, given the interval of the sections that don't set their own.
+	// See `crate::Schedule`
+	pub(crate) fn tick_interval(&self, interval: std::time::Duration) -> std::time::Duration {
+		[self.cpus_interval, self.interrupts_interval, self.processes_interval, self.vmstat_interval].into_iter()
+		.chain(self.sensors.iter().map(|sensor_group| sensor_group.interval))
+		.chain(self.networks.iter().map(|network| network.interval))
+		.chain(self.cgroups.iter().map(|cgroup| cgroup.interval))
+		.flatten()
+		.fold(interval, std::cmp::min)
+	}
+}
+
+// The global `interval` in seconds, which defaults to 1 second.
+fn interval_or_default(interval: Option<f32>) -> std::time::Duration {
+	let interval = interval.unwrap_or(1.);
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	std::time::Duration::from_millis((interval * 1000.) as u64)
+}
+
+// The default bounds of the intervals that clients can request.
+const DEFAULT_MIN_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+const DEFAULT_MAX_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+const DEFAULT_COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Devices that are marked as optional are not required to exist. If such a device is not found,
+// a warning is printed and its sensors are reported as unavailable instead of failing to start.
+fn optional<T>(result: Result<T, crate::Error>, is_optional: bool) -> Result<Option<T>, crate::Error> {
+	match result {
+		Ok(value) => Ok(Some(value)),
+		Err(err) if is_optional => {
+			eprint!("warning: optional device is unavailable: {err}");
+			Ok(None)
+		},
+		Err(err) => Err(err),
+	}
+}
+
+// Maps each hwmon to the first of the hwmon devices, in order of their numbers, that it matches.
+// Returns the hwmons in the order they were matched in, each with the directory of its device if one was found.
+//
+// Devices that are matched by an earlier hwmon are removed, so that two hwmons are never mapped to the same device.
+// The most specific hwmons are matched first, so that a less specific one doesn't take the device that a more specific one refers to.
+// Hwmons that are equally specific are matched in order of their names.
+//
+// `sys_devices` is `/sys/devices`, except in tests.
+fn match_hwmon_nodes(
+	hwmon: std::collections::BTreeMap<String, Hwmon>,
+	mut hwmon_nodes: Vec<(u32, String, std::path::PathBuf)>,
+	sys_devices: &std::path::Path,
+) -> Vec<(String, Hwmon, Option<std::path::PathBuf>)> {
+	hwmon_nodes.sort_by_key(|&(num, _, _)| num);
+
+	let mut hwmon: Vec<_> = hwmon.into_iter().collect();
+	hwmon.sort_by_key(|(_, hwmon)| std::cmp::Reverse(hwmon.num_properties()));
+
+	hwmon.into_iter()
+	.map(|(hwmon_name, hwmon)| {
+		let i = hwmon_nodes.iter().position(|(_, hwmon_node_name, hwmon_node_dir)| hwmon.matches(hwmon_node_name, hwmon_node_dir, sys_devices));
+		let hwmon_node_dir = i.map(|i| {
+			let (_, _, hwmon_node_dir) = hwmon_nodes.remove(i);
+			hwmon_node_dir
+		});
+		(hwmon_name, hwmon, hwmon_node_dir)
+	})
+	.collect()
+}
+
+// Returns the numbers and labels of the channels of the given kind (`temp`, `fan`, ...) of the hwmon that match the given spec.
+//
+// A number or a label without wildcards resolves to a single channel. If there is no channel with that label, the number is `None`.
+//
+// `num = "all"` and a label with wildcards resolve to every matching channel.
+// The channels are sorted naturally by label for a label pattern, so that eg `Core 2` comes before `Core 10`, and by number otherwise.
+fn hwmon_channels(hwmon: Option<&std::path::Path>, kind: &str, num_or_label: &HwmonNumOrLabel) -> Result<Vec<HwmonChannel>, crate::Error> {
+	// If the hwmon is unavailable, the sensor is too. A pattern can't be expanded, so it resolves to a single unavailable sensor.
+	let Some(hwmon) = hwmon else {
+		let label = match num_or_label {
+			HwmonNumOrLabel::Label(label) if !label.contains(['*', '?']) => Some(label.clone()),
+			_ => None,
+		};
+		return Ok(vec![HwmonChannel { num: None, label }]);
+	};
+
+	let label = |num: u8| {
+		let label_path = hwmon.join(format!("{kind}{num}_label"));
+		std::fs::read_to_string(label_path).ok().map(|label| label.trim().to_owned())
+	};
+
+	let mut channels = vec![];
+
+	let (suffix, is_pattern) = match num_or_label {
+		HwmonNumOrLabel::Num(HwmonNum::Num(num)) => return Ok(vec![HwmonChannel { num: Some(*num), label: label(*num) }]),
+		HwmonNumOrLabel::Num(HwmonNum::All) => ("_input", false),
+		HwmonNumOrLabel::Label(pattern) => ("_label", pattern.contains(['*', '?'])),
+	};
+
+	for entry in crate::std2::fs::read_dir(hwmon)? {
+		let entry = entry?.path();
+
+		let Some(entry_file_name) = entry.file_name().and_then(std::ffi::OsStr::to_str) else { continue; };
+		let Some(num) = entry_file_name.strip_prefix(kind).and_then(|num| num.strip_suffix(suffix)) else { continue; };
+		let Ok(num) = num.parse() else { continue; };
+
+		let label = label(num);
+
+		if let HwmonNumOrLabel::Label(pattern) = num_or_label &&
+			!label.as_deref().is_some_and(|label| glob_matches(pattern, label))
+		{
+			continue;
+		}
+
+		channels.push(HwmonChannel { num: Some(num), label });
+	}
+
+	if is_pattern {
+		channels.sort_by(|channel1, channel2|
+			natural_cmp(channel1.label.as_deref().unwrap_or_default(), channel2.label.as_deref().unwrap_or_default())
+			.then(channel1.num.cmp(&channel2.num)));
+	}
+	else {
+		channels.sort_by_key(|channel| channel.num);
+	}
+
+	if matches!(num_or_label, HwmonNumOrLabel::Label(_)) && !is_pattern {
+		// An exact label resolves to a single channel even if it doesn't exist, so that the sensor is still shown as N/A.
+		channels.truncate(1);
+		if channels.is_empty() {
+			channels.push(HwmonChannel { num: None, label: None });
+		}
+	}
+
+	Ok(channels)
+}
+
+// An exact label or a pattern that doesn't match any channel of an available hwmon is most likely a typo,
+// but the sensor is only reported as unavailable since the label might only show up once a driver is loaded.
+fn warn_missing_channels(
+	location: &Location,
+	context: impl std::fmt::Display,
+	hwmon_name: &str,
+	hwmon: Option<&std::path::Path>,
+	kind: &str,
+	num_or_label: &HwmonNumOrLabel,
+	channels: &[HwmonChannel],
+) {
+	let HwmonNumOrLabel::Label(label) = num_or_label else { return; };
+	if hwmon.is_some() && channels.iter().all(|channel| channel.num.is_none()) {
+		location.warn(context, format_args!("hwmon {hwmon_name:?} has no {kind} channel with label {label:?}, the sensor is reported as unavailable"));
+	}
+}
+
+// The sensors that a reference of a derived sensor matches, in the order they're published.
+// Sensors without a name can't be referred to.
+fn sensor_refs(sensors: &[SensorGroup], group_index: usize, reference: &str) -> Vec<crate::derived::SensorRef> {
+	use crate::derived::SensorKind;
+
+	let (group_pattern, name_pattern) = match reference.split_once('/') {
+		Some((group_pattern, name_pattern)) => (Some(group_pattern), name_pattern),
+		None => (None, reference),
+	};
+
+	let mut result = vec![];
+	for (i, sensor_group) in sensors.iter().enumerate() {
+		if !group_pattern.map_or(i == group_index, |group_pattern| glob_matches(group_pattern, &sensor_group.name)) {
+			continue;
+		}
+
+		let values =
+			sensor_group.files.iter().map(|sensor| &sensor.name)
+			.chain(sensor_group.commands.iter().map(|sensor| &sensor.name))
+			.chain(sensor_group.derived.iter().map(|sensor| &sensor.name));
+		let candidates =
+			sensor_group.temps.iter().enumerate().map(|(index, sensor)| (SensorKind::Temp, index, sensor.name.as_deref()))
+			.chain(sensor_group.fans.iter().enumerate().map(|(index, sensor)| (SensorKind::Fan, index, sensor.name.as_deref())))
+			.chain(sensor_group.bats.iter().enumerate().map(|(index, sensor)| (SensorKind::Bat, index, sensor.name.as_deref())))
+			.chain(sensor_group.cooling.iter().enumerate().map(|(index, sensor)| (SensorKind::Cooling, index, sensor.name.as_deref())))
+			.chain(values.enumerate().map(|(index, name)| (SensorKind::Value, index, Some(name.as_str()))));
+		for (kind, index, name) in candidates {
+			if let Some(name) = name.filter(|name| glob_matches(name_pattern, name)) {
+				result.push(crate::derived::SensorRef { name: format!("{}/{name}", sensor_group.name), group: i, kind, index });
+			}
+		}
+	}
+
+	result
+}
+
+struct HwmonChannel {
+	num: Option<u8>,
+	label: Option<String>,
+}
+
+// Matches `s` against `pattern`, where `*` in the pattern matches any sequence of characters and `?` matches any single character.
+pub(crate) fn glob_matches(pattern: &str, s: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let s: Vec<char> = s.chars().collect();
+
+	let (mut p, mut i) = (0, 0);
+	// The position of the last `*` in the pattern and the position in `s` that it was tried to match up to.
+	let mut backtrack = None;
+
+	while i < s.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				backtrack = Some((p, i));
+				p += 1;
+			},
+			Some(&c) if c == '?' || c == s[i] => {
+				p += 1;
+				i += 1;
+			},
+			_ => match &mut backtrack {
+				Some((star_p, star_i)) => {
+					*star_i += 1;
+					p = *star_p + 1;
+					i = *star_i;
+				},
+				None => return false,
+			},
+		}
+	}
+
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+// Compares strings such that runs of digits are compared by their numeric value, eg `Core 2` < `Core 10`
+pub(crate) fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+	let mut a = a.as_bytes();
+	let mut b = b.as_bytes();
+
+	loop {
+		match (a.first(), b.first()) {
+			(None, None) => return std::cmp::Ordering::Equal,
+			(None, Some(_)) => return std::cmp::Ordering::Less,
+			(Some(_), None) => return std::cmp::Ordering::Greater,
+
+			(Some(c1), Some(c2)) if c1.is_ascii_digit() && c2.is_ascii_digit() => {
+				let len1 = a.iter().take_while(|c| c.is_ascii_digit()).count();
+				let len2 = b.iter().take_while(|c| c.is_ascii_digit()).count();
+				let (num1, rest1) = a.split_at(len1);
+				let (num2, rest2) = b.split_at(len2);
+
+				// Leading zeroes don't affect the numeric value, so compare by length after stripping them, then lexicographically.
+				let trim = |num: &[u8]| -> usize { num.iter().take_while(|&&c| c == b'0').count() };
+				let num1 = &num1[trim(num1)..];
+				let num2 = &num2[trim(num2)..];
+				match num1.len().cmp(&num2.len()).then_with(|| num1.cmp(num2)) {
+					std::cmp::Ordering::Equal => (),
+					ordering => return ordering,
+				}
+
+				a = rest1;
+				b = rest2;
+			},
+
+			(Some(c1), Some(c2)) => {
+				match c1.cmp(c2) {
+					std::cmp::Ordering::Equal => (),
+					ordering => return ordering,
+				}
+
+				a = &a[1..];
+				b = &b[1..];
+			},
+		}
+	}
+}
+
+struct ThermalZone {
+	num: u32,
+	r#type: String,
+}
+
+// Returns all thermal zones, ordered by their number.
+fn thermal_zones() -> Result<Vec<ThermalZone>, crate::Error> {
+	let mut result = vec![];
+
+	for entry in crate::std2::fs::read_dir("/sys/class/thermal".as_ref())? {
+		let entry = entry?.path();
+
+		let Some(num) = entry.file_name().and_then(std::ffi::OsStr::to_str).and_then(|file_name| file_name.strip_prefix("thermal_zone")) else { continue; };
+		let Ok(num) = num.parse() else { continue; };
+
+		let Ok(r#type) = std::fs::read_to_string(entry.join("type")) else { continue; };
+
+		result.push(ThermalZone {
+			num,
+			r#type: r#type.trim().to_owned(),
+		});
+	}
+
+	result.sort_by_key(|thermal_zone| thermal_zone.num);
+
+	Ok(result)
+}
+
+// Maps thermal zones that are looked up by type, with an optional index among the zones of that type, to zone numbers.
+// Returns the number or an error for each lookup, in the same order.
+//
+// Every zone is used by at most one sensor. Zones that are specified by number, and then zones that are looked up with an explicit index,
+// are reserved first, so that a lookup without an index never takes the zone that another sensor refers to explicitly.
+// A lookup without an index then gets the first zone of its type that isn't used yet.
+fn resolve_thermal_types(thermal_zones: &[ThermalZone], nums: &[u32], lookups: &[(&str, Option<usize>)]) -> Vec<Result<u32, String>> {
+	let mut used: Vec<_> = thermal_zones.iter().map(|thermal_zone| nums.contains(&thermal_zone.num)).collect();
+	let mut result: Vec<_> = lookups.iter().map(|_| Err(String::new())).collect();
+
+	for (&(thermal_type, index), result) in lookups.iter().zip(&mut result) {
+		let Some(index) = index else { continue; };
+
+		let Some(i) = thermal_zones.iter().enumerate().filter(|(_, thermal_zone)| thermal_zone.r#type == thermal_type).map(|(i, _)| i).nth(index) else {
+			*result = Err(format!("could not find thermal zone of type {thermal_type} with index {index}"));
+			continue;
+		};
+
+		*result =
+			if used[i] {
+				Err(format!("thermal zone of type {thermal_type} with index {index} is thermal_zone{} which is already used by another sensor", thermal_zones[i].num))
+			}
+			else {
+				used[i] = true;
+				Ok(thermal_zones[i].num)
+			};
+	}
+
+	for (&(thermal_type, index), result) in lookups.iter().zip(&mut result) {
+		if index.is_some() {
+			continue;
+		}
 
-				Ok(SensorGroup {
-					name,
-					temps,
-					fans,
-					bats,
-				})
-			})
-			.collect();
-		let sensors = sensors.map_err(serde::de::Error::custom)?;
-
-		let networks =
-			networks.into_iter()
-			.map(|network| {
-				let mut dir: std::path::PathBuf = "/sys/class/net".into();
-				dir.push(&network);
-				let mut dir = std::fs::canonicalize(&dir).unwrap_or(dir);
-				dir.push("statistics");
-				let rx_path = dir.join("rx_bytes");
-				let tx_path = dir.join("tx_bytes");
-				Network {
-					name: network,
-					rx_path,
-					tx_path,
-				}
-			})
-			.collect();
+		*result = match thermal_zones.iter().zip(&mut used).find(|(thermal_zone, used)| thermal_zone.r#type == thermal_type && !**used) {
+			Some((thermal_zone, used)) => {
+				*used = true;
+				Ok(thermal_zone.num)
+			},
+			None => Err(format!("could not find unused thermal zone of type {thermal_type}")),
+		};
+	}
 
-		Ok(Config {
-			interval,
-			cpus,
-			sensors,
-			networks,
-		})
+	result
+}
+
+fn thermal_temp_sensor(thermal_zone: Option<u32>, calibration: Calibration, filter: Filter, name: Option<String>) -> TempSensor {
+	let Some(thermal_zone) = thermal_zone else {
+		return TempSensor {
+			path: None,
+			calibration,
+			filter,
+			name,
+			thermal_zone: None,
+		};
+	};
+
+	let mut thermal = std::path::Path::new("/sys/class/thermal").to_owned();
+	thermal.push(format!("thermal_zone{thermal_zone}"));
+
+	let name = name.or_else(|| {
+		let label_path = thermal.join("type");
+		std::fs::read_to_string(label_path).ok().map(|label| label.trim().to_owned())
+	});
+
+	TempSensor {
+		path: Some(thermal.join("temp")),
+		calibration,
+		filter,
+		name,
+		thermal_zone: Some(thermal),
 	}
 }
 
-#[derive(Debug, PartialEq, serde::Deserialize)]
+#[derive(Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 struct InnerConfig {
 	interval: Option<f32>,
+	min_interval: Option<Interval>,
+	max_interval: Option<Interval>,
 	#[serde(default)]
 	cpus: Cpus,
 	#[serde(default)]
 	hwmon: std::collections::BTreeMap<String, Hwmon>,
 	#[serde(default)]
-	power_supply: std::collections::BTreeMap<String, String>,
+	power_supply: std::collections::BTreeMap<String, InnerPowerSupply>,
 	#[serde(default, rename = "sensor")]
 	sensors: Vec<InnerSensorGroup>,
 	#[serde(default)]
-	networks: Vec<String>,
+	networks: Vec<InnerListEntry>,
+	#[serde(default)]
+	interrupts: Interrupts,
+	#[serde(default)]
+	processes: Processes,
+	#[serde(default)]
+	vmstat: VmStat,
+	#[serde(default)]
+	cgroups: Vec<InnerListEntry>,
 }
 
-#[derive(Debug, Eq, PartialEq, serde::Deserialize)]
-enum Hwmon {
-	#[serde(rename = "dev_name")]
+// Selects an hwmon device. Every property that is specified must match.
+#[derive(Debug, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Hwmon {
+	// `/sys/class/hwmon/hwmon*/name`
+	dev_name: Option<String>,
+	// Prefix of the canonical path of the hwmon device, eg `/sys/devices/platform/coretemp.0`
+	dev_path: Option<std::path::PathBuf>,
+	// Name of the driver of the device that the hwmon belongs to, ie the target of the `device/driver` symlink
+	driver: Option<String>,
+	pci_vendor: Option<u16>,
+	pci_device: Option<u16>,
+	pci_subsystem_vendor: Option<u16>,
+	pci_subsystem_device: Option<u16>,
+	// eg `0000:0b:00.0`. The domain can be omitted, eg `0b:00.0`
+	pci_slot: Option<String>,
+	usb_vendor: Option<u16>,
+	usb_product: Option<u16>,
+	// One of the strings in the device tree node's `compatible` property, eg `allwinner,sun50i-a64-ths`
+	compatible: Option<String>,
+	// If set, sensord starts even if the device is not found. Its sensors are reported as unavailable.
+	#[serde(default)]
+	optional: bool,
+}
+
+impl Hwmon {
+	// The number of properties that the device is matched by
+	fn num_properties(&self) -> usize {
+		let Hwmon {
+			dev_name,
+			dev_path,
+			driver,
+			pci_vendor,
+			pci_device,
+			pci_subsystem_vendor,
+			pci_subsystem_device,
+			pci_slot,
+			usb_vendor,
+			usb_product,
+			compatible,
+			optional: _,
+		} = self;
+
+		[
+			dev_name.is_some(),
+			dev_path.is_some(),
+			driver.is_some(),
+			pci_vendor.is_some(),
+			pci_device.is_some(),
+			pci_subsystem_vendor.is_some(),
+			pci_subsystem_device.is_some(),
+			pci_slot.is_some(),
+			usb_vendor.is_some(),
+			usb_product.is_some(),
+			compatible.is_some(),
+		].into_iter().filter(|&is_some| is_some).count()
+	}
+
+	fn matches(&self, name: &str, dir: &std::path::Path, sys_devices: &std::path::Path) -> bool {
+		if self.dev_name.as_ref().is_some_and(|dev_name| dev_name != name) {
+			return false;
+		}
+
+		if self.dev_path.as_ref().is_some_and(|dev_path| !dir.starts_with(dev_path)) {
+			return false;
+		}
+
+		// Virtual hwmons like `acpitz` do not have a device, in which case none of the device properties match.
+		let device = std::fs::canonicalize(dir.join("device")).ok();
+		let device = device.as_deref();
+
+		if let Some(driver) = &self.driver {
+			let actual_driver = device.and_then(|device| std::fs::read_link(device.join("driver")).ok());
+			if actual_driver.as_deref().and_then(std::path::Path::file_name) != Some(driver.as_ref()) {
+				return false;
+			}
+		}
+
+		// The hwmon's device can be a child of the PCI or USB device, eg the `nvme0` device of an NVMe drive, so the ancestors are searched too.
+
+		let pci_ids = [
+			(self.pci_vendor, "vendor"),
+			(self.pci_device, "device"),
+			(self.pci_subsystem_vendor, "subsystem_vendor"),
+			(self.pci_subsystem_device, "subsystem_device"),
+		];
+		if pci_ids.iter().any(|(expected, _)| expected.is_some()) {
+			let pci_device = device.and_then(|device| device_ancestor_under(sys_devices, device, "pci", "vendor"));
+			for (expected, file_name) in pci_ids {
+				let Some(expected) = expected else { continue; };
+				if pci_device.and_then(|pci_device| parse_device_id(&pci_device.join(file_name))) != Some(expected) {
+					return false;
+				}
+			}
+		}
+
+		if let Some(pci_slot) = &self.pci_slot {
+			let pci_slot = if pci_slot.matches(':').count() == 1 { format!("0000:{pci_slot}") } else { pci_slot.to_owned() };
+			let pci_device = device.and_then(|device| device_ancestor_under(sys_devices, device, "pci", "vendor"));
+			if pci_device.and_then(std::path::Path::file_name) != Some(pci_slot.as_ref()) {
+				return false;
+			}
+		}
+
+		let usb_ids = [
+			(self.usb_vendor, "idVendor"),
+			(self.usb_product, "idProduct"),
+		];
+		if usb_ids.iter().any(|(expected, _)| expected.is_some()) {
+			let usb_device = device.and_then(|device| device_ancestor_under(sys_devices, device, "usb", "idVendor"));
+			for (expected, file_name) in usb_ids {
+				let Some(expected) = expected else { continue; };
+				if usb_device.and_then(|usb_device| parse_device_id(&usb_device.join(file_name))) != Some(expected) {
+					return false;
+				}
+			}
+		}
+
+		if let Some(compatible) = &self.compatible {
+			let actual_compatible =
+				device.into_iter()
+				.flat_map(std::path::Path::ancestors)
+				.find_map(|device| std::fs::read_to_string(device.join("of_node").join("compatible")).ok());
+			// The property is a list of NUL-terminated strings.
+			if !actual_compatible.is_some_and(|actual_compatible| actual_compatible.split('\0').any(|actual_compatible| actual_compatible == compatible)) {
+				return false;
+			}
+		}
+
+		true
+	}
+}
+
+impl std::fmt::Display for Hwmon {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let Hwmon {
+			dev_name,
+			dev_path,
+			driver,
+			pci_vendor,
+			pci_device,
+			pci_subsystem_vendor,
+			pci_subsystem_device,
+			pci_slot,
+			usb_vendor,
+			usb_product,
+			compatible,
+			optional: _,
+		} = self;
+
+		let mut properties: Vec<String> = vec![];
+		if let Some(dev_name) = dev_name { properties.push(format!("dev_name = {dev_name:?}")); }
+		if let Some(dev_path) = dev_path { properties.push(format!("dev_path = {:?}", dev_path.display())); }
+		if let Some(driver) = driver { properties.push(format!("driver = {driver:?}")); }
+		if let Some(pci_vendor) = pci_vendor { properties.push(format!("pci_vendor = {pci_vendor:#06x}")); }
+		if let Some(pci_device) = pci_device { properties.push(format!("pci_device = {pci_device:#06x}")); }
+		if let Some(pci_subsystem_vendor) = pci_subsystem_vendor { properties.push(format!("pci_subsystem_vendor = {pci_subsystem_vendor:#06x}")); }
+		if let Some(pci_subsystem_device) = pci_subsystem_device { properties.push(format!("pci_subsystem_device = {pci_subsystem_device:#06x}")); }
+		if let Some(pci_slot) = pci_slot { properties.push(format!("pci_slot = {pci_slot:?}")); }
+		if let Some(usb_vendor) = usb_vendor { properties.push(format!("usb_vendor = {usb_vendor:#06x}")); }
+		if let Some(usb_product) = usb_product { properties.push(format!("usb_product = {usb_product:#06x}")); }
+		if let Some(compatible) = compatible { properties.push(format!("compatible = {compatible:?}")); }
+
+		write!(f, "{{ {} }}", properties.join(", "))
+	}
+}
+
+// Returns the closest ancestor of the given device (including the device itself) that belongs to the given bus.
+// Only devices that have the given file are considered, to skip eg USB interfaces which are also on the `usb` bus.
+pub(crate) fn device_ancestor<'a>(device: &'a std::path::Path, subsystem: &str, file_name: &str) -> Option<&'a std::path::Path> {
+	device_ancestor_under("/sys/devices".as_ref(), device, subsystem, file_name)
+}
+
+// `sys_devices` is `/sys/devices`, except in tests.
+fn device_ancestor_under<'a>(sys_devices: &std::path::Path, device: &'a std::path::Path, subsystem: &str, file_name: &str) -> Option<&'a std::path::Path> {
+	device.ancestors()
+		.take_while(|device| device.starts_with(sys_devices))
+		.find(|device| {
+			let actual_subsystem = std::fs::read_link(device.join("subsystem")).ok();
+			actual_subsystem.as_deref().and_then(std::path::Path::file_name) == Some(subsystem.as_ref()) &&
+				device.join(file_name).exists()
+		})
+}
+
+// PCI IDs are written as `0x1002`, USB IDs are written as `1002`
+fn parse_device_id(path: &std::path::Path) -> Option<u16> {
+	let id = std::fs::read_to_string(path).ok()?;
+	let id = id.trim();
+	let id = id.strip_prefix("0x").unwrap_or(id);
+	u16::from_str_radix(id, 16).ok()
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum InnerPowerSupply {
 	Name(String),
-	#[serde(rename = "dev_path")]
-	Path(std::path::PathBuf),
+	Spec {
+		dev_name: String,
+		optional: bool,
+	},
+}
+
+impl<'de> serde::Deserialize<'de> for InnerPowerSupply {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: serde::Deserializer<'de> {
+		#[derive(serde::Deserialize)]
+		#[serde(deny_unknown_fields)]
+		struct Spec {
+			dev_name: String,
+			#[serde(default)]
+			optional: bool,
+		}
+
+		struct Visitor;
+
+		impl<'de> serde::de::Visitor<'de> for Visitor {
+			type Value = InnerPowerSupply;
+
+			fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				formatter.write_str("a power_supply device name or a table with `dev_name`")
+			}
+
+			fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: serde::de::Error {
+				Ok(InnerPowerSupply::Name(v.to_owned()))
+			}
+
+			fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error> where A: serde::de::MapAccess<'de> {
+				let Spec { dev_name, optional } = serde::Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+				Ok(InnerPowerSupply::Spec { dev_name, optional })
+			}
+		}
+
+		deserializer.deserialize_any(Visitor)
+	}
+}
+
+// An entry of `networks` or `cgroups`, which is either just its name or a table with the name and the entry's own interval.
+#[derive(Debug, Eq, PartialEq)]
+struct InnerListEntry {
+	name: String,
+	interval: Option<Interval>,
+}
+
+impl<'de> serde::Deserialize<'de> for InnerListEntry {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: serde::Deserializer<'de> {
+		#[derive(serde::Deserialize)]
+		#[serde(deny_unknown_fields)]
+		struct Spec {
+			name: String,
+			interval: Option<Interval>,
+		}
+
+		struct Visitor;
+
+		impl<'de> serde::de::Visitor<'de> for Visitor {
+			type Value = InnerListEntry;
+
+			fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				formatter.write_str("a name or a table with `name` and `interval`")
+			}
+
+			fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: serde::de::Error {
+				Ok(InnerListEntry { name: v.to_owned(), interval: None })
+			}
+
+			fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error> where A: serde::de::MapAccess<'de> {
+				let Spec { name, interval } = serde::Deserialize::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+				Ok(InnerListEntry { name, interval })
+			}
+		}
+
+		deserializer.deserialize_any(Visitor)
+	}
 }
 
 #[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
 struct InnerSensorGroup {
 	name: String,
+	interval: Option<Interval>,
 	#[serde(default)]
 	temps: Vec<InnerTempSensor>,
 	#[serde(default)]
 	fans: Vec<InnerFanSensor>,
 	#[serde(default)]
 	bats: Vec<InnerBatSensor>,
+	#[serde(default)]
+	cooling: Vec<InnerCoolingSensor>,
+	#[serde(default)]
+	files: Vec<InnerFileSensor>,
+	#[serde(default)]
+	commands: Vec<InnerCommandSensor>,
+	#[serde(default)]
+	derived: Vec<InnerDerivedSensor>,
 }
 
 #[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawTempSensor")]
 struct InnerTempSensor {
-	#[serde(flatten)]
 	spec: InnerTempSensorSpec,
-	offset: Option<f64>,
+	calibration: Calibration,
+	filter: Filter,
 	name: Option<String>,
+	optional: bool,
 }
 
-#[derive(Debug, Eq, PartialEq, serde::Deserialize)]
-#[serde(untagged)]
+#[derive(Debug, Eq, PartialEq)]
 enum InnerTempSensorSpec {
-	Hwmon { hwmon: String, #[serde(flatten)] num_or_label: HwmonNumOrLabel },
+	Hwmon { hwmon: String, num_or_label: HwmonNumOrLabel },
 	Thermal { thermal_zone: u8 },
+	ThermalType { thermal_type: String, index: Option<usize> },
+}
+
+// Sensors are first deserialized with every key being optional, so that a sensor with a wrong combination of keys
+// gets an error that says what's wrong with it, rather than that it didn't match any of the kinds of sensors.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawTempSensor {
+	hwmon: Option<String>,
+	num: Option<HwmonNum>,
+	label: Option<String>,
+	thermal_zone: Option<u8>,
+	thermal_type: Option<String>,
+	index: Option<usize>,
+	scale: Option<f64>,
+	offset: Option<f64>,
+	#[serde(default)]
+	invert: bool,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+	name: Option<String>,
+	#[serde(default)]
+	optional: bool,
+}
+
+impl TryFrom<RawTempSensor> for InnerTempSensor {
+	type Error = String;
+
+	fn try_from(raw: RawTempSensor) -> Result<Self, Self::Error> {
+		let RawTempSensor { hwmon, num, label, thermal_zone, thermal_type, index, scale, offset, invert, name, optional, valid_range, ema, median } = raw;
+
+		let spec = match (hwmon, thermal_zone, thermal_type) {
+			(Some(hwmon), None, None) => {
+				if index.is_some() {
+					return Err("`index` can only be used with `thermal_type`".to_owned());
+				}
+				// Whether the sensors of an hwmon are available depends on whether the hwmon is, so it's the hwmon that's optional.
+				if optional {
+					return Err("`optional` can only be used with `thermal_zone` or `thermal_type`, mark the hwmon as optional instead".to_owned());
+				}
+				InnerTempSensorSpec::Hwmon { hwmon, num_or_label: HwmonNumOrLabel::new(num, label, name.is_some())? }
+			},
+
+			(None, thermal_zone, thermal_type) => {
+				if num.is_some() || label.is_some() {
+					return Err("`num` and `label` can only be used with `hwmon`".to_owned());
+				}
+				match (thermal_zone, thermal_type) {
+					(Some(thermal_zone), None) => {
+						if index.is_some() {
+							return Err("`index` can only be used with `thermal_type`".to_owned());
+						}
+						InnerTempSensorSpec::Thermal { thermal_zone }
+					},
+					(None, Some(thermal_type)) => InnerTempSensorSpec::ThermalType { thermal_type, index },
+					(None, None) => return Err("temp sensor must have one of `hwmon`, `thermal_zone` or `thermal_type`".to_owned()),
+					(Some(_), Some(_)) => return Err("temp sensor can only have one of `hwmon`, `thermal_zone` or `thermal_type`".to_owned()),
+				}
+			},
+
+			(Some(_), _, _) => return Err("temp sensor can only have one of `hwmon`, `thermal_zone` or `thermal_type`".to_owned()),
+		};
+
+		Ok(InnerTempSensor { spec, calibration: Calibration::new(scale, offset, invert)?, filter: Filter::new(valid_range, ema, median)?, name, optional })
+	}
 }
 
-#[derive(Debug, Eq, PartialEq, serde::Deserialize)]
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawFanSensor")]
 struct InnerFanSensor {
 	hwmon: String,
-	#[serde(flatten)] num_or_label: HwmonNumOrLabel,
+	num_or_label: HwmonNumOrLabel,
 	name: Option<String>,
+	calibration: Calibration,
+	filter: Filter,
 }
 
-#[derive(Debug, Eq, PartialEq, serde::Deserialize)]
-enum InnerBatSensor {
-	#[serde(rename = "hwmon")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFanSensor {
+	hwmon: String,
+	num: Option<HwmonNum>,
+	label: Option<String>,
+	name: Option<String>,
+	scale: Option<f64>,
+	offset: Option<f64>,
+	#[serde(default)]
+	invert: bool,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+}
+
+impl TryFrom<RawFanSensor> for InnerFanSensor {
+	type Error = String;
+
+	fn try_from(raw: RawFanSensor) -> Result<Self, Self::Error> {
+		let RawFanSensor { hwmon, num, label, name, scale, offset, invert, valid_range, ema, median } = raw;
+		Ok(InnerFanSensor { hwmon, num_or_label: HwmonNumOrLabel::new(num, label, name.is_some())?, name, calibration: Calibration::new(scale, offset, invert)?, filter: Filter::new(valid_range, ema, median)? })
+	}
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawBatSensor")]
+struct InnerBatSensor {
+	device: InnerBatSensorDevice,
+	calibration: Calibration,
+	filter: Filter,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum InnerBatSensorDevice {
 	Hwmon(String),
-	#[serde(rename = "power_supply")]
 	PowerSupply(String),
 }
 
-#[derive(Debug, Eq, PartialEq, serde::Deserialize)]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawBatSensor {
+	hwmon: Option<String>,
+	power_supply: Option<String>,
+	scale: Option<f64>,
+	offset: Option<f64>,
+	#[serde(default)]
+	invert: bool,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+}
+
+impl TryFrom<RawBatSensor> for InnerBatSensor {
+	type Error = String;
+
+	fn try_from(raw: RawBatSensor) -> Result<Self, Self::Error> {
+		let RawBatSensor { hwmon, power_supply, scale, offset, invert, valid_range, ema, median } = raw;
+		let device = match (hwmon, power_supply) {
+			(Some(hwmon), None) => InnerBatSensorDevice::Hwmon(hwmon),
+			(None, Some(power_supply)) => InnerBatSensorDevice::PowerSupply(power_supply),
+			(None, None) => return Err("battery sensor must have one of `hwmon` or `power_supply`".to_owned()),
+			(Some(_), Some(_)) => return Err("battery sensor can only have one of `hwmon` or `power_supply`".to_owned()),
+		};
+		Ok(InnerBatSensor { device, calibration: Calibration::new(scale, offset, invert)?, filter: Filter::new(valid_range, ema, median)? })
+	}
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawCoolingSensor")]
+struct InnerCoolingSensor {
+	cooling_device: u8,
+	name: Option<String>,
+	optional: bool,
+	filter: Filter,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCoolingSensor {
+	cooling_device: u8,
+	name: Option<String>,
+	#[serde(default)]
+	optional: bool,
+	scale: Option<f64>,
+	offset: Option<f64>,
+	#[serde(default)]
+	invert: bool,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+}
+
+impl TryFrom<RawCoolingSensor> for InnerCoolingSensor {
+	type Error = String;
+
+	fn try_from(raw: RawCoolingSensor) -> Result<Self, Self::Error> {
+		let RawCoolingSensor { cooling_device, name, optional, scale, offset, invert, valid_range, ema, median } = raw;
+		// The current state is displayed relative to the max state, so calibrating only one of them would be misleading,
+		// and calibrating both would be meaningless for a step count.
+		if scale.is_some() || offset.is_some() || invert {
+			return Err("`scale`, `offset` and `invert` cannot be set on cooling devices".to_owned());
+		}
+		Ok(InnerCoolingSensor { cooling_device, name, optional, filter: Filter::new(valid_range, ema, median)? })
+	}
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawFileSensor")]
+struct InnerFileSensor {
+	path: std::path::PathBuf,
+	regex: Option<String>,
+	rate: bool,
+	unit: Option<String>,
+	name: Option<String>,
+	calibration: Calibration,
+	filter: Filter,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFileSensor {
+	path: std::path::PathBuf,
+	regex: Option<String>,
+	#[serde(default)]
+	rate: bool,
+	unit: Option<String>,
+	name: Option<String>,
+	scale: Option<f64>,
+	offset: Option<f64>,
+	#[serde(default)]
+	invert: bool,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+}
+
+impl TryFrom<RawFileSensor> for InnerFileSensor {
+	type Error = String;
+
+	fn try_from(raw: RawFileSensor) -> Result<Self, Self::Error> {
+		let RawFileSensor { path, regex, rate, unit, name, scale, offset, invert, valid_range, ema, median } = raw;
+		if !path.is_absolute() {
+			return Err("`path` must be an absolute path".to_owned());
+		}
+		Ok(InnerFileSensor { path, regex, rate, unit, name, calibration: Calibration::new(scale, offset, invert)?, filter: Filter::new(valid_range, ema, median)? })
+	}
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawCommandSensor")]
+struct InnerCommandSensor {
+	command: Vec<String>,
+	interval: Option<Interval>,
+	timeout: Option<Interval>,
+	output: InnerCommandOutput,
+	unit: Option<String>,
+	name: Option<String>,
+	calibration: Calibration,
+	filter: Filter,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum InnerCommandOutput {
+	Number,
+	Regex(String),
+	Key(String),
+	Json(String),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCommandSensor {
+	command: Vec<String>,
+	interval: Option<f64>,
+	timeout: Option<f64>,
+	regex: Option<String>,
+	key: Option<String>,
+	json: Option<String>,
+	unit: Option<String>,
+	name: Option<String>,
+	scale: Option<f64>,
+	offset: Option<f64>,
+	#[serde(default)]
+	invert: bool,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+}
+
+impl TryFrom<RawCommandSensor> for InnerCommandSensor {
+	type Error = String;
+
+	fn try_from(raw: RawCommandSensor) -> Result<Self, Self::Error> {
+		let RawCommandSensor { command, interval, timeout, regex, key, json, unit, name, scale, offset, invert, valid_range, ema, median } = raw;
+
+		if command.is_empty() {
+			return Err("`command` must not be empty".to_owned());
+		}
+
+		let interval = interval.map(Interval::try_from).transpose()?;
+		let timeout = timeout.map(|timeout| Interval::try_from(timeout).map_err(|_| "`timeout` must be a positive number of seconds".to_owned())).transpose()?;
+
+		let output = match (regex, key, json) {
+			(None, None, None) => InnerCommandOutput::Number,
+			(Some(regex), None, None) => InnerCommandOutput::Regex(regex),
+			(None, Some(key), None) => InnerCommandOutput::Key(key),
+			(None, None, Some(path)) => InnerCommandOutput::Json(path),
+			_ => return Err("command sensor can only have one of `regex`, `key` or `json`".to_owned()),
+		};
+
+		Ok(InnerCommandSensor { command, interval, timeout, output, unit, name, calibration: Calibration::new(scale, offset, invert)?, filter: Filter::new(valid_range, ema, median)? })
+	}
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize)]
+#[serde(try_from = "RawDerivedSensor")]
+struct InnerDerivedSensor {
+	expression: crate::derived::Expression<String>,
+	unit: Option<String>,
+	name: String,
+	filter: Filter,
+}
+
+// `max`, `min`, `avg` and `sum` are shorthands for expressions that apply the function to the listed sensors,
+// and `diff` for an expression that subtracts the second sensor from the first.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDerivedSensor {
+	max: Option<Vec<String>>,
+	min: Option<Vec<String>>,
+	avg: Option<Vec<String>>,
+	sum: Option<Vec<String>>,
+	diff: Option<[String; 2]>,
+	expr: Option<String>,
+	unit: Option<String>,
+	name: String,
+	valid_range: Option<[f64; 2]>,
+	ema: Option<f64>,
+	median: Option<usize>,
+}
+
+impl TryFrom<RawDerivedSensor> for InnerDerivedSensor {
+	type Error = String;
+
+	fn try_from(raw: RawDerivedSensor) -> Result<Self, Self::Error> {
+		use crate::derived::{Expression, Function, Operator};
+
+		let RawDerivedSensor { max, min, avg, sum, diff, expr, unit, name, valid_range, ema, median } = raw;
+
+		let mut expressions = vec![];
+		for (function, references) in [(Function::Max, max), (Function::Min, min), (Function::Avg, avg), (Function::Sum, sum)] {
+			if let Some(references) = references {
+				if references.is_empty() {
+					return Err(format!("`{}` must not be empty", function.name()));
+				}
+				expressions.push(Expression::Function(function, references.into_iter().map(Expression::Sensor).collect()));
+			}
+		}
+		if let Some([minuend, subtrahend]) = diff {
+			expressions.push(Expression::Binary(Box::new(Expression::Sensor(minuend)), Operator::Subtract, Box::new(Expression::Sensor(subtrahend))));
+		}
+		if let Some(expr) = expr {
+			expressions.push(crate::derived::parse(&expr)?);
+		}
+
+		let expression = expressions.pop().filter(|_| expressions.is_empty());
+		let expression = expression.ok_or("derived sensor must have exactly one of `max`, `min`, `avg`, `sum`, `diff` or `expr`")?;
+
+		Ok(InnerDerivedSensor { expression, unit, name, filter: Filter::new(valid_range, ema, median)? })
+	}
+}
+
+#[derive(Debug, Eq, PartialEq)]
 enum HwmonNumOrLabel {
-	#[serde(rename = "num")]
-	Num(u8),
-	#[serde(rename = "label")]
+	Num(HwmonNum),
 	Label(String),
 }
 
+impl HwmonNumOrLabel {
+	fn new(num: Option<HwmonNum>, label: Option<String>, has_name: bool) -> Result<Self, String> {
+		let num_or_label = match (num, label) {
+			(Some(num), None) => HwmonNumOrLabel::Num(num),
+			(None, Some(label)) => HwmonNumOrLabel::Label(label),
+			(None, None) => return Err("hwmon sensor must have one of `num` or `label`".to_owned()),
+			(Some(_), Some(_)) => return Err("hwmon sensor can only have one of `num` or `label`".to_owned()),
+		};
+
+		// Every sensor that `num = "all"` or a label pattern expands to would get the same name.
+		let is_multiple = match &num_or_label {
+			HwmonNumOrLabel::Num(num) => *num == HwmonNum::All,
+			HwmonNumOrLabel::Label(label) => label.contains(['*', '?']),
+		};
+		if is_multiple && has_name {
+			return Err("`name` can't be used with `num = \"all\"` or a label pattern, since every matching sensor would get the same name".to_owned());
+		}
+
+		Ok(num_or_label)
+	}
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum HwmonNum {
+	Num(u8),
+	All,
+}
+
+impl<'de> serde::Deserialize<'de> for HwmonNum {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: serde::Deserializer<'de> {
+		struct Visitor;
+
+		impl serde::de::Visitor<'_> for Visitor {
+			type Value = HwmonNum;
+
+			fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				formatter.write_str(r#"a sensor number or "all""#)
+			}
+
+			fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: serde::de::Error {
+				let num = v.try_into().map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Signed(v), &self))?;
+				Ok(HwmonNum::Num(num))
+			}
+
+			fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: serde::de::Error {
+				let num = v.try_into().map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Unsigned(v), &self))?;
+				Ok(HwmonNum::Num(num))
+			}
+
+			fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: serde::de::Error {
+				match v {
+					"all" => Ok(HwmonNum::All),
+					v => Err(serde::de::Error::invalid_value(serde::de::Unexpected::Str(v), &self)),
+				}
+			}
+		}
+
+		deserializer.deserialize_any(Visitor)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -404,51 +2184,77 @@ mod tests {
 	fn microsoft_surfacert() {
 		test_inner("microsoft-surfacert.toml", &InnerConfig {
 			interval: None,
+			min_interval: None,
+			max_interval: None,
 			cpus: Cpus {
 				use_sysfs: false,
+				container: false,
+				interval: None,
 			},
 			hwmon: [
-				("soc".to_owned(), Hwmon::Name("nct1008".to_owned())),
+				("soc".to_owned(), Hwmon { dev_name: Some("nct1008".to_owned()), ..Default::default() }),
 			].into(),
 			power_supply: [
-				("bat".to_owned(), "surface-rt-battery".to_owned()),
+				("bat".to_owned(), InnerPowerSupply::Name("surface-rt-battery".to_owned())),
 			].into(),
 			sensors: vec![
 				InnerSensorGroup {
 					name: "SoC".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "soc".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "soc".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(2),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(2)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "Bat".to_owned(),
+					interval: None,
 					temps: vec![],
 					fans: vec![],
 					bats: vec![
-						InnerBatSensor::PowerSupply("bat".to_owned()),
+						InnerBatSensor {
+							device: InnerBatSensorDevice::PowerSupply("bat".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
+						},
 					],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 			],
 			networks: vec![
-				"mlan0".to_owned(),
+				InnerListEntry { name: "mlan0".to_owned(), interval: None },
 			],
+			interrupts: Default::default(),
+			processes: Default::default(),
+			vmstat: Default::default(),
+			cgroups: vec![],
 		});
 	}
 
@@ -456,68 +2262,101 @@ mod tests {
 	fn pinephone() {
 		test_inner("pinephone.toml", &InnerConfig {
 			interval: None,
+			min_interval: None,
+			max_interval: None,
 			cpus: Cpus {
 				use_sysfs: true,
+				container: false,
+				interval: None,
 			},
 			hwmon: [
-				("cpu".to_owned(), Hwmon::Name("cpu0_thermal".to_owned())),
-				("gpu0".to_owned(), Hwmon::Name("gpu0_thermal".to_owned())),
-				("gpu1".to_owned(), Hwmon::Name("gpu1_thermal".to_owned())),
-				("bat".to_owned(), Hwmon::Name("axp20x_battery".to_owned())),
+				("cpu".to_owned(), Hwmon { dev_name: Some("cpu0_thermal".to_owned()), ..Default::default() }),
+				("gpu0".to_owned(), Hwmon { dev_name: Some("gpu0_thermal".to_owned()), ..Default::default() }),
+				("gpu1".to_owned(), Hwmon { dev_name: Some("gpu1_thermal".to_owned()), ..Default::default() }),
+				("bat".to_owned(), Hwmon { dev_name: Some("axp20x_battery".to_owned()), ..Default::default() }),
 			].into(),
 			power_supply: Default::default(),
 			sensors: vec![
 				InnerSensorGroup {
 					name: "CPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "GPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "gpu0".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "gpu1".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "Bat".to_owned(),
+					interval: None,
 					temps: vec![],
 					fans: vec![],
 					bats: vec![
-						InnerBatSensor::Hwmon("bat".to_owned()),
+						InnerBatSensor {
+							device: InnerBatSensorDevice::Hwmon("bat".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
+						},
 					],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 			],
 			networks: vec![
-				"eth0".to_owned(),
-				"wwan0".to_owned(),
+				InnerListEntry { name: "eth0".to_owned(), interval: None },
+				InnerListEntry { name: "wwan0".to_owned(), interval: None },
 			],
+			interrupts: Default::default(),
+			processes: Default::default(),
+			vmstat: Default::default(),
+			cgroups: vec![],
 		});
 	}
 
@@ -525,40 +2364,64 @@ mod tests {
 	fn raspberry_pi() {
 		test_inner("raspberry-pi.toml", &InnerConfig {
 			interval: None,
+			min_interval: None,
+			max_interval: None,
 			cpus: Cpus {
 				use_sysfs: true,
+				container: false,
+				interval: None,
 			},
 			hwmon: [
-				("cpu".to_owned(), Hwmon::Name("cpu_thermal".to_owned())),
+				("cpu".to_owned(), Hwmon { dev_name: Some("cpu_thermal".to_owned()), ..Default::default() }),
 			].into(),
 			power_supply: Default::default(),
 			sensors: vec![
 				InnerSensorGroup {
 					name: "CPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Thermal {
 								thermal_zone: 0,
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![
+						InnerCoolingSensor {
+							cooling_device: 0,
+							name: Some("cpufreq".to_owned()),
+							optional: false,
+							filter: Default::default(),
+						},
+					],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 			],
 			networks: vec![
-				"eth0".to_owned(),
+				InnerListEntry { name: "eth0".to_owned(), interval: None },
 			],
+			interrupts: Default::default(),
+			processes: Default::default(),
+			vmstat: Default::default(),
+			cgroups: vec![],
 		});
 	}
 
@@ -566,175 +2429,235 @@ mod tests {
 	fn t61() {
 		test_inner("t61.toml", &InnerConfig {
 			interval: None,
+			min_interval: None,
+			max_interval: None,
 			cpus: Cpus {
 				use_sysfs: false,
+				container: false,
+				interval: None,
 			},
 			hwmon: [
-				("acpi".to_owned(), Hwmon::Name("acpitz".to_owned())),
-				("cpu".to_owned(), Hwmon::Name("coretemp".to_owned())),
-				("gpu".to_owned(), Hwmon::Name("nouveau".to_owned())),
-				("mobo".to_owned(), Hwmon::Name("thinkpad".to_owned())),
+				("acpi".to_owned(), Hwmon { dev_name: Some("acpitz".to_owned()), ..Default::default() }),
+				("cpu".to_owned(), Hwmon { dev_name: Some("coretemp".to_owned()), ..Default::default() }),
+				("gpu".to_owned(), Hwmon { dev_name: Some("nouveau".to_owned()), ..Default::default() }),
+				("mobo".to_owned(), Hwmon { dev_name: Some("thinkpad".to_owned()), ..Default::default() }),
 			].into(),
 			power_supply: Default::default(),
 			sensors: vec![
 				InnerSensorGroup {
 					name: "CPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("Core 0".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("Core 1".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("mobo".to_owned()),
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "GPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "gpu".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(1),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(4),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(4)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("mobo".to_owned()),
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "Mobo".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(2),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(2)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("aps".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(3),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(3)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("crd".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(5),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(5)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("no5".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(9),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(9)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("bus".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(10),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(10)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("pci".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(11),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(11)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("pwr".to_owned()),
+							optional: false,
 						},
 					],
 					fans: vec![
 						InnerFanSensor {
 							hwmon: "mobo".to_owned(),
-							num_or_label: HwmonNumOrLabel::Num(1),
+							num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							name: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 						},
 					],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "Mobo".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(6),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(6)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("x7d".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(7),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(7)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("bat".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(8),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(8)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("x7f".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
-								num_or_label: HwmonNumOrLabel::Num(12),
+								num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(12)),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("xc3".to_owned()),
+							optional: false,
 						},
 					],
 					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 			],
 			networks: vec![
-				"enp0s25".to_owned(),
+				InnerListEntry { name: "enp0s25".to_owned(), interval: None },
 			],
+			interrupts: Default::default(),
+			processes: Default::default(),
+			vmstat: Default::default(),
+			cgroups: vec![],
 		});
 	}
 
@@ -742,172 +2665,251 @@ mod tests {
 	fn threadripper2() {
 		test_inner("threadripper2.toml", &InnerConfig {
 			interval: None,
+			min_interval: None,
+			max_interval: None,
 			cpus: Cpus {
 				use_sysfs: false,
+				container: false,
+				interval: Some(Interval(std::time::Duration::from_millis(250))),
 			},
 			hwmon: [
-				("cpu1".to_owned(), Hwmon::Name("k10temp".to_owned())),
-				("cpu2".to_owned(), Hwmon::Name("k10temp".to_owned())),
-				("gpu".to_owned(), Hwmon::Name("amdgpu".to_owned())),
-				("mobo".to_owned(), Hwmon::Name("nct6779".to_owned())),
+				("cpu1".to_owned(), Hwmon { dev_name: Some("k10temp".to_owned()), pci_slot: Some("0000:00:18.3".to_owned()), ..Default::default() }),
+				("cpu2".to_owned(), Hwmon { dev_name: Some("k10temp".to_owned()), pci_slot: Some("0000:00:19.3".to_owned()), ..Default::default() }),
+				("gpu".to_owned(), Hwmon { dev_name: Some("amdgpu".to_owned()), driver: Some("amdgpu".to_owned()), pci_vendor: Some(0x1002), optional: true, ..Default::default() }),
+				("mobo".to_owned(), Hwmon { dev_name: Some("nct6779".to_owned()), ..Default::default() }),
 			].into(),
 			power_supply: Default::default(),
 			sensors: vec![
 				InnerSensorGroup {
 					name: "CPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu1".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("Tdie".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("CPU 1".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu2".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("Tdie".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: Some("CPU 2".to_owned()),
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("SMBUSMASTER 0".to_owned()),
 							},
-							offset: Some(-27.0),
+							calibration: Calibration { offset: -27., ..Default::default() },
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
-					],
-					fans: vec![
-						InnerFanSensor {
-							hwmon: "mobo".to_owned(),
-							num_or_label: HwmonNumOrLabel::Num(2),
-							name: Some("Fan 1".to_owned()),
-						},
-					],
-					bats: vec![],
-				},
-				InnerSensorGroup {
-					name: "CPU".to_owned(),
-					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu1".to_owned(),
-								num_or_label: HwmonNumOrLabel::Label("Tccd1".to_owned()),
+								num_or_label: HwmonNumOrLabel::Label("Tccd*".to_owned()),
 							},
-							offset: Some(-27.0),
+							calibration: Calibration { offset: -27., ..Default::default() },
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "cpu2".to_owned(),
-								num_or_label: HwmonNumOrLabel::Label("Tccd1".to_owned()),
+								num_or_label: HwmonNumOrLabel::Label("Tccd*".to_owned()),
 							},
-							offset: Some(-27.0),
+							calibration: Calibration { offset: -27., ..Default::default() },
+							filter: Default::default(),
 							name: None,
+							optional: false,
+						},
+					],
+					fans: vec![
+						InnerFanSensor {
+							hwmon: "mobo".to_owned(),
+							num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(2)),
+							name: Some("Fan 1".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
 						},
 					],
-					fans: vec![],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![
+						InnerDerivedSensor {
+							expression: crate::derived::Expression::Function(
+								crate::derived::Function::Max,
+								vec![crate::derived::Expression::Sensor("Tccd*".to_owned())],
+							),
+							unit: Some("°C".to_owned()),
+							name: "Hottest CCD".to_owned(),
+							filter: Default::default(),
+						},
+					],
 				},
 				InnerSensorGroup {
 					name: "GPU".to_owned(),
+					interval: None,
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "gpu".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("edge".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "gpu".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("junction".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "gpu".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("mem".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 					],
 					fans: vec![
 						InnerFanSensor {
 							hwmon: "gpu".to_owned(),
-							num_or_label: HwmonNumOrLabel::Num(1),
+							num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							name: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 						},
 					],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![
+						InnerFileSensor {
+							path: "/sys/class/drm/card0/device/gpu_busy_percent".into(),
+							regex: None,
+							rate: false,
+							unit: Some("%".to_owned()),
+							name: Some("Busy".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
+						},
+					],
+					commands: vec![],
+					derived: vec![],
 				},
 				InnerSensorGroup {
 					name: "Mobo".to_owned(),
+					interval: Some(Interval(std::time::Duration::from_secs(5))),
 					temps: vec![
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("SYSTIN".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Default::default(),
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("AUXTIN1".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Filter { valid_range: Some((0., 110.)), ..Default::default() },
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("AUXTIN2".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Filter { valid_range: Some((0., 110.)), ..Default::default() },
 							name: None,
+							optional: false,
 						},
 						InnerTempSensor {
 							spec: InnerTempSensorSpec::Hwmon {
 								hwmon: "mobo".to_owned(),
 								num_or_label: HwmonNumOrLabel::Label("AUXTIN3".to_owned()),
 							},
-							offset: None,
+							calibration: Default::default(),
+							filter: Filter { valid_range: Some((0., 110.)), ..Default::default() },
 							name: None,
+							optional: false,
 						},
 					],
 					fans: vec![
 						InnerFanSensor {
 							hwmon: "mobo".to_owned(),
-							num_or_label: HwmonNumOrLabel::Num(1),
+							num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(1)),
 							name: Some("Front".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
 						},
 						InnerFanSensor {
 							hwmon: "mobo".to_owned(),
-							num_or_label: HwmonNumOrLabel::Num(4),
+							num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(4)),
 							name: Some("Side".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
 						},
 						InnerFanSensor {
 							hwmon: "mobo".to_owned(),
-							num_or_label: HwmonNumOrLabel::Num(5),
+							num_or_label: HwmonNumOrLabel::Num(HwmonNum::Num(5)),
 							name: Some("Rear".to_owned()),
+							calibration: Default::default(),
+							filter: Default::default(),
 						},
 					],
 					bats: vec![],
+					cooling: vec![],
+					files: vec![],
+					commands: vec![],
+					derived: vec![],
 				},
 			],
 			networks: vec![
-				"enp4s0".to_owned(),
+				InnerListEntry { name: "enp4s0".to_owned(), interval: None },
+			],
+			interrupts: Interrupts {
+				top: 5,
+				interval: None,
+			},
+			processes: Processes {
+				top: 10,
+				interval: None,
+			},
+			vmstat: Default::default(),
+			cgroups: vec![
+				InnerListEntry { name: "system.slice/docker.service".to_owned(), interval: None },
+				InnerListEntry { name: "user.slice".to_owned(), interval: None },
 			],
 		});
 	}
@@ -920,4 +2922,388 @@ mod tests {
 		let actual = toml::from_str(&actual).unwrap();
 		assert_eq!(*expected, actual);
 	}
+
+	#[test]
+	fn glob_matches() {
+		for (pattern, s, expected) in [
+			("Core 0", "Core 0", true),
+			("Core 0", "Core 01", false),
+			("Core *", "Core 12", true),
+			("Core *", "Package id 0", false),
+			("Tccd?", "Tccd1", true),
+			("Tccd?", "Tccd10", false),
+			("*die", "Tdie", true),
+			("T*d*", "Tccd1", true),
+			("*", "", true),
+			("?", "", false),
+		] {
+			assert_eq!(super::glob_matches(pattern, s), expected, "{pattern:?} {s:?}");
+		}
+	}
+
+	#[test]
+	fn natural_cmp() {
+		let mut labels = vec!["Core 10", "Core 2", "Core 1", "Package id 0", "Core 02", "Tccd11", "Tccd9"];
+		labels.sort_by(|a, b| super::natural_cmp(a, b));
+		assert_eq!(labels, ["Core 1", "Core 2", "Core 02", "Core 10", "Package id 0", "Tccd9", "Tccd11"]);
+	}
+
+	#[test]
+	fn resolve_thermal_types() {
+		let thermal_zones: Vec<_> =
+			[(0, "cpu-thermal"), (1, "gpu-thermal"), (2, "cpu-thermal"), (3, "cpu-thermal"), (5, "cpu-thermal")].into_iter()
+			.map(|(num, r#type)| super::ThermalZone { num, r#type: r#type.to_owned() })
+			.collect();
+
+		let resolve = |nums: &[u32], lookups: &[(&str, Option<usize>)]| super::resolve_thermal_types(&thermal_zones, nums, lookups);
+
+		// Lookups without an index get distinct zones of their type, in order of zone number.
+		assert_eq!(
+			resolve(&[], &[("cpu-thermal", None), ("gpu-thermal", None), ("cpu-thermal", None)]),
+			[Ok(0), Ok(1), Ok(2)],
+		);
+
+		// Zones that are referred to by number or by index are reserved before lookups without an index,
+		// even if those come first.
+		assert_eq!(
+			resolve(&[0], &[("cpu-thermal", None), ("cpu-thermal", Some(2)), ("cpu-thermal", None)]),
+			[Ok(2), Ok(3), Ok(5)],
+		);
+
+		assert_eq!(
+			resolve(&[3], &[("cpu-thermal", Some(2)), ("cpu-thermal", Some(0)), ("cpu-thermal", Some(0)), ("cpu-thermal", Some(4)), ("gpu-thermal", None), ("gpu-thermal", None)]),
+			[
+				Err("thermal zone of type cpu-thermal with index 2 is thermal_zone3 which is already used by another sensor".to_owned()),
+				Ok(0),
+				Err("thermal zone of type cpu-thermal with index 0 is thermal_zone0 which is already used by another sensor".to_owned()),
+				Err("could not find thermal zone of type cpu-thermal with index 4".to_owned()),
+				Ok(1),
+				Err("could not find unused thermal zone of type gpu-thermal".to_owned()),
+			],
+		);
+
+		let config: InnerConfig = toml::from_str(r#"
+			[[sensor]]
+			name = "SoC"
+			temps = [{ thermal_type = "cpu-thermal", index = 1 }, { thermal_type = "gpu-thermal" }]
+		"#).unwrap();
+		assert_eq!(config.sensors[0].temps.iter().map(|sensor| &sensor.spec).collect::<Vec<_>>(), [
+			&InnerTempSensorSpec::ThermalType { thermal_type: "cpu-thermal".to_owned(), index: Some(1) },
+			&InnerTempSensorSpec::ThermalType { thermal_type: "gpu-thermal".to_owned(), index: None },
+		]);
+	}
+
+	#[test]
+	fn match_hwmon_nodes() {
+		// A fake `/sys` with two NVMe drives behind PCI bridges, a USB PSU that is a HID device, and a virtual hwmon without a device
+		let dir = std::env::temp_dir().join(format!("sensord-hwmon-test-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		let sys_devices = dir.join("devices");
+		let path = |path: &str| sys_devices.join(path);
+		let device = |path: &str, subsystem: &str, files: &[(&str, &str)]| {
+			std::fs::create_dir_all(sys_devices.join(path)).unwrap();
+			std::os::unix::fs::symlink(dir.join("bus").join(subsystem), sys_devices.join(path).join("subsystem")).unwrap();
+			for (name, contents) in files {
+				std::fs::write(sys_devices.join(path).join(name), contents).unwrap();
+			}
+		};
+		let hwmon_node = |num: u32, name: &str, device: Option<&str>| {
+			let hwmon_node_dir = match device {
+				Some(device) => path(&format!("{device}/hwmon/hwmon{num}")),
+				None => path(&format!("virtual/hwmon/hwmon{num}")),
+			};
+			std::fs::create_dir_all(&hwmon_node_dir).unwrap();
+			if let Some(device) = device {
+				std::os::unix::fs::symlink(path(device), hwmon_node_dir.join("device")).unwrap();
+			}
+			(num, name.to_owned(), hwmon_node_dir)
+		};
+
+		device("pci0000:00/0000:00:01.1", "pci", &[("vendor", "0x1022\n"), ("device", "0x1483\n")]);
+		device("pci0000:00/0000:00:01.1/0000:01:00.0", "pci", &[("vendor", "0x144d\n"), ("device", "0xa808\n")]);
+		device("pci0000:00/0000:00:01.1/0000:01:00.0/nvme/nvme0", "nvme", &[]);
+		device("pci0000:00/0000:00:01.2", "pci", &[("vendor", "0x1022\n"), ("device", "0x1483\n")]);
+		device("pci0000:00/0000:00:01.2/0000:02:00.0", "pci", &[("vendor", "0x1e0f\n"), ("device", "0x0001\n")]);
+		device("pci0000:00/0000:00:01.2/0000:02:00.0/nvme/nvme1", "nvme", &[]);
+		device("pci0000:00/0000:00:14.0", "pci", &[("vendor", "0x8086\n"), ("device", "0x43ed\n")]);
+		device("pci0000:00/0000:00:14.0/usb1", "usb", &[("idVendor", "1d6b\n"), ("idProduct", "0002\n")]);
+		device("pci0000:00/0000:00:14.0/usb1/1-1", "usb", &[("idVendor", "1b1c\n"), ("idProduct", "1c05\n")]);
+		// A USB interface is on the `usb` bus too, but has no IDs of its own.
+		device("pci0000:00/0000:00:14.0/usb1/1-1/1-1:1.0", "usb", &[]);
+		device("pci0000:00/0000:00:14.0/usb1/1-1/1-1:1.0/0003:1B1C:1C05.0001", "hid", &[]);
+		std::os::unix::fs::symlink(dir.join("bus/hid/drivers/corsair-psu"), path("pci0000:00/0000:00:14.0/usb1/1-1/1-1:1.0/0003:1B1C:1C05.0001/driver")).unwrap();
+
+		let hwmon_nodes = vec![
+			hwmon_node(0, "nvme", Some("pci0000:00/0000:00:01.1/0000:01:00.0/nvme/nvme0")),
+			hwmon_node(1, "nvme", Some("pci0000:00/0000:00:01.2/0000:02:00.0/nvme/nvme1")),
+			hwmon_node(2, "corsairpsu", Some("pci0000:00/0000:00:14.0/usb1/1-1/1-1:1.0/0003:1B1C:1C05.0001")),
+			hwmon_node(3, "acpitz", None),
+		];
+
+		// The closest ancestor on the bus is found, not eg the PCI bridge or the USB root hub.
+		let nvme0 = path("pci0000:00/0000:00:01.1/0000:01:00.0/nvme/nvme0");
+		let psu = path("pci0000:00/0000:00:14.0/usb1/1-1/1-1:1.0/0003:1B1C:1C05.0001");
+		assert_eq!(super::device_ancestor_under(&sys_devices, &nvme0, "pci", "vendor"), Some(&*path("pci0000:00/0000:00:01.1/0000:01:00.0")));
+		assert_eq!(super::device_ancestor_under(&sys_devices, &psu, "usb", "idVendor"), Some(&*path("pci0000:00/0000:00:14.0/usb1/1-1")));
+		assert_eq!(super::device_ancestor_under(&sys_devices, &psu, "pci", "vendor"), Some(&*path("pci0000:00/0000:00:14.0")));
+		assert_eq!(super::device_ancestor_under(&sys_devices, &nvme0, "usb", "idVendor"), None);
+
+		// PCI IDs have a `0x` prefix and USB IDs don't.
+		assert_eq!(super::parse_device_id(&path("pci0000:00/0000:00:01.1/0000:01:00.0/vendor")), Some(0x144d));
+		assert_eq!(super::parse_device_id(&path("pci0000:00/0000:00:14.0/usb1/1-1/idProduct")), Some(0x1c05));
+		assert_eq!(super::parse_device_id(&path("pci0000:00/0000:00:14.0/usb1/1-1/1-1:1.0/idVendor")), None);
+
+		let matches = |hwmon: &Hwmon| -> Vec<u32> {
+			hwmon_nodes.iter()
+			.filter(|(_, name, hwmon_node_dir)| hwmon.matches(name, hwmon_node_dir, &sys_devices))
+			.map(|&(num, _, _)| num)
+			.collect()
+		};
+		assert_eq!(matches(&Hwmon { dev_name: Some("nvme".to_owned()), ..Default::default() }), [0, 1]);
+		assert_eq!(matches(&Hwmon { dev_name: Some("nvme".to_owned()), pci_vendor: Some(0x144d), pci_device: Some(0xa808), ..Default::default() }), [0]);
+		assert_eq!(matches(&Hwmon { pci_vendor: Some(0x1022), ..Default::default() }), [0_u32; 0]);
+		assert_eq!(matches(&Hwmon { pci_slot: Some("02:00.0".to_owned()), ..Default::default() }), [1]);
+		assert_eq!(matches(&Hwmon { pci_slot: Some("0000:01:00.0".to_owned()), ..Default::default() }), [0]);
+		assert_eq!(matches(&Hwmon { dev_path: Some(path("pci0000:00/0000:00:01.2")), ..Default::default() }), [1]);
+		assert_eq!(matches(&Hwmon { usb_vendor: Some(0x1b1c), usb_product: Some(0x1c05), ..Default::default() }), [2]);
+		assert_eq!(matches(&Hwmon { usb_vendor: Some(0x1d6b), ..Default::default() }), [0_u32; 0]);
+		assert_eq!(matches(&Hwmon { pci_vendor: Some(0x8086), ..Default::default() }), [2]);
+		assert_eq!(matches(&Hwmon { driver: Some("corsair-psu".to_owned()), ..Default::default() }), [2]);
+		assert_eq!(matches(&Hwmon { dev_name: Some("acpitz".to_owned()), ..Default::default() }), [3]);
+		assert_eq!(matches(&Hwmon { dev_name: Some("acpitz".to_owned()), pci_vendor: Some(0x8086), ..Default::default() }), [0_u32; 0]);
+
+		// `second` is matched before `first` because it's more specific, so `first` doesn't take its device even though it comes first by name.
+		// `third` is as specific as `first`, so it's matched after it and there is no unused device left for it.
+		let hwmon: std::collections::BTreeMap<_, _> = [
+			("first", Hwmon { dev_name: Some("nvme".to_owned()), ..Default::default() }),
+			("second", Hwmon { dev_name: Some("nvme".to_owned()), pci_slot: Some("01:00.0".to_owned()), ..Default::default() }),
+			("third", Hwmon { dev_name: Some("nvme".to_owned()), ..Default::default() }),
+		].into_iter().map(|(name, hwmon)| (name.to_owned(), hwmon)).collect();
+		let resolved: Vec<_> =
+			super::match_hwmon_nodes(hwmon, hwmon_nodes.clone(), &sys_devices).into_iter()
+			.map(|(name, _, hwmon_node_dir)| (name, hwmon_node_dir))
+			.collect();
+		assert_eq!(resolved, [
+			("second".to_owned(), Some(hwmon_nodes[0].2.clone())),
+			("first".to_owned(), Some(hwmon_nodes[1].2.clone())),
+			("third".to_owned(), None),
+		]);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn merge() {
+		let files = super::Files(vec![
+			(
+				"/etc/sensord/config.toml".into(),
+				r#"
+					networks = ["enp4s0"]
+					interval = 2
+
+					[hwmon]
+					cpu = { dev_name = "k10temp" }
+
+					[[sensor]]
+					name = "CPU"
+					temps = [{ hwmon = "cpu", label = "Tctl" }]
+				"#.to_owned(),
+			),
+			(
+				"/etc/sensord/config.d/10-gpu.toml".into(),
+				r#"
+					[hwmon]
+					gpu = { dev_name = "amdgpu" }
+
+					[[sensor]]
+					name = "GPU"
+					temps = [{ hwmon = "gpu", label = "edge" }]
+				"#.to_owned(),
+			),
+		]);
+
+		let (config, sources) = files.merge().unwrap();
+		assert_eq!(config.interval, Some(2.));
+		assert_eq!(config.hwmon.keys().collect::<Vec<_>>(), ["cpu", "gpu"]);
+		assert_eq!(config.sensors.iter().map(|sensor_group| &*sensor_group.name).collect::<Vec<_>>(), ["CPU", "GPU"]);
+		assert_eq!(config.networks.iter().map(|network| &*network.name).collect::<Vec<_>>(), ["enp4s0"]);
+		assert_eq!(sources.hwmon["gpu"].to_string(), "/etc/sensord/config.d/10-gpu.toml:3:12");
+		assert_eq!(
+			sources.sensors.iter().map(|sensor_group| sensor_group.location.path.as_path()).collect::<Vec<_>>(),
+			[std::path::Path::new("/etc/sensord/config.toml"), std::path::Path::new("/etc/sensord/config.d/10-gpu.toml")],
+		);
+		assert_eq!(sources.sensors[1].temps[0].to_string(), "/etc/sensord/config.d/10-gpu.toml:7:15");
+
+		let files = super::Files(vec![
+			("/etc/sensord/config.toml".into(), "interval = 2\n[hwmon]\ngpu = { dev_name = \"amdgpu\" }\n".to_owned()),
+			("/etc/sensord/config.d/10-gpu.toml".into(), "[hwmon]\ngpu = { dev_name = \"nouveau\" }\n".to_owned()),
+		]);
+		let err = files.merge().unwrap_err().to_string();
+		assert_eq!(err, "error for path /etc/sensord/config.d/10-gpu.toml: line 2, column 7: hwmon \"gpu\": already defined at /etc/sensord/config.toml:3:7\n");
+
+		let files = super::Files(vec![
+			("/etc/sensord/config.toml".into(), "interval = 2\n".to_owned()),
+			("/etc/sensord/config.d/10-fast.toml".into(), "interval = 0.5\n".to_owned()),
+		]);
+		let err = files.merge().unwrap_err().to_string();
+		assert_eq!(err, "error for path /etc/sensord/config.d/10-fast.toml: interval is already set in /etc/sensord/config.toml\n");
+	}
+
+	#[test]
+	fn strict() {
+		for (config, expected) in [
+			("intreval = 2", "unknown field `intreval`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ hwmon = "cpu", lable = "Tdie" }] }]"#, "unknown field `lable`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ hwmon = "cpu" }] }]"#, "hwmon sensor must have one of `num` or `label`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ hwmon = "cpu", num = 1, label = "Tdie" }] }]"#, "hwmon sensor can only have one of `num` or `label`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ label = "Tdie" }] }]"#, "`num` and `label` can only be used with `hwmon`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ hwmon = "cpu", label = "Tccd*", name = "CCD" }] }]"#, "`name` can't be used with `num = \"all\"` or a label pattern"),
+			(r#"sensor = [{ name = "Mobo", fans = [{ hwmon = "mobo", num = "all", name = "Fan" }] }]"#, "`name` can't be used with `num = \"all\"` or a label pattern"),
+			(r#"sensor = [{ name = "CPU", temps = [{ name = "CPU" }] }]"#, "temp sensor must have one of `hwmon`, `thermal_zone` or `thermal_type`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ thermal_zone = 0, index = 1 }] }]"#, "`index` can only be used with `thermal_type`"),
+			(r#"sensor = [{ name = "CPU", temps = [{ hwmon = "cpu", label = "Tdie", optional = true }] }]"#, "`optional` can only be used with `thermal_zone` or `thermal_type`"),
+			(r#"sensor = [{ name = "CPU", fans = [{ hwmon = "cpu", num = 1, pwm = 1 }] }]"#, "unknown field `pwm`"),
+			(r#"sensor = [{ name = "CPU", cooling = [{ cooling_device = 0, scale = 2.0 }] }]"#, "`scale`, `offset` and `invert` cannot be set on cooling devices"),
+			(r#"power_supply = { bat = { name = "BAT0" } }"#, "unknown field `name`"),
+			(r#"sensor = [{ name = "GPU", files = [{ path = "gpu_busy_percent" }] }]"#, "`path` must be an absolute path"),
+			(r#"sensor = [{ name = "Disk", commands = [{ command = [] }] }]"#, "`command` must not be empty"),
+			(r#"sensor = [{ name = "Disk", commands = [{ command = ["nvme"], key = "a", json = "a" }] }]"#, "command sensor can only have one of `regex`, `key` or `json`"),
+			(r#"sensor = [{ name = "Disk", commands = [{ command = ["nvme"], timeout = 0 }] }]"#, "`timeout` must be a positive number of seconds"),
+			(r#"sensor = [{ name = "Disk", commands = [{ command = ["nvme"], interval = 1e20 }] }]"#, "`interval` must be a positive number of seconds"),
+			(r#"sensor = [{ name = "CPU", derived = [{ name = "CCD", max = ["Tccd*"], min = ["Tccd*"] }] }]"#, "derived sensor must have exactly one of"),
+			(r#"sensor = [{ name = "CPU", derived = [{ name = "CCD", avg = [] }] }]"#, "`avg` must not be empty"),
+			(r#"sensor = [{ name = "Mobo", temps = [{ hwmon = "mobo", num = 1, valid_range = [110, 0] }] }]"#, "`valid_range` must be `[min, max]` with `min` <= `max`"),
+			(r#"sensor = [{ name = "Mobo", fans = [{ hwmon = "mobo", num = 1, ema = 0.3, median = 5 }] }]"#, "sensor can only have one of `ema` or `median`"),
+			(r#"sensor = [{ name = "Mobo", fans = [{ hwmon = "mobo", num = 1, ema = 1.5 }] }]"#, "`ema` must be greater than 0 and at most 1"),
+			(r#"sensor = [{ name = "CPU", derived = [{ name = "CCD", expr = "max({Tccd*}" }] }]"#, "expected ')' at position 11 of expression"),
+		] {
+			let err = toml::from_str::<super::InnerConfig>(config).unwrap_err().to_string();
+			assert!(err.contains(expected), "{config:?} {err:?}");
+		}
+	}
+
+	#[test]
+	fn calibration() {
+		let config: InnerConfig = toml::from_str(r#"
+			[[sensor]]
+			name = "Mobo"
+			temps = [{ hwmon = "mobo", num = 1, invert = true, offset = 100 }]
+			fans = [{ hwmon = "mobo", num = 2, scale = 2 }]
+			bats = [{ power_supply = "bat", scale = 1.25 }]
+		"#).unwrap();
+		let sensor_group = &config.sensors[0];
+
+		let calibration = sensor_group.temps[0].calibration;
+		assert!((calibration.apply(35.) - 65.).abs() < f64::EPSILON);
+		assert_eq!(calibration.to_string(), " inverted offset +100");
+
+		let calibration = sensor_group.fans[0].calibration;
+		assert_eq!(calibration.apply_u16(600), 1200);
+		assert_eq!(calibration.apply_u16(40000), u16::MAX);
+		assert_eq!(calibration.to_string(), " scale 2");
+
+		let calibration = sensor_group.bats[0].calibration;
+		assert_eq!(calibration.apply_u8(50), 63);
+
+		assert_eq!(Calibration::default().to_string(), "");
+
+		let err = toml::from_str::<InnerConfig>(r#"sensor = [{ name = "Mobo", fans = [{ hwmon = "mobo", num = 2, scale = nan }] }]"#).unwrap_err().to_string();
+		assert!(err.contains("`scale` and `offset` must be finite numbers"), "{err:?}");
+	}
+
+	#[test]
+	fn derived() {
+		let files = super::Files(vec![("/etc/sensord/config.toml".into(), r#"
+[[sensor]]
+name = "GPU"
+files = [{ path = "/sys/class/drm/card0/device/gpu_busy_percent", name = "Busy" }]
+derived = [{ name = "Idle", expr = "100 - {Busy}" }]
+
+[[sensor]]
+name = "Disk"
+files = [{ path = "/sys/block/nvme0n1/stat", name = "nvme0" }, { path = "/sys/block/nvme1n1/stat", name = "nvme1" }]
+derived = [
+	{ name = "Total", sum = ["nvme*", "GPU/Idle"] },
+	{ name = "Difference", diff = ["nvme0", "nvme1"] },
+]
+"#.to_owned())]);
+		let config = files.resolve().unwrap();
+
+		let derived: Vec<_> = config.sensors.iter().flat_map(|sensor_group| &sensor_group.derived).map(|sensor| sensor.expression.to_string()).collect();
+		assert_eq!(derived, [
+			"(100 - {GPU/Busy})",
+			"sum({Disk/nvme0}, {Disk/nvme1}, {GPU/Idle})",
+			"({Disk/nvme0} - {Disk/nvme1})",
+		]);
+
+		// References that don't match any sensor are unavailable rather than errors.
+		// Derived sensors can only refer to derived sensors that are defined before them.
+		for (derived, expected) in [
+			(r#"{ name = "Total", sum = ["nvme*", "nvme2"] }"#, "sum({Disk/nvme0}, {Disk/nvme1}, {nvme2})"),
+			(r#"{ name = "Total", diff = ["Difference", "nvme0"] }, { name = "Difference", diff = ["nvme0", "nvme1"] }"#, "({Difference} - {Disk/nvme0})"),
+		] {
+			let files = super::Files(vec![("/etc/sensord/config.toml".into(), format!(r#"
+[[sensor]]
+name = "Disk"
+files = [{{ path = "/sys/block/nvme0n1/stat", name = "nvme0" }}, {{ path = "/sys/block/nvme1n1/stat", name = "nvme1" }}]
+derived = [{derived}]
+"#))]);
+			let config = files.resolve().unwrap();
+			assert_eq!(config.sensors[0].derived[0].expression.to_string(), expected, "{derived:?}");
+		}
+
+		let files = super::Files(vec![("/etc/sensord/config.toml".into(), r#"
+[[sensor]]
+name = "Disk"
+files = [{ path = "/sys/block/nvme0n1/stat", name = "nvme0" }, { path = "/sys/block/nvme1n1/stat", name = "nvme1" }]
+derived = [{ name = "Total", expr = "{nvme*} + 1" }]
+"#.to_owned())]);
+		let err = files.resolve().unwrap_err().to_string();
+		assert!(err.contains(r#"line 5, column 12: [[sensor]] group "Disk": {nvme*} matches 2 sensors, but only one can be used outside of max, min, avg and sum"#), "{err:?}");
+	}
+
+	#[test]
+	fn intervals() {
+		let files = super::Files(vec![("/etc/sensord/config.toml".into(), r#"
+interval = 2
+networks = ["eth0", { name = "wlan0", interval = 0.5 }]
+cgroups = [{ name = "user.slice", interval = 10 }]
+
+[cpus]
+interval = 0.25
+
+[vmstat]
+interval = 5
+
+[[sensor]]
+name = "Battery"
+interval = 30
+"#.to_owned())]);
+		let config = files.resolve().unwrap();
+
+		let secs = std::time::Duration::from_secs_f64;
+		assert_eq!(config.interval, secs(2.));
+		assert_eq!((config.min_interval, config.max_interval), (secs(0.25), secs(10.)));
+		assert_eq!(config.cpus_interval, Some(secs(0.25)));
+		assert_eq!(config.interrupts_interval, None);
+		assert_eq!(config.processes_interval, None);
+		assert_eq!(config.vmstat_interval, Some(secs(5.)));
+		assert_eq!(config.sensors[0].interval, Some(secs(30.)));
+		assert_eq!(config.networks.iter().map(|network| network.interval).collect::<Vec<_>>(), [None, Some(secs(0.5))]);
+		assert_eq!(config.cgroups[0].interval, Some(secs(10.)));
+		assert_eq!(config.tick_interval(config.interval), secs(0.25));
+		// An interval requested by a client only applies to the sections that don't set their own.
+		assert_eq!(config.tick_interval(secs(0.1)), secs(0.1));
+
+		for (section, expected) in [
+			("[cpus]\ninterval = 0", "`interval` must be a positive number of seconds"),
+			("[processes]\ninterval = -1", "`interval` must be a positive number of seconds"),
+			("networks = [{ name = \"eth0\", rate = 1 }]", "unknown field `rate`"),
+			("min_interval = 5\nmax_interval = 1", "`min_interval` must not be greater than `max_interval`"),
+		] {
+			let files = super::Files(vec![("/etc/sensord/config.toml".into(), section.to_owned())]);
+			let err = files.resolve().unwrap_err().to_string();
+			assert!(err.contains(expected), "{section:?} {err:?}");
+		}
+	}
 }
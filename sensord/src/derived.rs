@@ -0,0 +1,433 @@
+// Derived sensors, whose values are computed from the values of other sensors.
+//
+// The value is an expression of numbers, references to other sensors like `{CPU/Tccd1}`, the operators `+`, `-`, `*` and `/`,
+// parentheses, and the functions `max`, `min`, `avg` and `sum`.
+//
+// A reference is `{group/name}`, or `{name}` for a sensor in the same group, where both the group and the name can be patterns
+// with `*` and `?` like labels of hwmon sensors. Inside a function, a reference expands to every matching sensor.
+// Everywhere else, a reference must match at most one sensor.
+//
+// A reference that doesn't match any sensor, eg because the sensors it refers to are optional or the device only has some of them,
+// isn't an error. Its value is unavailable, just like the value of a sensor that is unavailable.
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Expression<R> {
+	Number(f64),
+	Sensor(R),
+	// A reference that didn't match any sensor
+	Unmatched(String),
+	Negate(Box<Self>),
+	Binary(Box<Self>, Operator, Box<Self>),
+	Function(Function, Vec<Self>),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Operator {
+	Add,
+	Subtract,
+	Multiply,
+	Divide,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Function {
+	Max,
+	Min,
+	Avg,
+	Sum,
+}
+
+impl Function {
+	pub(crate) fn name(self) -> &'static str {
+		match self {
+			Function::Max => "max",
+			Function::Min => "min",
+			Function::Avg => "avg",
+			Function::Sum => "sum",
+		}
+	}
+}
+
+// A sensor that is referenced by a derived sensor, resolved to where its value is in `sensord_common::SensorGroup`
+#[derive(Clone, Debug)]
+pub(crate) struct SensorRef {
+	// `group/name`, for messages
+	pub(crate) name: String,
+	pub(crate) group: usize,
+	pub(crate) kind: SensorKind,
+	pub(crate) index: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum SensorKind {
+	Temp,
+	Fan,
+	Bat,
+	Cooling,
+	Value,
+}
+
+pub(crate) fn parse(s: &str) -> Result<Expression<String>, String> {
+	let mut parser = Parser { s, pos: 0 };
+	let expression = parser.expression()?;
+	parser.skip_whitespace();
+	if parser.pos < s.len() {
+		return Err(format!("unexpected {:?} at position {} of expression", &s[parser.pos..], parser.pos));
+	}
+	Ok(expression)
+}
+
+impl Expression<String> {
+	// Resolves every reference to the sensors that it matches.
+	pub(crate) fn resolve(self, mut matches: impl FnMut(&str) -> Result<Vec<SensorRef>, String>) -> Result<Expression<SensorRef>, String> {
+		self.resolve_inner(&mut matches)
+	}
+
+	fn resolve_inner(self, matches: &mut impl FnMut(&str) -> Result<Vec<SensorRef>, String>) -> Result<Expression<SensorRef>, String> {
+		Ok(match self {
+			Expression::Number(value) => Expression::Number(value),
+
+			Expression::Sensor(reference) => {
+				let mut sensors = matches(&reference)?;
+				match sensors.len() {
+					0 => Expression::Unmatched(reference),
+					1 => Expression::Sensor(sensors.remove(0)),
+					len => return Err(format!("{{{reference}}} matches {len} sensors, but only one can be used outside of max, min, avg and sum")),
+				}
+			},
+
+			Expression::Unmatched(reference) => Expression::Unmatched(reference),
+
+			Expression::Negate(operand) => Expression::Negate(Box::new(operand.resolve_inner(matches)?)),
+
+			Expression::Binary(left, operator, right) =>
+				Expression::Binary(Box::new(left.resolve_inner(matches)?), operator, Box::new(right.resolve_inner(matches)?)),
+
+			Expression::Function(function, args) => {
+				let mut resolved_args = vec![];
+				for arg in args {
+					match arg {
+						Expression::Sensor(reference) => {
+							let sensors = matches(&reference)?;
+							if sensors.is_empty() {
+								resolved_args.push(Expression::Unmatched(reference));
+							}
+							else {
+								resolved_args.extend(sensors.into_iter().map(Expression::Sensor));
+							}
+						},
+						arg => resolved_args.push(arg.resolve_inner(matches)?),
+					}
+				}
+				Expression::Function(function, resolved_args)
+			},
+		})
+	}
+}
+
+impl Expression<SensorRef> {
+	// Sensors that are unavailable or failed to be read are skipped by functions, so that eg the max of the CCD temps is still shown
+	// when one of the CCDs' sensors can't be read. Everywhere else they make the whole value fail.
+	pub(crate) fn evaluate(&self, sensor_groups: &[sensord_common::SensorGroup<'_>]) -> Result<f64, String> {
+		match self {
+			Expression::Number(value) => Ok(*value),
+
+			Expression::Sensor(sensor) => sensor_value(sensor, sensor_groups),
+
+			Expression::Unmatched(reference) => Err(format!("{{{reference}}} does not match any sensor")),
+
+			Expression::Negate(operand) => Ok(-operand.evaluate(sensor_groups)?),
+
+			Expression::Binary(left, operator, right) => {
+				let left = left.evaluate(sensor_groups)?;
+				let right = right.evaluate(sensor_groups)?;
+				match operator {
+					Operator::Add => Ok(left + right),
+					Operator::Subtract => Ok(left - right),
+					Operator::Multiply => Ok(left * right),
+					Operator::Divide if right == 0. => Err("division by zero".to_owned()),
+					Operator::Divide => Ok(left / right),
+				}
+			},
+
+			Expression::Function(function, args) => {
+				let mut values = args.iter().filter_map(|arg| arg.evaluate(sensor_groups).ok());
+				let first = values.next().ok_or_else(|| format!("none of the values of {} are available", function.name()))?;
+				let (result, count) = values.fold((first, 1_u32), |(result, count), value| {
+					let result = match function {
+						Function::Max => result.max(value),
+						Function::Min => result.min(value),
+						Function::Avg | Function::Sum => result + value,
+					};
+					(result, count + 1)
+				});
+				Ok(if *function == Function::Avg { result / f64::from(count) } else { result })
+			},
+		}
+	}
+}
+
+impl std::fmt::Display for Expression<SensorRef> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Expression::Number(value) => write!(f, "{value}"),
+			Expression::Sensor(sensor) => write!(f, "{{{}}}", sensor.name),
+			Expression::Unmatched(reference) => write!(f, "{{{reference}}}"),
+			Expression::Negate(operand) => write!(f, "-{operand}"),
+			Expression::Binary(left, operator, right) => {
+				let operator = match operator {
+					Operator::Add => '+',
+					Operator::Subtract => '-',
+					Operator::Multiply => '*',
+					Operator::Divide => '/',
+				};
+				write!(f, "({left} {operator} {right})")
+			},
+			Expression::Function(function, args) => {
+				write!(f, "{}(", function.name())?;
+				for (i, arg) in args.iter().enumerate() {
+					if i > 0 {
+						f.write_str(", ")?;
+					}
+					write!(f, "{arg}")?;
+				}
+				f.write_str(")")
+			},
+		}
+	}
+}
+
+fn sensor_value(sensor: &SensorRef, sensor_groups: &[sensord_common::SensorGroup<'_>]) -> Result<f64, String> {
+	let sensor_group = &sensor_groups[sensor.group];
+	let (available, error, value) = match sensor.kind {
+		SensorKind::Temp => sensor_group.temps.get(sensor.index).map(|sensor| (sensor.available, &sensor.error, sensor.value)),
+		SensorKind::Fan => sensor_group.fans.get(sensor.index).map(|sensor| (sensor.available, &sensor.error, sensor.fan.into())),
+		SensorKind::Bat => sensor_group.bats.get(sensor.index).map(|sensor| (sensor.available, &sensor.error, sensor.capacity.into())),
+		SensorKind::Cooling => sensor_group.cooling.get(sensor.index).map(|sensor| (sensor.available, &sensor.error, sensor.cur_state.into())),
+		SensorKind::Value => sensor_group.values.get(sensor.index).map(|sensor| (sensor.available, &sensor.error, sensor.value)),
+	}
+	.ok_or_else(|| format!("{{{}}} does not exist", sensor.name))?;
+
+	if !available {
+		return Err(format!("{{{}}} is unavailable", sensor.name));
+	}
+	if !error.is_empty() {
+		return Err(format!("{{{}}} could not be read", sensor.name));
+	}
+	Ok(value)
+}
+
+struct Parser<'a> {
+	s: &'a str,
+	pos: usize,
+}
+
+impl Parser<'_> {
+	fn peek(&mut self) -> Option<char> {
+		self.skip_whitespace();
+		self.s[self.pos..].chars().next()
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = &self.s[self.pos..];
+		self.pos += rest.len() - rest.trim_start().len();
+	}
+
+	fn expect(&mut self, expected: char) -> Result<(), String> {
+		if self.peek() == Some(expected) {
+			self.pos += expected.len_utf8();
+			Ok(())
+		}
+		else {
+			Err(format!("expected {expected:?} at position {} of expression", self.pos))
+		}
+	}
+
+	// expression = term (("+" | "-") term)*
+	fn expression(&mut self) -> Result<Expression<String>, String> {
+		let mut result = self.term()?;
+		loop {
+			let operator = match self.peek() {
+				Some('+') => Operator::Add,
+				Some('-') => Operator::Subtract,
+				_ => break Ok(result),
+			};
+			self.pos += 1;
+			result = Expression::Binary(Box::new(result), operator, Box::new(self.term()?));
+		}
+	}
+
+	// term = factor (("*" | "/") factor)*
+	fn term(&mut self) -> Result<Expression<String>, String> {
+		let mut result = self.factor()?;
+		loop {
+			let operator = match self.peek() {
+				Some('*') => Operator::Multiply,
+				Some('/') => Operator::Divide,
+				_ => break Ok(result),
+			};
+			self.pos += 1;
+			result = Expression::Binary(Box::new(result), operator, Box::new(self.factor()?));
+		}
+	}
+
+	// factor = number | "{" reference "}" | function "(" expression ("," expression)* ")" | "(" expression ")" | "-" factor
+	fn factor(&mut self) -> Result<Expression<String>, String> {
+		match self.peek() {
+			Some('-') => {
+				self.pos += 1;
+				Ok(Expression::Negate(Box::new(self.factor()?)))
+			},
+
+			Some('(') => {
+				self.pos += 1;
+				let result = self.expression()?;
+				self.expect(')')?;
+				Ok(result)
+			},
+
+			Some('{') => {
+				self.pos += 1;
+				let len = self.s[self.pos..].find('}').ok_or_else(|| format!("unterminated sensor reference at position {}", self.pos - 1))?;
+				let reference = self.s[self.pos..(self.pos + len)].trim().to_owned();
+				self.pos += len + 1;
+				Ok(Expression::Sensor(reference))
+			},
+
+			Some('0'..='9' | '.') => {
+				let start = self.pos;
+				let rest = &self.s[start..];
+				let mut len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+				// An exponent, eg `1e-3`
+				if let Some(exponent) = rest[len..].strip_prefix(['e', 'E']) {
+					let sign_len = usize::from(exponent.starts_with(['+', '-']));
+					let digits_len = exponent[sign_len..].find(|c: char| !c.is_ascii_digit()).unwrap_or(exponent.len() - sign_len);
+					if digits_len > 0 {
+						len += 1 + sign_len + digits_len;
+					}
+				}
+				self.pos += len;
+				let value = rest[..len].parse().map_err(|_| format!("invalid number {:?} at position {start} of expression", &rest[..len]))?;
+				Ok(Expression::Number(value))
+			},
+
+			Some(c) if c.is_ascii_alphabetic() => {
+				let start = self.pos;
+				let rest = &self.s[start..];
+				let len = rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(rest.len());
+				let function = match &rest[..len] {
+					"max" => Function::Max,
+					"min" => Function::Min,
+					"avg" => Function::Avg,
+					"sum" => Function::Sum,
+					name => return Err(format!("unknown function {name:?} at position {start} of expression, expected one of max, min, avg or sum")),
+				};
+				self.pos += len;
+
+				self.expect('(')?;
+				let mut args = vec![self.expression()?];
+				while self.peek() == Some(',') {
+					self.pos += 1;
+					args.push(self.expression()?);
+				}
+				self.expect(')')?;
+
+				Ok(Expression::Function(function, args))
+			},
+
+			Some(c) => Err(format!("unexpected {c:?} at position {} of expression", self.pos)),
+
+			None => Err("unexpected end of expression".to_owned()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Expression, Function, Operator};
+
+	#[test]
+	fn parse() {
+		assert_eq!(
+			super::parse("({CPU/CPU 1} + {CPU/CPU 2}) / 2 - -1.5e1").unwrap(),
+			Expression::Binary(
+				Box::new(Expression::Binary(
+					Box::new(Expression::Binary(
+						Box::new(Expression::Sensor("CPU/CPU 1".to_owned())),
+						Operator::Add,
+						Box::new(Expression::Sensor("CPU/CPU 2".to_owned())),
+					)),
+					Operator::Divide,
+					Box::new(Expression::Number(2.)),
+				)),
+				Operator::Subtract,
+				Box::new(Expression::Negate(Box::new(Expression::Number(15.)))),
+			),
+		);
+
+		assert_eq!(
+			super::parse("max({Tccd*}, 40)").unwrap(),
+			Expression::Function(Function::Max, vec![Expression::Sensor("Tccd*".to_owned()), Expression::Number(40.)]),
+		);
+
+		for (s, expected) in [
+			("1 +", "unexpected end of expression"),
+			("{CPU", "unterminated sensor reference at position 0"),
+			("median({a})", r#"unknown function "median" at position 0 of expression, expected one of max, min, avg or sum"#),
+			("(1 + 2", "expected ')' at position 6 of expression"),
+			("1 2", r#"unexpected "2" at position 2 of expression"#),
+		] {
+			assert_eq!(super::parse(s).unwrap_err(), expected, "{s:?}");
+		}
+	}
+
+	#[test]
+	fn evaluate() {
+		let temp = |name: &str, value, error: &str| sensord_common::TempSensor {
+			name: name.to_owned().into(),
+			available: true,
+			error: error.to_owned().into(),
+			value,
+			trip_points: vec![],
+			policy: "".into(),
+			mode: "".into(),
+		};
+		let sensor_groups = [sensord_common::SensorGroup {
+			name: "CPU".into(),
+			timestamp: 0,
+			temps: vec![temp("Tccd1", 50., ""), temp("Tccd2", 60., ""), temp("Tccd3", 70., "EIO")],
+			fans: vec![],
+			bats: vec![],
+			cooling: vec![],
+			values: vec![],
+		}];
+
+		let matches = |reference: &str| -> Result<Vec<super::SensorRef>, String> {
+			Ok(
+				sensor_groups[0].temps.iter().enumerate()
+				.filter(|(_, sensor)| crate::config::glob_matches(reference, &sensor.name))
+				.map(|(index, sensor)| super::SensorRef { name: format!("CPU/{}", sensor.name), group: 0, kind: super::SensorKind::Temp, index })
+				.collect(),
+			)
+		};
+
+		for (s, expected) in [
+			("max({Tccd*})", Ok(60.)),
+			("avg({Tccd*})", Ok(55.)),
+			("sum({Tccd1}, {Tccd2}, 5)", Ok(115.)),
+			("{Tccd2} - {Tccd1}", Ok(10.)),
+			("{Tccd3} + 1", Err("{CPU/Tccd3} could not be read".to_owned())),
+			("min({Tccd3})", Err("none of the values of min are available".to_owned())),
+			("{Tccd1} / ({Tccd1} - 50)", Err("division by zero".to_owned())),
+			("{Tctl} + 1", Err("{Tctl} does not match any sensor".to_owned())),
+			("max({Tccd*}, {Tdie*})", Ok(60.)),
+			("max({Tdie*})", Err("none of the values of max are available".to_owned())),
+		] {
+			let expression = super::parse(s).unwrap().resolve(matches).unwrap();
+			assert_eq!(expression.evaluate(&sensor_groups), expected, "{s:?}");
+		}
+
+		let err = super::parse("{Tccd*} + 1").unwrap().resolve(matches).unwrap_err();
+		assert_eq!(err, "{Tccd*} matches 3 sensors, but only one can be used outside of max, min, avg and sum");
+	}
+}
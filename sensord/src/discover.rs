@@ -0,0 +1,528 @@
+// Implements `sensord discover`, which prints a starter config for the running system.
+//
+// All hwmon devices and their channels, thermal zones, batteries and network interfaces are enumerated along with their current readings.
+// The sensors are sorted into CPU, GPU, Disk, Mobo and Bat groups based on the names of their devices. The result is meant to be edited by hand,
+// so everything that was found is mentioned in it, and entries that are probably not useful are commented out rather than left out.
+pub(crate) fn print() -> Result<(), crate::Error> {
+	let system = System::scan()?;
+	let mut config = String::new();
+	render(&system, &mut config).expect("writing to a String cannot fail");
+	std::io::Write::write_all(&mut std::io::stdout().lock(), config.as_bytes()).map_err(|err| crate::Error::Other(err.into()))?;
+	Ok(())
+}
+
+#[derive(Debug, Default)]
+struct System {
+	hwmons: Vec<Hwmon>,
+	thermal_zones: Vec<ThermalZone>,
+	batteries: Vec<Battery>,
+	networks: Vec<Network>,
+}
+
+#[derive(Debug)]
+struct Hwmon {
+	num: u32,
+	name: String,
+	// The canonical path of the hwmon device, eg `/sys/devices/pci0000:00/0000:00:18.3/hwmon/hwmon2`
+	dir: std::path::PathBuf,
+	// The canonical path of the device that the hwmon belongs to, if any.
+	device: Option<std::path::PathBuf>,
+	pci_slot: Option<String>,
+	is_power_supply: bool,
+	channels: Vec<Channel>,
+}
+
+#[derive(Debug)]
+struct Channel {
+	kind: ChannelKind,
+	num: u32,
+	label: Option<String>,
+	// Scaled to °C, RPM, V or W
+	value: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum ChannelKind {
+	Temp,
+	Fan,
+	In,
+	Power,
+}
+
+#[derive(Debug)]
+struct ThermalZone {
+	num: u32,
+	r#type: String,
+	temp: Option<f64>,
+}
+
+#[derive(Debug)]
+struct Battery {
+	name: String,
+	capacity: Option<u8>,
+	status: Option<String>,
+}
+
+#[derive(Debug)]
+struct Network {
+	name: String,
+	is_virtual: bool,
+}
+
+impl System {
+	fn scan() -> Result<Self, crate::Error> {
+		let mut system: System = Default::default();
+
+		for (num, dir) in class_devices("hwmon", "hwmon")? {
+			let Some(name) = read_trimmed(&dir.join("name")) else { continue; };
+			let device = std::fs::canonicalize(dir.join("device")).ok();
+			let pci_slot =
+				device.as_deref()
+				.and_then(|device| crate::config::device_ancestor(device, "pci", "vendor"))
+				.and_then(|pci_device| pci_device.file_name()?.to_str().map(ToOwned::to_owned));
+			let is_power_supply = device.as_deref().is_some_and(|device| subsystem(device).as_deref() == Some("power_supply"));
+			let channels = hwmon_channels(&dir)?;
+			system.hwmons.push(Hwmon { num, name, dir, device, pci_slot, is_power_supply, channels });
+		}
+
+		for (num, dir) in class_devices("thermal", "thermal_zone")? {
+			let Some(r#type) = read_trimmed(&dir.join("type")) else { continue; };
+			#[allow(clippy::cast_precision_loss)]
+			let temp = read_trimmed(&dir.join("temp")).and_then(|temp| temp.parse::<i64>().ok()).map(|temp| temp as f64 / 1000.);
+			system.thermal_zones.push(ThermalZone { num, r#type, temp });
+		}
+
+		let power_supply_class_dir = std::path::Path::new("/sys/class/power_supply");
+		if power_supply_class_dir.exists() {
+			for entry in crate::std2::fs::read_dir(power_supply_class_dir)? {
+				let dir = entry?.path();
+				if read_trimmed(&dir.join("type")).as_deref() != Some("Battery") {
+					continue;
+				}
+				let Some(name) = dir.file_name().and_then(std::ffi::OsStr::to_str) else { continue; };
+				system.batteries.push(Battery {
+					name: name.to_owned(),
+					capacity: read_trimmed(&dir.join("capacity")).and_then(|capacity| capacity.parse().ok()),
+					status: read_trimmed(&dir.join("status")),
+				});
+			}
+			system.batteries.sort_by(|a, b| crate::config::natural_cmp(&a.name, &b.name));
+		}
+
+		let net_class_dir = std::path::Path::new("/sys/class/net");
+		if net_class_dir.exists() {
+			for entry in crate::std2::fs::read_dir(net_class_dir)? {
+				let dir = entry?.path();
+				let Some(name) = dir.file_name().and_then(std::ffi::OsStr::to_str) else { continue; };
+				let is_virtual = std::fs::canonicalize(&dir).map_or(true, |dir| dir.starts_with("/sys/devices/virtual"));
+				system.networks.push(Network { name: name.to_owned(), is_virtual });
+			}
+			system.networks.sort_by(|a, b| crate::config::natural_cmp(&a.name, &b.name));
+		}
+
+		Ok(system)
+	}
+}
+
+// Returns the canonical paths of `/sys/class/{class}/{prefix}N`, sorted by N
+fn class_devices(class: &str, prefix: &str) -> Result<Vec<(u32, std::path::PathBuf)>, crate::Error> {
+	let class_dir = std::path::Path::new("/sys/class").join(class);
+	if !class_dir.exists() {
+		return Ok(vec![]);
+	}
+
+	let mut devices = vec![];
+	for entry in crate::std2::fs::read_dir(&class_dir)? {
+		let entry = entry?;
+		let Some(num) = entry.file_name().to_str().and_then(|file_name| file_name.strip_prefix(prefix)?.parse().ok()) else { continue; };
+		let dir = crate::std2::fs::canonicalize(&entry.path())?;
+		devices.push((num, dir));
+	}
+	devices.sort_by_key(|&(num, _)| num);
+	Ok(devices)
+}
+
+fn hwmon_channels(dir: &std::path::Path) -> Result<Vec<Channel>, crate::Error> {
+	let mut channels = vec![];
+
+	for entry in crate::std2::fs::read_dir(dir)? {
+		let file_name = entry?.file_name();
+		let Some(file_name) = file_name.to_str() else { continue; };
+
+		let (kind, num, scale) =
+			if let Some(num) = file_name.strip_prefix("temp").and_then(|s| s.strip_suffix("_input")) {
+				(ChannelKind::Temp, num, 1000.)
+			}
+			else if let Some(num) = file_name.strip_prefix("fan").and_then(|s| s.strip_suffix("_input")) {
+				(ChannelKind::Fan, num, 1.)
+			}
+			else if let Some(num) = file_name.strip_prefix("in").and_then(|s| s.strip_suffix("_input")) {
+				(ChannelKind::In, num, 1000.)
+			}
+			else if let Some(num) = file_name.strip_prefix("power").and_then(|s| s.strip_suffix("_input").or_else(|| s.strip_suffix("_average"))) {
+				(ChannelKind::Power, num, 1_000_000.)
+			}
+			else {
+				continue;
+			};
+		let Ok(num) = num.parse() else { continue; };
+
+		// Some devices have both `powerN_input` and `powerN_average`
+		if channels.iter().any(|channel: &Channel| channel.kind == kind && channel.num == num) {
+			continue;
+		}
+
+		let label = read_trimmed(&dir.join(format!("{}{num}_label", kind.prefix())));
+		let value = read_trimmed(&dir.join(file_name)).and_then(|value| value.parse::<f64>().ok()).map(|value| value / scale);
+		channels.push(Channel { kind, num, label, value });
+	}
+
+	channels.sort_by_key(|channel| (channel.kind, channel.num));
+	Ok(channels)
+}
+
+fn read_trimmed(path: &std::path::Path) -> Option<String> {
+	let s = std::fs::read_to_string(path).ok()?;
+	Some(s.trim().to_owned())
+}
+
+fn subsystem(device: &std::path::Path) -> Option<String> {
+	let subsystem = std::fs::read_link(device.join("subsystem")).ok()?;
+	subsystem.file_name()?.to_str().map(ToOwned::to_owned)
+}
+
+impl ChannelKind {
+	fn prefix(self) -> &'static str {
+		match self {
+			ChannelKind::Temp => "temp",
+			ChannelKind::Fan => "fan",
+			ChannelKind::In => "in",
+			ChannelKind::Power => "power",
+		}
+	}
+}
+
+impl std::fmt::Display for Channel {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}", self.kind.prefix(), self.num)?;
+		if let Some(label) = &self.label {
+			write!(f, " {label:?}")?;
+		}
+		match (self.kind, self.value) {
+			(_, None) => f.write_str(" could not be read"),
+			(ChannelKind::Temp, Some(value)) => write!(f, " {value:.1}°C"),
+			(ChannelKind::Fan, Some(value)) => write!(f, " {value} RPM"),
+			(ChannelKind::In, Some(value)) => write!(f, " {value:.3} V"),
+			(ChannelKind::Power, Some(value)) => write!(f, " {value:.1} W"),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Group {
+	Cpu,
+	Gpu,
+	Disk,
+	Mobo,
+	Bat,
+}
+
+impl Group {
+	const ALL: [Group; 5] = [Group::Cpu, Group::Gpu, Group::Disk, Group::Mobo, Group::Bat];
+
+	fn of_hwmon(hwmon: &Hwmon) -> Self {
+		if hwmon.is_power_supply {
+			return Group::Bat;
+		}
+
+		match &*hwmon.name {
+			"coretemp" | "fam15h_power" | "k8temp" | "k10temp" | "via_cputemp" | "zenpower" => Group::Cpu,
+			"amdgpu" | "i915" | "nouveau" | "radeon" | "xe" => Group::Gpu,
+			"drivetemp" | "nvme" => Group::Disk,
+			name if name.starts_with("cpu") => Group::Cpu,
+			name if name.starts_with("gpu") => Group::Gpu,
+			_ => Group::Mobo,
+		}
+	}
+
+	fn of_thermal_zone(thermal_zone: &ThermalZone) -> Self {
+		let r#type = thermal_zone.r#type.to_ascii_lowercase();
+		if r#type.starts_with("cpu") || r#type == "x86_pkg_temp" {
+			Group::Cpu
+		}
+		else if r#type.starts_with("gpu") {
+			Group::Gpu
+		}
+		else {
+			Group::Mobo
+		}
+	}
+
+	fn name(self) -> &'static str {
+		match self {
+			Group::Cpu => "CPU",
+			Group::Gpu => "GPU",
+			Group::Disk => "Disk",
+			Group::Mobo => "Mobo",
+			Group::Bat => "Bat",
+		}
+	}
+}
+
+fn render(system: &System, w: &mut impl std::fmt::Write) -> std::fmt::Result {
+	writeln!(w, "# This config was generated by `sensord discover`. Review it and remove the sensors you aren't interested in.")?;
+	writeln!(w, "# See the examples under `sensord/config-examples/` for all the available options.")?;
+	writeln!(w)?;
+	writeln!(w)?;
+
+	writeln!(w, "# Monitor usage of these network interfaces. Loopback and virtual interfaces are commented out.")?;
+	writeln!(w, "networks = [")?;
+	for network in &system.networks {
+		let comment = if network.is_virtual { "# " } else { "" };
+		writeln!(w, "\t{comment}{:?},", network.name)?;
+	}
+	writeln!(w, "]")?;
+	writeln!(w)?;
+	writeln!(w)?;
+
+	// Every hwmon device gets an entry named after its device name. Devices that share a name are numbered,
+	// and additionally matched by their PCI slot or their device path so that they are always mapped the same way.
+	let mut hwmon_names = vec![];
+	for (i, hwmon) in system.hwmons.iter().enumerate() {
+		let same_name = system.hwmons.iter().filter(|other| other.name == hwmon.name).count();
+		let index = system.hwmons[..i].iter().filter(|other| other.name == hwmon.name).count();
+		let hwmon_name = if index == 0 { toml_key(&hwmon.name) } else { toml_key(&format!("{}_{}", hwmon.name, index + 1)) };
+		hwmon_names.push((hwmon_name, same_name > 1));
+	}
+
+	if !system.hwmons.is_empty() {
+		writeln!(w, "# Sensors are read using the hwmon sysfs interface. Each entry here corresponds to a path under `/sys/class/hwmon`.")?;
+		writeln!(w, "# The `dev_name` is the value of `/sys/class/hwmon/hwmon*/name`")?;
+		writeln!(w, "#")?;
+		writeln!(w, "# The channels of each device and their current readings are listed above it. sensord can report temp and fan channels.")?;
+		writeln!(w, "[hwmon]")?;
+		for (hwmon, (hwmon_name, has_same_name)) in system.hwmons.iter().zip(&hwmon_names) {
+			writeln!(w, "# /sys/class/hwmon/hwmon{} -> {}", hwmon.num, hwmon.dir.display())?;
+			if !hwmon.channels.is_empty() {
+				writeln!(w, "#")?;
+			}
+			for channel in &hwmon.channels {
+				writeln!(w, "#   {channel}")?;
+			}
+
+			let mut properties = vec![format!("dev_name = {:?}", hwmon.name)];
+			if *has_same_name {
+				if let Some(pci_slot) = &hwmon.pci_slot {
+					properties.push(format!("pci_slot = {pci_slot:?}"));
+				}
+				else if let Some(device) = &hwmon.device {
+					properties.push(format!("dev_path = {:?}", device.display()));
+				}
+			}
+			writeln!(w, "{hwmon_name} = {{ {} }}", properties.join(", "))?;
+			writeln!(w)?;
+		}
+		writeln!(w)?;
+	}
+
+	if !system.batteries.is_empty() {
+		writeln!(w, "# Batteries that are not exposed as hwmon devices. Each entry here corresponds to a path under `/sys/class/power_supply`.")?;
+		writeln!(w, "[power_supply]")?;
+		for battery in &system.batteries {
+			let capacity = battery.capacity.map_or_else(|| "unknown capacity".to_owned(), |capacity| format!("{capacity}%"));
+			let status = battery.status.as_deref().unwrap_or("unknown status");
+			writeln!(w, "# {capacity}, {status}")?;
+			writeln!(w, "{} = {:?}", toml_key(&battery.name.to_ascii_lowercase()), battery.name)?;
+		}
+		writeln!(w)?;
+		writeln!(w)?;
+	}
+
+	// Thermal zones usually report the same sensors as hwmon devices, so they're only used if there are no hwmon devices.
+	let use_thermal_zones = system.hwmons.is_empty();
+
+	writeln!(w, "# Sensor groups. Each group has a name, zero or more temp sensors, zero or more fan sensors, and zero or more battery sensors.")?;
+	writeln!(w, "#")?;
+	writeln!(w, "# Sensors are identified by their label if they have one, otherwise by their number. The label is also used as the name of the sensor.")?;
+	writeln!(w, "# Fans that aren't spinning are commented out, since they're usually unconnected fan headers.")?;
+	for group in Group::ALL {
+		let mut temps = vec![];
+		let mut fans = vec![];
+		let mut bats = vec![];
+
+		for (hwmon, (hwmon_name, _)) in system.hwmons.iter().zip(&hwmon_names) {
+			if Group::of_hwmon(hwmon) != group {
+				continue;
+			}
+
+			for channel in &hwmon.channels {
+				let is_label_unique =
+					channel.label.as_ref()
+					.is_some_and(|label| hwmon.channels.iter().filter(|other| other.kind == channel.kind && other.label.as_ref() == Some(label)).count() == 1);
+				let num_or_label = match &channel.label {
+					Some(label) if is_label_unique => format!("label = {label:?}"),
+					_ => format!("num = {}", channel.num),
+				};
+
+				match channel.kind {
+					ChannelKind::Temp => {
+						let comment = if channel.value.is_some() { "" } else { "# " };
+						temps.push(format!("{comment}{{ hwmon = {hwmon_name:?}, {num_or_label} }}, # {channel}"));
+					},
+					ChannelKind::Fan => {
+						let comment = if channel.value.is_some_and(|value| value > 0.) { "" } else { "# " };
+						fans.push(format!("{comment}{{ hwmon = {hwmon_name:?}, {num_or_label} }}, # {channel}"));
+					},
+					ChannelKind::In | ChannelKind::Power => (),
+				}
+			}
+		}
+
+		if use_thermal_zones {
+			for thermal_zone in &system.thermal_zones {
+				if Group::of_thermal_zone(thermal_zone) != group {
+					continue;
+				}
+
+				let index = system.thermal_zones.iter().filter(|other| other.r#type == thermal_zone.r#type && other.num < thermal_zone.num).count();
+				let same_type = system.thermal_zones.iter().filter(|other| other.r#type == thermal_zone.r#type).count();
+				let index = if same_type > 1 { format!(", index = {index}") } else { String::new() };
+				let temp = thermal_zone.temp.map_or_else(|| "could not be read".to_owned(), |temp| format!("{temp:.1}°C"));
+				temps.push(format!("{{ thermal_type = {:?}{index} }}, # thermal_zone{} {temp}", thermal_zone.r#type, thermal_zone.num));
+			}
+		}
+
+		if group == Group::Bat {
+			for battery in &system.batteries {
+				bats.push(format!("{{ power_supply = {:?} }},", battery.name.to_ascii_lowercase()));
+			}
+		}
+
+		if temps.is_empty() && fans.is_empty() && bats.is_empty() {
+			continue;
+		}
+
+		writeln!(w)?;
+		writeln!(w, "[[sensor]]")?;
+		writeln!(w, "name = {:?}", group.name())?;
+		for (key, sensors) in [("temps", &temps), ("fans", &fans), ("bats", &bats)] {
+			if sensors.is_empty() {
+				continue;
+			}
+			writeln!(w, "{key} = [")?;
+			for sensor in sensors {
+				writeln!(w, "\t{sensor}")?;
+			}
+			writeln!(w, "]")?;
+		}
+	}
+
+	if !use_thermal_zones && !system.thermal_zones.is_empty() {
+		writeln!(w)?;
+		writeln!(w, "# Thermal zones usually report the same sensors as the hwmon devices above, so they're commented out.")?;
+		writeln!(w, "#")?;
+		writeln!(w, "# [[sensor]]")?;
+		writeln!(w, "# name = \"Thermal\"")?;
+		writeln!(w, "# temps = [")?;
+		for thermal_zone in &system.thermal_zones {
+			let temp = thermal_zone.temp.map_or_else(|| "could not be read".to_owned(), |temp| format!("{temp:.1}°C"));
+			writeln!(w, "# \t{{ thermal_zone = {}, name = {:?} }}, # {temp}", thermal_zone.num, thermal_zone.r#type)?;
+		}
+		writeln!(w, "# ]")?;
+	}
+
+	Ok(())
+}
+
+// Keys that contain characters other than ASCII letters, digits, `-` and `_` must be quoted.
+fn toml_key(key: &str) -> String {
+	if !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+		key.to_owned()
+	}
+	else {
+		format!("{key:?}")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn render() {
+		use super::{Battery, Channel, ChannelKind, Hwmon, Network, System, ThermalZone};
+
+		let k10temp = |num, pci_slot: &str, channels| Hwmon {
+			num,
+			name: "k10temp".to_owned(),
+			dir: format!("/sys/devices/pci0000:00/{pci_slot}/hwmon/hwmon{num}").into(),
+			device: Some(format!("/sys/devices/pci0000:00/{pci_slot}").into()),
+			pci_slot: Some(pci_slot.to_owned()),
+			is_power_supply: false,
+			channels,
+		};
+		let channel = |kind, num, label: Option<&str>, value| Channel { kind, num, label: label.map(ToOwned::to_owned), value };
+
+		let system = System {
+			hwmons: vec![
+				k10temp(0, "0000:00:18.3", vec![
+					channel(ChannelKind::Temp, 1, Some("Tctl"), Some(45.25)),
+					channel(ChannelKind::Temp, 3, Some("Tccd1"), Some(40.)),
+				]),
+				k10temp(1, "0000:00:19.3", vec![
+					channel(ChannelKind::Temp, 1, Some("Tctl"), Some(46.)),
+				]),
+				Hwmon {
+					num: 2,
+					name: "nct6779".to_owned(),
+					dir: "/sys/devices/platform/nct6775.656/hwmon/hwmon2".into(),
+					device: Some("/sys/devices/platform/nct6775.656".into()),
+					pci_slot: None,
+					is_power_supply: false,
+					channels: vec![
+						channel(ChannelKind::Temp, 1, Some("SYSTIN"), Some(30.)),
+						channel(ChannelKind::Fan, 1, None, Some(1200.)),
+						channel(ChannelKind::Fan, 2, None, Some(0.)),
+						channel(ChannelKind::In, 0, Some("Vcore"), Some(1.224)),
+					],
+				},
+			],
+			thermal_zones: vec![
+				ThermalZone { num: 0, r#type: "acpitz".to_owned(), temp: Some(27.8) },
+			],
+			batteries: vec![
+				Battery { name: "BAT0".to_owned(), capacity: Some(80), status: Some("Discharging".to_owned()) },
+			],
+			networks: vec![
+				Network { name: "docker0".to_owned(), is_virtual: true },
+				Network { name: "enp4s0".to_owned(), is_virtual: false },
+			],
+		};
+
+		let mut config = String::new();
+		super::render(&system, &mut config).unwrap();
+
+		assert!(config.contains("\t# \"docker0\",\n\t\"enp4s0\",\n"), "{config}");
+		assert!(config.contains("#   in0 \"Vcore\" 1.224 V\n"), "{config}");
+		assert!(config.contains("k10temp = { dev_name = \"k10temp\", pci_slot = \"0000:00:18.3\" }\n"), "{config}");
+		assert!(config.contains("k10temp_2 = { dev_name = \"k10temp\", pci_slot = \"0000:00:19.3\" }\n"), "{config}");
+		assert!(config.contains("nct6779 = { dev_name = \"nct6779\" }\n"), "{config}");
+		assert!(config.contains("bat0 = \"BAT0\"\n"), "{config}");
+		assert!(config.contains(
+			"[[sensor]]\nname = \"CPU\"\ntemps = [\n\
+			\t{ hwmon = \"k10temp\", label = \"Tctl\" }, # temp1 \"Tctl\" 45.2°C\n\
+			\t{ hwmon = \"k10temp\", label = \"Tccd1\" }, # temp3 \"Tccd1\" 40.0°C\n\
+			\t{ hwmon = \"k10temp_2\", label = \"Tctl\" }, # temp1 \"Tctl\" 46.0°C\n\
+			]\n"), "{config}");
+		assert!(config.contains(
+			"fans = [\n\
+			\t{ hwmon = \"nct6779\", num = 1 }, # fan1 1200 RPM\n\
+			\t# { hwmon = \"nct6779\", num = 2 }, # fan2 0 RPM\n\
+			]\n"), "{config}");
+		assert!(config.contains("[[sensor]]\nname = \"Bat\"\nbats = [\n\t{ power_supply = \"bat0\" },\n]\n"), "{config}");
+		assert!(config.contains("# \t{ thermal_zone = 0, name = \"acpitz\" }, # 27.8°C\n"), "{config}");
+
+		// The generated config must be valid, so that it can be used as-is.
+		if let Err(err) = crate::config::Files::parse("config.toml".as_ref(), &config) {
+			panic!("{err}{config}");
+		}
+	}
+}
@@ -0,0 +1,111 @@
+// The recent readings of a sensor that are needed to apply its `crate::config::Filter`.
+//
+// The state is reset when the config is reloaded, so smoothing starts over from the next reading.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct State {
+	average: Option<f64>,
+	window: std::collections::VecDeque<f64>,
+}
+
+impl State {
+	// Returns the smoothed value, or an error if the reading is outside the valid range.
+	// An invalid reading is not used for smoothing, so a single spike doesn't skew the following values either.
+	pub(crate) fn apply(&mut self, filter: &crate::config::Filter, value: f64) -> Result<f64, String> {
+		if let Some((min, max)) = filter.valid_range && !(min..=max).contains(&value) {
+			return Err(format!("invalid reading {value}, outside of the valid range [{min}, {max}]"));
+		}
+
+		Ok(match filter.smoothing {
+			crate::config::Smoothing::None => value,
+
+			crate::config::Smoothing::Ema(weight) => {
+				let average = self.average.map_or(value, |average| weight * value + (1. - weight) * average);
+				self.average = Some(average);
+				average
+			},
+
+			crate::config::Smoothing::Median(len) => {
+				if self.window.len() >= len {
+					self.window.pop_front();
+				}
+				self.window.push_back(value);
+
+				let mut sorted: Vec<_> = self.window.iter().copied().collect();
+				sorted.sort_by(f64::total_cmp);
+				let mid = sorted.len() / 2;
+				if sorted.len() % 2 == 0 { f64::midpoint(sorted[mid - 1], sorted[mid]) } else { sorted[mid] }
+			},
+		})
+	}
+
+	// Integer values are rounded after being smoothed, and saturate at the bounds of their type.
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	pub(crate) fn apply_u8(&mut self, filter: &crate::config::Filter, value: f64) -> Result<u8, String> {
+		Ok(self.apply(filter, value)?.round() as u8)
+	}
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	pub(crate) fn apply_u16(&mut self, filter: &crate::config::Filter, value: f64) -> Result<u16, String> {
+		Ok(self.apply(filter, value)?.round() as u16)
+	}
+
+	#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+	pub(crate) fn apply_u32(&mut self, filter: &crate::config::Filter, value: f64) -> Result<u32, String> {
+		Ok(self.apply(filter, value)?.round() as u32)
+	}
+}
+
+pub(crate) struct SensorGroup {
+	pub(crate) temps: Box<[State]>,
+	pub(crate) fans: Box<[State]>,
+	pub(crate) bats: Box<[State]>,
+	pub(crate) cooling: Box<[State]>,
+	pub(crate) files: Box<[State]>,
+	// Command sensors filter their values on their own threads. See `crate::command::Sensor`
+	pub(crate) derived: Box<[State]>,
+}
+
+impl SensorGroup {
+	pub(crate) fn new(sensor_group: &crate::config::SensorGroup) -> Self {
+		SensorGroup {
+			temps: vec![Default::default(); sensor_group.temps.len()].into_boxed_slice(),
+			fans: vec![Default::default(); sensor_group.fans.len()].into_boxed_slice(),
+			bats: vec![Default::default(); sensor_group.bats.len()].into_boxed_slice(),
+			cooling: vec![Default::default(); sensor_group.cooling.len()].into_boxed_slice(),
+			files: vec![Default::default(); sensor_group.files.len()].into_boxed_slice(),
+			derived: vec![Default::default(); sensor_group.derived.len()].into_boxed_slice(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::config::{Filter, Smoothing};
+
+	#[test]
+	fn filter() {
+		let filter = Filter { valid_range: Some((-40., 120.)), smoothing: Smoothing::None };
+		let mut state: super::State = Default::default();
+		assert_eq!(state.apply(&filter, 45.), Ok(45.));
+		assert_eq!(state.apply(&filter, -128.), Err("invalid reading -128, outside of the valid range [-40, 120]".to_owned()));
+		assert!(state.apply(&filter, f64::NAN).is_err());
+
+		let filter = Filter { valid_range: None, smoothing: Smoothing::Ema(0.5) };
+		let mut state: super::State = Default::default();
+		let values: Vec<_> = [40., 60., 60.].into_iter().map(|value| state.apply(&filter, value).unwrap()).collect();
+		assert_eq!(values, [40., 50., 55.]);
+
+		let filter = Filter { valid_range: Some((1., 5000.)), smoothing: Smoothing::Median(3) };
+		let mut state: super::State = Default::default();
+		let values: Vec<_> = [1200, 1210, 0, 65535, 1190, 1220].into_iter().map(|value| state.apply_u16(&filter, value.into())).collect();
+		assert_eq!(values, [
+			Ok(1200),
+			Ok(1205),
+			Err("invalid reading 0, outside of the valid range [1, 5000]".to_owned()),
+			Err("invalid reading 65535, outside of the valid range [1, 5000]".to_owned()),
+			Ok(1200),
+			Ok(1210),
+		]);
+	}
+}
@@ -0,0 +1,100 @@
+// Tracks read errors of a single sensor or section, so that one failing read doesn't stop everything else from being published.
+//
+// A sensor that fails repeatedly is read less often, backing off exponentially up to `MAX_SKIPPED_SAMPLES` samples between reads.
+// This avoids hammering a device that is slow to fail, like a sleeping disk or a GPU that is being reset.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Health {
+	consecutive_errors: u32,
+	samples_to_skip: u32,
+	error: Option<String>,
+}
+
+const MAX_SKIPPED_SAMPLES: u32 = 63;
+
+impl Health {
+	// Runs `f` to read the sensor, unless the sensor is being backed off.
+	//
+	// Returns `None` if `f` was not run or failed. In that case the caller should keep publishing the sensor's previous value.
+	pub(crate) fn sample<T>(&mut self, name: impl std::fmt::Display, f: impl FnOnce() -> Result<T, crate::Error>) -> Option<T> {
+		if self.samples_to_skip > 0 {
+			self.samples_to_skip -= 1;
+			return None;
+		}
+
+		match f() {
+			Ok(value) => {
+				if self.error.is_some() {
+					eprintln!("{name}: recovered");
+				}
+
+				self.consecutive_errors = 0;
+				self.error = None;
+
+				Some(value)
+			},
+
+			Err(err) => {
+				let err = err.to_string().trim_end().replace('\n', "; ");
+
+				// Only log when the error changes, so that a sensor that is failing persistently doesn't flood the journal.
+				if self.error.as_ref() != Some(&err) {
+					eprintln!("warning: {name}: {err}");
+				}
+
+				self.consecutive_errors = self.consecutive_errors.saturating_add(1);
+				self.samples_to_skip = 1_u32.checked_shl(self.consecutive_errors - 1).map_or(MAX_SKIPPED_SAMPLES, |n| (n - 1).min(MAX_SKIPPED_SAMPLES));
+				self.error = Some(err);
+
+				None
+			},
+		}
+	}
+
+	// The error of the most recent read, or an empty string if it succeeded.
+	pub(crate) fn error(&self) -> &str {
+		self.error.as_deref().unwrap_or_default()
+	}
+}
+
+pub(crate) struct SensorGroup {
+	pub(crate) temps: Box<[Health]>,
+	pub(crate) fans: Box<[Health]>,
+	pub(crate) bats: Box<[Health]>,
+	pub(crate) cooling: Box<[Health]>,
+	pub(crate) files: Box<[Health]>,
+}
+
+impl SensorGroup {
+	pub(crate) fn new(sensor_group: &crate::config::SensorGroup) -> Self {
+		SensorGroup {
+			temps: vec![Default::default(); sensor_group.temps.len()].into_boxed_slice(),
+			fans: vec![Default::default(); sensor_group.fans.len()].into_boxed_slice(),
+			bats: vec![Default::default(); sensor_group.bats.len()].into_boxed_slice(),
+			cooling: vec![Default::default(); sensor_group.cooling.len()].into_boxed_slice(),
+			files: vec![Default::default(); sensor_group.files.len()].into_boxed_slice(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn backoff() {
+		let mut health: super::Health = Default::default();
+
+		let mut sampled = vec![];
+		for i in 0..20 {
+			let _ = health.sample("test", || {
+				sampled.push(i);
+				Err::<(), _>(crate::Error::Other("EIO".into()))
+			});
+		}
+		assert_eq!(sampled, [0, 1, 3, 7, 15]);
+		assert_eq!(health.error(), "EIO");
+
+		// Skip the remaining backoff, then recover.
+		while health.sample("test", || Ok(())).is_none() {}
+		assert_eq!(health.error(), "");
+		assert!(health.sample("test", || Ok(())).is_some());
+	}
+}
@@ -43,24 +43,40 @@ pub(crate) fn parse_proc_cpuinfo(cpus: &mut [(Cpu, f64)], proc_cpu_info_line_reg
 	})
 }
 
-pub(crate) fn parse_proc_stat(average_cpu: &mut Cpu, cpus: &mut [(Cpu, f64)], buf: &mut Vec<u8>) -> Result<(), crate::Error> {
+pub(crate) fn parse_proc_stat(average_cpu: &mut Cpu, cpus: &mut [(Cpu, f64)], interrupts: &mut InterruptStats, buf: &mut Vec<u8>) -> Result<(), crate::Error> {
 	for_each_line("/proc/stat".as_ref(), buf, |line| {
-		if !line.starts_with(b"cpu") {
-			return Ok(true);
-		}
-
 		let mut parts = line.split(|&b| b == b' ').filter(|s| !s.is_empty());
 
-		let id = parts.next().unwrap();
-		let id = &id[(b"cpu".len())..];
+		let Some(key) = parts.next() else { return Ok(false); };
+		let id = match key {
+			b"intr" => {
+				interrupts.interrupts = parse_bytes(parts.next().ok_or("interrupts total missing")?)?;
+				return Ok(false);
+			},
+
+			b"ctxt" => {
+				interrupts.context_switches = parse_bytes(parts.next().ok_or("context switches total missing")?)?;
+				return Ok(false);
+			},
+
+			// This is the last line of the file.
+			b"softirq" => {
+				interrupts.softirqs = parse_bytes(parts.next().ok_or("softirqs total missing")?)?;
+				return Ok(true);
+			},
+
+			key => match key.strip_prefix(b"cpu") {
+				Some(id) => id,
+				None => return Ok(false),
+			},
+		};
+
 		let id: Option<usize> =
 			if id.is_empty() {
 				None
 			}
 			else {
-				let id = str::from_utf8(id)?;
-				let id = id.parse()?;
-				Some(id)
+				Some(parse_bytes(id)?)
 			};
 
 		let cpu =
@@ -71,14 +87,7 @@ pub(crate) fn parse_proc_stat(average_cpu: &mut Cpu, cpus: &mut [(Cpu, f64)], bu
 				&mut *average_cpu
 			};
 
-		let mut parts =
-			parts
-			.map(|part| -> Result<u64, Box<dyn std::error::Error>> {
-				let part = str::from_utf8(part)?;
-				let part = part.parse()?;
-				Ok(part)
-			})
-			.fuse();
+		let mut parts = parts.map(parse_bytes::<u64>).fuse();
 
 		let user_time = parts.next().ok_or("user time missing")??;
 		let nice_time = parts.next().ok_or("nice time missing")??;
@@ -95,6 +104,144 @@ pub(crate) fn parse_proc_stat(average_cpu: &mut Cpu, cpus: &mut [(Cpu, f64)], bu
 	})
 }
 
+#[derive(Clone)]
+pub(crate) struct InterruptStats {
+	pub(crate) now: std::time::Instant,
+	pub(crate) interrupts: u64,
+	pub(crate) softirqs: u64,
+	pub(crate) context_switches: u64,
+	pub(crate) interrupt_sources: Vec<InterruptSource>,
+	pub(crate) softirq_sources: Vec<InterruptSource>,
+}
+
+#[derive(Clone)]
+pub(crate) struct InterruptSource {
+	pub(crate) name: String,
+	pub(crate) description: String,
+	// Indexed by CPU ID. CPUs that are not online have a count of 0.
+	pub(crate) cpus: Vec<u64>,
+}
+
+// Parses `/proc/interrupts` or `/proc/softirqs`. Both files have a header line of CPU IDs, followed by one line per source
+// with the source's name, a count for each CPU in the header, and optionally a description.
+//
+// The set of sources rarely changes, so the existing elements of `sources` are overwritten to reuse their allocations.
+pub(crate) fn parse_proc_interrupts(
+	path: &std::path::Path,
+	num_cpus: usize,
+	sources: &mut Vec<InterruptSource>,
+	buf: &mut Vec<u8>,
+) -> Result<(), crate::Error> {
+	let mut num_sources = 0;
+
+	let mut cpu_ids: Option<Vec<usize>> = None;
+
+	let result = for_each_line(path, buf, |line| {
+		let mut parts = line.split(u8::is_ascii_whitespace).filter(|s| !s.is_empty()).peekable();
+
+		let Some(cpu_ids) = &cpu_ids else {
+			let ids: Result<_, Box<dyn std::error::Error>> =
+				parts
+				.map(|part| parse_bytes(part.strip_prefix(b"CPU").ok_or("malformed header")?))
+				.collect();
+			cpu_ids = Some(ids?);
+			return Ok(false);
+		};
+
+		let name = parts.next().ok_or("source name missing")?;
+		let name = name.strip_suffix(b":").ok_or("malformed source name")?;
+		let name = str::from_utf8(name)?;
+
+		if num_sources == sources.len() {
+			sources.push(InterruptSource {
+				name: String::new(),
+				description: String::new(),
+				cpus: vec![],
+			});
+		}
+		let source = &mut sources[num_sources];
+		num_sources += 1;
+
+		source.name.clear();
+		source.name.push_str(name);
+		source.description.clear();
+		source.cpus.clear();
+		source.cpus.resize(num_cpus, 0);
+
+		// Some sources like `ERR` and `MIS` only have a single system-wide count instead of one per CPU.
+		for &id in cpu_ids {
+			let Some(count) = parts.next_if(|part| part.iter().all(u8::is_ascii_digit)) else { break; };
+			*source.cpus.get_mut(id).ok_or_else(|| format!("unexpected CPU ID {id}"))? = parse_bytes(count)?;
+		}
+
+		for part in parts {
+			if !source.description.is_empty() {
+				source.description.push(' ');
+			}
+			source.description.push_str(str::from_utf8(part)?);
+		}
+
+		Ok(false)
+	});
+
+	sources.truncate(num_sources);
+
+	result
+}
+
+#[derive(Clone)]
+pub(crate) struct VmStat {
+	pub(crate) now: std::time::Instant,
+	pub(crate) swap_ins: u64,
+	pub(crate) swap_outs: u64,
+	pub(crate) page_faults: u64,
+	pub(crate) major_page_faults: u64,
+	pub(crate) pages_scanned: u64,
+	pub(crate) pages_stolen: u64,
+	pub(crate) oom_kills: u64,
+	pub(crate) thp: Vec<(String, u64)>,
+}
+
+pub(crate) fn parse_proc_vmstat(path: &std::path::Path, vmstat: &mut VmStat, buf: &mut Vec<u8>) -> Result<(), crate::Error> {
+	// `pgscan_*` and `pgsteal_*` are broken down both by the reclaimer (kswapd, direct, etc) and by the kind of page (anon, file).
+	// Only the former is summed, since summing both would count every page twice. Older kernels further break down the former by zone,
+	// eg `pgscan_kswapd_normal`. `pgscan_direct_throttle` is a count of throttling events rather than pages, so it's excluded.
+	fn is_reclaimer_counter(name: &[u8]) -> bool {
+		name != b"direct_throttle" &&
+		[&b"kswapd"[..], b"direct", b"khugepaged", b"proactive"].into_iter().any(|reclaimer| match name.strip_prefix(reclaimer) {
+			Some(rest) => rest.is_empty() || rest.starts_with(b"_"),
+			None => false,
+		})
+	}
+
+	vmstat.pages_scanned = 0;
+	vmstat.pages_stolen = 0;
+	vmstat.thp.clear();
+
+	for_each_line(path, buf, |line| {
+		let mut parts = line.split(|&b| b == b' ');
+		let name = parts.next().ok_or("counter name missing")?;
+		let value = parts.next().ok_or("counter value missing")?;
+
+		match name {
+			b"pswpin" => vmstat.swap_ins = parse_bytes(value)?,
+			b"pswpout" => vmstat.swap_outs = parse_bytes(value)?,
+			b"pgfault" => vmstat.page_faults = parse_bytes(value)?,
+			b"pgmajfault" => vmstat.major_page_faults = parse_bytes(value)?,
+			b"oom_kill" => vmstat.oom_kills = parse_bytes(value)?,
+
+			name if name.strip_prefix(b"pgscan_").is_some_and(is_reclaimer_counter) => vmstat.pages_scanned += parse_bytes::<u64>(value)?,
+			name if name.strip_prefix(b"pgsteal_").is_some_and(is_reclaimer_counter) => vmstat.pages_stolen += parse_bytes::<u64>(value)?,
+
+			name if name.starts_with(b"thp_") => vmstat.thp.push((str::from_utf8(name)?.to_owned(), parse_bytes(value)?)),
+
+			_ => (),
+		}
+
+		Ok(false)
+	})
+}
+
 pub(crate) fn parse_scaling_cur_freq(id: usize, cpu_freq: &mut f64, buf: &mut Vec<u8>) -> Result<(), crate::Error> {
 	*cpu_freq = parse_hwmon::<f64>(std::path::Path::new(&format!("/sys/devices/system/cpu/cpu{id}/cpufreq/scaling_cur_freq")), buf)?.unwrap_or_default() / 1000.;
 
@@ -110,10 +257,8 @@ pub(crate) struct Network {
 }
 
 impl Network {
-	pub(crate) fn update_all<'a>(
-		specs_and_networks: impl IntoIterator<Item = (&'a crate::config::Network, &'a mut Self)>,
-		buf: &mut Vec<u8>,
-	) -> Result<(), crate::Error> {
+	// Returns the IP addresses of all network interfaces, keyed by interface name.
+	pub(crate) fn addresses() -> Result<std::collections::BTreeMap<String, Vec<std::net::IpAddr>>, crate::Error> {
 		let mut addresses: std::collections::BTreeMap<_, Vec<_>> = Default::default();
 
 		unsafe {
@@ -148,20 +293,27 @@ impl Network {
 					_ => continue,
 				};
 
-				addresses.entry(name).or_default().push(ip);
-			}
-
-			for (network_spec, network) in specs_and_networks {
-				network.now = std::time::Instant::now();
-				network.rx = parse_hwmon(&network_spec.rx_path, buf)?.unwrap_or(0);
-				network.tx = parse_hwmon(&network_spec.tx_path, buf)?.unwrap_or(0);
-
-				network.addresses = addresses.get(&*network_spec.name).cloned().unwrap_or_default();
+				addresses.entry(name.to_owned()).or_default().push(ip);
 			}
 
 			libc::freeifaddrs(addrs);
 		}
 
+		Ok(addresses)
+	}
+
+	pub(crate) fn update(
+		&mut self,
+		network_spec: &crate::config::Network,
+		addresses: &std::collections::BTreeMap<String, Vec<std::net::IpAddr>>,
+		buf: &mut Vec<u8>,
+	) -> Result<(), crate::Error> {
+		self.now = std::time::Instant::now();
+		self.rx = parse_hwmon(&network_spec.rx_path, buf)?.unwrap_or(0);
+		self.tx = parse_hwmon(&network_spec.tx_path, buf)?.unwrap_or(0);
+
+		self.addresses = addresses.get(&network_spec.name).cloned().unwrap_or_default();
+
 		Ok(())
 	}
 }
@@ -184,52 +336,178 @@ pub(crate) fn parse_pwm_sensor(path: Option<&std::path::Path>, buf: &mut Vec<u8>
 	path.map_or(Ok(None), |path| parse_hwmon(path, buf))
 }
 
-pub(crate) fn parse_bat_capacity_sensor(path: &std::path::Path, buf: &mut Vec<u8>) -> Result<Option<u8>, crate::Error> {
-	parse_hwmon(path, buf)
+pub(crate) fn parse_bat_capacity_sensor(path: Option<&std::path::Path>, buf: &mut Vec<u8>) -> Result<Option<u8>, crate::Error> {
+	path.map_or(Ok(None), |path| parse_hwmon(path, buf))
 }
 
-pub(crate) fn parse_bat_status_sensor(path: &std::path::Path, buf: &mut Vec<u8>) -> Result<Option<bool>, crate::Error> {
+pub(crate) fn parse_bat_status_sensor(path: Option<&std::path::Path>, buf: &mut Vec<u8>) -> Result<Option<bool>, crate::Error> {
+	let Some(path) = path else { return Ok(None); };
 	Ok(match parse_hwmon_raw(path, buf)? {
 		Some("Charging" | "Full") => Some(true),
 		_ => None,
 	})
 }
 
-fn for_each_line(
+pub(crate) fn parse_cooling_sensor(path: Option<&std::path::Path>, buf: &mut Vec<u8>) -> Result<Option<u32>, crate::Error> {
+	path.map_or(Ok(None), |path| parse_hwmon(path, buf))
+}
+
+// The trip points, policy and mode of a thermal zone. See `crate::config::TempSensor::thermal_zone`
+#[derive(Debug, Default)]
+pub(crate) struct ThermalZone {
+	// The type and temp of each trip point, in °C
+	pub(crate) trip_points: Vec<(String, f64)>,
+	pub(crate) policy: String,
+	pub(crate) mode: String,
+}
+
+// Trip points are numbered consecutively from 0. Trip points and attributes that can't be read are left out
+// rather than failing the whole sensor, since eg some drivers fail reads of trip points that aren't set.
+pub(crate) fn parse_thermal_zone(dir: &std::path::Path, buf: &mut Vec<u8>) -> ThermalZone {
+	let mut thermal_zone: ThermalZone = Default::default();
+
+	for i in 0.. {
+		let Ok(Some(kind)) = parse_hwmon_raw(&dir.join(format!("trip_point_{i}_type")), buf) else { break; };
+		let kind = kind.trim().to_owned();
+		let Ok(Some(temp)) = parse_hwmon_raw(&dir.join(format!("trip_point_{i}_temp")), buf) else { break; };
+		let Ok(temp) = temp.trim().parse::<f64>() else { continue; };
+		thermal_zone.trip_points.push((kind, temp / 1000.));
+	}
+
+	if let Ok(Some(policy)) = parse_hwmon_raw(&dir.join("policy"), buf) {
+		policy.trim().clone_into(&mut thermal_zone.policy);
+	}
+
+	if let Ok(Some(mode)) = parse_hwmon_raw(&dir.join("mode"), buf) {
+		mode.trim().clone_into(&mut thermal_zone.mode);
+	}
+
+	thermal_zone
+}
+
+// A file that doesn't exist is an error rather than a value, so that it isn't mistaken for a reading of 0. See `crate::config::FileSensor`
+pub(crate) fn parse_file_sensor(path: &std::path::Path, regex: Option<&regex::bytes::Regex>, buf: &mut Vec<u8>) -> Result<f64, crate::Error> {
+	let Some(regex) = regex else {
+		let value = parse_hwmon_raw(path, buf)?.map(|value| value.trim().parse()).transpose();
+		return crate::Error::with_path_context(path, |_| value?.ok_or_else(|| "file does not exist".into()));
+	};
+
+	let mut value = None;
+	let exists = for_each_line_if_exists(path, buf, |line| {
+		value = parse_regex_capture(regex, line)?;
+		Ok(value.is_some())
+	})?;
+	if !exists {
+		return Err(crate::Error::Path("file does not exist".into(), path.to_owned()));
+	}
+
+	value.ok_or_else(|| crate::Error::Path("no line matches the regex".into(), path.to_owned()))
+}
+
+// Parses the first capture group of the regex's match in the line, or the whole match if the regex has no capture groups.
+//
+// Returns `None` if the regex doesn't match the line.
+pub(crate) fn parse_regex_capture(regex: &regex::bytes::Regex, line: &[u8]) -> Result<Option<f64>, Box<dyn std::error::Error>> {
+	let Some(captures) = regex.captures(line) else { return Ok(None); };
+	let Some(m) = captures.get(1).or_else(|| captures.get(0)) else { return Ok(None); };
+	Ok(Some(parse_bytes(m.as_bytes().trim_ascii())?))
+}
+
+// Computes the rate per second at which a counter increases from consecutive samples of it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Counter {
+	previous: Option<(std::time::Instant, f64)>,
+}
+
+impl Counter {
+	// The first sample, and a sample after the counter was reset, only serve as the base for the next sample's rate,
+	// so their rate is zero.
+	pub(crate) fn rate(&mut self, value: f64) -> f64 {
+		self.rate_at(std::time::Instant::now(), value)
+	}
+
+	fn rate_at(&mut self, now: std::time::Instant, value: f64) -> f64 {
+		match self.previous.replace((now, value)) {
+			Some((previous_now, previous_value)) if value >= previous_value =>
+				now.checked_duration_since(previous_now)
+				.filter(|duration| !duration.is_zero())
+				.map_or(0., |duration| (value - previous_value) / duration.as_secs_f64()),
+			_ => 0.,
+		}
+	}
+}
+
+pub(crate) fn for_each_line(
 	path: &std::path::Path,
 	buf: &mut Vec<u8>,
-	mut f: impl FnMut(&[u8]) -> Result<bool, Box<dyn std::error::Error>>,
+	f: impl FnMut(&[u8]) -> Result<bool, Box<dyn std::error::Error>>,
 ) -> Result<(), crate::Error> {
 	crate::Error::with_path_context(path, |path| {
-		let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+		let file = std::fs::File::open(path)?;
+		for_each_line_inner(file, buf, f)
+	})
+}
+
+// Same as `for_each_line`, except that a file that does not exist is not an error. Returns whether the file exists.
+pub(crate) fn for_each_line_if_exists(
+	path: &std::path::Path,
+	buf: &mut Vec<u8>,
+	f: impl FnMut(&[u8]) -> Result<bool, Box<dyn std::error::Error>>,
+) -> Result<bool, crate::Error> {
+	crate::Error::with_path_context(path, |path| {
+		let file = match std::fs::File::open(path) {
+			Ok(file) => file,
+			Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+			Err(err) => return Err(err.into()),
+		};
+		for_each_line_inner(file, buf, f)?;
+		Ok(true)
+	})
+}
 
-		loop {
-			buf.clear();
+fn for_each_line_inner(
+	file: std::fs::File,
+	buf: &mut Vec<u8>,
+	mut f: impl FnMut(&[u8]) -> Result<bool, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+	let mut file = std::io::BufReader::new(file);
 
-			let read = std::io::BufRead::read_until(&mut file, b'\n', buf)?;
-			if read == 0 {
-				break;
-			}
+	loop {
+		buf.clear();
 
-			let buf = &buf[..read];
-			let buf =
-				if buf[buf.len() - 1] == b'\n' {
-					&buf[..(buf.len() - 1)]
-				}
-				else {
-					buf
-				};
+		let read = std::io::BufRead::read_until(&mut file, b'\n', buf)?;
+		if read == 0 {
+			break;
+		}
 
-			if f(buf)? {
-				break;
+		let buf = &buf[..read];
+		let buf =
+			if buf[buf.len() - 1] == b'\n' {
+				&buf[..(buf.len() - 1)]
 			}
+			else {
+				buf
+			};
+
+		if f(buf)? {
+			break;
 		}
+	}
 
-		Ok(())
-	})
+	Ok(())
+}
+
+pub(crate) fn parse_bytes<T>(s: &[u8]) -> Result<T, Box<dyn std::error::Error>>
+where
+	T: std::str::FromStr,
+	Box<dyn std::error::Error>: From<<T as std::str::FromStr>::Err>,
+{
+	let s = str::from_utf8(s)?;
+	let value = s.parse()?;
+	Ok(value)
 }
 
-fn parse_hwmon<T>(path: &std::path::Path, buf: &mut Vec<u8>) -> Result<Option<T>, crate::Error>
+pub(crate) fn parse_hwmon<T>(path: &std::path::Path, buf: &mut Vec<u8>) -> Result<Option<T>, crate::Error>
 where
 	T: std::str::FromStr,
 	Box<dyn std::error::Error>: From<<T as std::str::FromStr>::Err>,
@@ -238,7 +516,7 @@ where
 	crate::Error::with_path_context(path, |_| Ok(value?))
 }
 
-fn parse_hwmon_raw<'a>(path: &std::path::Path, buf: &'a mut Vec<u8>) -> Result<Option<&'a str>, crate::Error> {
+pub(crate) fn parse_hwmon_raw<'a>(path: &std::path::Path, buf: &'a mut Vec<u8>) -> Result<Option<&'a str>, crate::Error> {
 	crate::Error::with_path_context(path, |path| {
 		let file = match std::fs::File::open(path) {
 			Ok(file) => file,
@@ -269,3 +547,184 @@ fn parse_hwmon_raw<'a>(path: &std::path::Path, buf: &'a mut Vec<u8>) -> Result<O
 		Ok(Some(value))
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn parse_file_sensor() {
+		let dir = std::env::temp_dir().join(format!("sensord-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("arcstats");
+		std::fs::write(&path, "12 1 0x01 123 33456 1234 5678\nname type data\nhits 4 1000\nsize 4 2048\n").unwrap();
+
+		let mut buf = vec![];
+		let regex = regex::bytes::Regex::new(r"^size +[0-9]+ +([0-9]+)$").unwrap();
+		assert_eq!(super::parse_file_sensor(&path, Some(&regex), &mut buf).ok(), Some(2048.));
+
+		let regex = regex::bytes::Regex::new(r"^misses ").unwrap();
+		let err = super::parse_file_sensor(&path, Some(&regex), &mut buf).unwrap_err().to_string();
+		assert!(err.contains("no line matches the regex"), "{err:?}");
+
+		std::fs::write(&path, "35\n").unwrap();
+		assert_eq!(super::parse_file_sensor(&path, None, &mut buf).ok(), Some(35.));
+
+		std::fs::remove_dir_all(&dir).unwrap();
+		for regex in [None, Some(&regex)] {
+			let err = super::parse_file_sensor(&path, regex, &mut buf).unwrap_err().to_string();
+			assert!(err.contains("file does not exist"), "{err:?}");
+		}
+	}
+
+	#[test]
+	fn parse_proc_interrupts() {
+		let dir = std::env::temp_dir().join(format!("sensord-interrupts-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("interrupts");
+
+		// CPU 2 is offline, so it has no column.
+		std::fs::write(&path, concat!(
+			"           CPU0       CPU1       CPU3       \n",
+			"  0:         45          0          7   IO-APIC    2-edge      timer\n",
+			"  9:          0         12          3   IO-APIC    9-fasteoi   acpi\n",
+			"NMI:          1          2          3   Non-maskable interrupts\n",
+			"ERR:          4\n",
+			"MIS:          0\n",
+		)).unwrap();
+
+		let mut sources = vec![];
+		let mut buf = vec![];
+		super::parse_proc_interrupts(&path, 4, &mut sources, &mut buf).unwrap();
+		let actual: Vec<_> = sources.iter().map(|source| (&*source.name, &*source.description, &*source.cpus)).collect();
+		assert_eq!(actual, [
+			("0", "IO-APIC 2-edge timer", &[45, 0, 0, 7][..]),
+			("9", "IO-APIC 9-fasteoi acpi", &[0, 12, 0, 3][..]),
+			("NMI", "Non-maskable interrupts", &[1, 2, 0, 3][..]),
+			// `ERR` and `MIS` have a single system-wide count.
+			("ERR", "", &[4, 0, 0, 0][..]),
+			("MIS", "", &[0, 0, 0, 0][..]),
+		]);
+
+		// softirqs have no description. The previous sources are overwritten.
+		std::fs::write(&path, concat!(
+			"                    CPU0       CPU1\n",
+			"          HI:          1          0\n",
+			"       TIMER:     123456      65432\n",
+		)).unwrap();
+		super::parse_proc_interrupts(&path, 2, &mut sources, &mut buf).unwrap();
+		let actual: Vec<_> = sources.iter().map(|source| (&*source.name, &*source.description, &*source.cpus)).collect();
+		assert_eq!(actual, [
+			("HI", "", &[1, 0][..]),
+			("TIMER", "", &[123_456, 65432][..]),
+		]);
+
+		std::fs::write(&path, "           CPU0       CPU1\n  0:         45          0\n").unwrap();
+		let err = super::parse_proc_interrupts(&path, 1, &mut sources, &mut buf).unwrap_err().to_string();
+		assert!(err.contains("unexpected CPU ID 1"), "{err:?}");
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn parse_proc_vmstat() {
+		let dir = std::env::temp_dir().join(format!("sensord-vmstat-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("vmstat");
+
+		std::fs::write(&path, concat!(
+			"nr_free_pages 123456\n",
+			"pgfault 1000\n",
+			"pgmajfault 10\n",
+			"pswpin 3\n",
+			"pswpout 4\n",
+			// Only the per-reclaimer counters are summed, not the per-kind ones.
+			"pgscan_kswapd 100\n",
+			"pgscan_direct 20\n",
+			"pgscan_direct_throttle 5\n",
+			"pgscan_anon 80\n",
+			"pgscan_file 40\n",
+			"pgsteal_kswapd_normal 50\n",
+			"pgsteal_kswapd_movable 7\n",
+			"pgsteal_khugepaged 2\n",
+			"pgsteal_file 59\n",
+			"oom_kill 1\n",
+			"thp_fault_alloc 6\n",
+			"thp_collapse_alloc 8\n",
+		)).unwrap();
+
+		let mut vmstat = super::VmStat {
+			now: std::time::Instant::now(),
+			swap_ins: 0,
+			swap_outs: 0,
+			page_faults: 0,
+			major_page_faults: 0,
+			pages_scanned: 0,
+			pages_stolen: 0,
+			oom_kills: 0,
+			thp: vec![],
+		};
+		let mut buf = vec![];
+		super::parse_proc_vmstat(&path, &mut vmstat, &mut buf).unwrap();
+		assert_eq!(
+			(vmstat.swap_ins, vmstat.swap_outs, vmstat.page_faults, vmstat.major_page_faults, vmstat.pages_scanned, vmstat.pages_stolen, vmstat.oom_kills),
+			(3, 4, 1000, 10, 120, 59, 1),
+		);
+		assert_eq!(vmstat.thp, [("thp_fault_alloc".to_owned(), 6), ("thp_collapse_alloc".to_owned(), 8)]);
+
+		// The sums and THP counters are reset rather than accumulated across reads.
+		super::parse_proc_vmstat(&path, &mut vmstat, &mut buf).unwrap();
+		assert_eq!((vmstat.pages_scanned, vmstat.pages_stolen, vmstat.thp.len()), (120, 59, 2));
+
+		std::fs::write(&path, "pgfault\n").unwrap();
+		let err = super::parse_proc_vmstat(&path, &mut vmstat, &mut buf).unwrap_err().to_string();
+		assert!(err.contains("counter value missing"), "{err:?}");
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn parse_thermal_zone() {
+		let dir = std::env::temp_dir().join(format!("sensord-thermal-zone-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		for (name, contents) in [
+			("trip_point_0_type", "passive\n"),
+			("trip_point_0_temp", "85000\n"),
+			("trip_point_1_type", "critical\n"),
+			("trip_point_1_temp", "105000\n"),
+			("policy", "step_wise\n"),
+		] {
+			std::fs::write(dir.join(name), contents).unwrap();
+		}
+
+		let mut buf = vec![];
+		let thermal_zone = super::parse_thermal_zone(&dir, &mut buf);
+		assert_eq!(thermal_zone.trip_points, [("passive".to_owned(), 85.), ("critical".to_owned(), 105.)]);
+		assert_eq!(thermal_zone.policy, "step_wise");
+		assert_eq!(thermal_zone.mode, "");
+
+		// Trip points and the policy can be changed at runtime, eg by thermald.
+		std::fs::write(dir.join("trip_point_0_temp"), "80000\n").unwrap();
+		std::fs::write(dir.join("policy"), "user_space\n").unwrap();
+		let thermal_zone = super::parse_thermal_zone(&dir, &mut buf);
+		assert_eq!(thermal_zone.trip_points, [("passive".to_owned(), 80.), ("critical".to_owned(), 105.)]);
+		assert_eq!(thermal_zone.policy, "user_space");
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn counter() {
+		let mut counter: super::Counter = Default::default();
+		let start = std::time::Instant::now();
+		let at = |secs| start + std::time::Duration::from_secs(secs);
+
+		for (secs, value, expected) in [
+			(0, 100., 0.),
+			(2, 300., 100.),
+			// The counter was reset.
+			(4, 50., 0.),
+			(5, 60., 10.),
+		] {
+			assert!((counter.rate_at(at(secs), value) - expected).abs() < f64::EPSILON, "{secs} {value}");
+		}
+	}
+}
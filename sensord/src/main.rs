@@ -6,18 +6,99 @@
 	clippy::too_many_lines,
 )]
 
+mod cgroup;
+
+mod check;
+
+mod clients;
+
+mod command;
+
 mod config;
 
+mod derived;
+
+mod discover;
+
+mod filter;
+
+mod health;
+
 mod hwmon;
 
+mod process;
+
 mod std2;
 
+mod uevent;
+
+// Set by the SIGHUP handler to make the main loop reload the config.
+static RELOAD_CONFIG: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+const USAGE: &str = "\
+Usage: sensord [--config <path>] [--check-config | --print-resolved]
+       sensord discover
+
+Commands:
+    discover            Print a starter config for the running system, listing all the sensors that were found.
+
+Options:
+    --config <path>     Read the config from <path> instead of /etc/sensord/config.toml
+    --check-config      Resolve the config, print the sysfs path and current value of every sensor, and exit.
+                        Exits with an error if the config is invalid or any sensor could not be read.
+    --print-resolved    Resolve the config, print the sysfs path of every sensor, and exit.
+    --help              Print this help and exit.
+";
+
+enum Mode {
+	Run,
+	CheckConfig,
+	PrintResolved,
+}
+
 fn main() -> Result<(), Error> {
-	let config: config::Config = Error::with_path_context("/etc/sensord/config.toml".as_ref(), |path| {
-		let config = std::fs::read_to_string(path)?;
-		let config = toml::from_str(&config)?;
-		Ok(config)
-	})?;
+	let mut config_path: std::path::PathBuf = "/etc/sensord/config.toml".into();
+	let mut mode = Mode::Run;
+
+	let mut args = std::env::args_os().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.to_str() {
+			Some("--config") => config_path = args.next().ok_or_else(|| Error::Other(format!("--config requires a path\n\n{USAGE}").into()))?.into(),
+			Some("--check-config") => mode = Mode::CheckConfig,
+			Some("--print-resolved") => mode = Mode::PrintResolved,
+			Some("discover") => {
+				discover::print()?;
+				return Ok(());
+			},
+			Some("--help" | "-h") => {
+				print!("{USAGE}");
+				return Ok(());
+			},
+			_ => return Err(Error::Other(format!("unrecognized argument {}\n\n{USAGE}", arg.display()).into())),
+		}
+	}
+
+	let config_path = &*config_path;
+	let (mut config_files, mut config) = load_config(config_path)?;
+
+	match mode {
+		Mode::Run => (),
+
+		Mode::CheckConfig => {
+			let problems = check::print(&config, true)?;
+			if problems > 0 {
+				return Err(Error::Other(format!("{problems} sensor(s) could not be read").into()));
+			}
+			return Ok(());
+		},
+
+		Mode::PrintResolved => {
+			check::print(&config, false)?;
+			return Ok(());
+		},
+	}
+
+	handle_sighup()?;
 
 	let connection =
 		dbus_pure::Connection::new(
@@ -45,6 +126,16 @@ fn main() -> Result<(), Error> {
 
 	dbus_client.set_name("dev.arnavion.sensord.Daemon".to_owned());
 
+	// The connection that owns the name receives the method calls of clients on its own thread, so the signals are sent on another one.
+	let clients = clients::Clients::spawn(dbus_client, &config)?;
+
+	let connection =
+		dbus_pure::Connection::new(
+			dbus_pure::BusPath::System,
+			dbus_pure::SaslAuthType::Uid,
+		).map_err(|err| Error::Other(err.into()))?;
+	let mut dbus_client = dbus_pure::Client::new(connection).map_err(|err| Error::Other(err.into()))?;
+
 	let sys_devices_system_cpu_present_line_regex = regex::bytes::Regex::new(r"^0-([0-9]+)$").expect("hard-coded regex is expected to be valid");
 	let proc_cpu_info_line_regex = regex::bytes::Regex::new(r"^(?:(?:processor\t*: (?P<id>[0-9]+))|(?:cpu MHz\t*: (?P<frequency>[0-9]+(?:\.[0-9]+)?)))$").expect("hard-coded regex is expected to be valid");
 
@@ -59,135 +150,635 @@ fn main() -> Result<(), Error> {
 	let mut cpus: Box<[(hwmon::Cpu, f64)]> = vec![(Default::default(), 0.); num_cpus].into_boxed_slice();
 	let mut message_cpus: Box<[sensord_common::Cpu]> = vec![Default::default(); num_cpus].into_boxed_slice();
 
+	let mut previous_interrupts = hwmon::InterruptStats {
+		now: std::time::Instant::now(),
+		interrupts: 0,
+		softirqs: 0,
+		context_switches: 0,
+		interrupt_sources: vec![],
+		softirq_sources: vec![],
+	};
+	let mut interrupts = previous_interrupts.clone();
+	let mut message_top_interrupts: Vec<sensord_common::InterruptSource<'_>> = vec![];
+	let mut message_top_softirqs: Vec<sensord_common::InterruptSource<'_>> = vec![];
+
+	let mut previous_vmstat = hwmon::VmStat {
+		now: std::time::Instant::now(),
+		swap_ins: 0,
+		swap_outs: 0,
+		page_faults: 0,
+		major_page_faults: 0,
+		pages_scanned: 0,
+		pages_stolen: 0,
+		oom_kills: 0,
+		thp: vec![],
+	};
+	let mut vmstat = previous_vmstat.clone();
+	let mut message_thp: Vec<sensord_common::VmStatCounter<'_>> = vec![];
+
+	let empty_cgroup = cgroup::Cgroup {
+		now: std::time::Instant::now(),
+		exists: false,
+		usage_usec: 0,
+		cpu_quota: None,
+		memory_current: 0,
+		memory_max: None,
+		read_bytes: 0,
+		written_bytes: 0,
+		pids_current: 0,
+	};
+
+	let mut previous_cgroups: Box<[cgroup::Cgroup]> = Box::default();
+	let mut cgroups: Box<[cgroup::Cgroup]> = Box::default();
+	let mut message_cgroups: Box<[sensord_common::Cgroup<'_>]> = Box::default();
+
+	let mut previous_container_cgroup = empty_cgroup.clone();
+	let mut container_cgroup = empty_cgroup.clone();
+
+	let mut processes = None;
+	let mut message_processes: Vec<sensord_common::Process<'_>> = vec![];
+
 	let num_cpus = u32::try_from(num_cpus).map_err(|err| Error::Other(err.into()))?;
 
-	let mut message_sensor_groups: Box<[sensord_common::SensorGroup<'_>]> =
-		config.sensors.iter()
-		.map(|sensor_group| sensord_common::SensorGroup {
-			name: (&sensor_group.name).into(),
-			temps:
-				sensor_group.temps.iter()
-				.map(|sensor| {
-					sensord_common::TempSensor {
-						name: sensor.name.as_ref().map_or("", AsRef::as_ref).into(),
-						value: 0.,
+	let mut message_sensor_groups: Box<[sensord_common::SensorGroup<'_>]> = Box::default();
+
+	// The previous sample of each file sensor, to compute the rate of the ones that are counters.
+	let mut file_counters: Box<[Box<[hwmon::Counter]>]> = Box::default();
+
+	// The recent readings of each sensor, for its valid range and smoothing. See `filter::State`
+	let mut filter_states: Box<[filter::SensorGroup]> = Box::default();
+
+	// Command sensors run their commands on their own threads. See `command::Sensor`
+	let mut command_sensors: Box<[Box<[command::Sensor]>]> = Box::default();
+
+	let mut previous_networks: Box<[hwmon::Network]> = Box::default();
+	let mut networks: Box<[hwmon::Network]> = Box::default();
+	let mut message_networks: Box<[sensord_common::Network<'_>]> = Box::default();
+
+	// Each sensor and section is read independently of the others. If a read fails, the error is published
+	// and the rest of the message is unaffected. See `health::Health`
+	let mut cpu_frequency_health: health::Health = Default::default();
+	let mut proc_stat_health: health::Health = Default::default();
+	let mut interrupt_sources_health: health::Health = Default::default();
+	let mut vmstat_health: health::Health = Default::default();
+	let mut container_health: health::Health = Default::default();
+	let mut processes_health: health::Health = Default::default();
+	let mut network_addresses_health: health::Health = Default::default();
+	let mut sensor_group_healths: Box<[health::SensorGroup]> = Box::default();
+	let mut network_healths: Box<[health::Health]> = Box::default();
+	let mut cgroup_healths: Box<[health::Health]> = Box::default();
+
+	// Each section is sampled at its own interval, and the message is published every tick with the most recent values of every section.
+	// See `Schedule`
+	let mut cpus_schedule: Schedule = Default::default();
+	let mut interrupts_schedule: Schedule = Default::default();
+	let mut vmstat_schedule: Schedule = Default::default();
+	let mut processes_schedule: Schedule = Default::default();
+	let mut sensor_group_schedules: Box<[Schedule]> = Box::default();
+	let mut network_schedules: Box<[Schedule]> = Box::default();
+	let mut cgroup_schedules: Box<[Schedule]> = Box::default();
+
+	let mut cpus_timestamp = 0;
+	let mut processes_timestamp = 0;
+	let mut interrupts_timestamp = 0;
+	let mut interrupt_rates = (0., 0., 0.);
+	let mut message_vmstat = sensord_common::VmStat {
+		timestamp: 0,
+		swap_ins: 0.,
+		swap_outs: 0.,
+		page_faults: 0.,
+		major_page_faults: 0.,
+		pages_scanned: 0.,
+		pages_stolen: 0.,
+		oom_kills: 0,
+		thp: std::borrow::Cow::Borrowed(&[]),
+	};
+
+	let mut cpu_average_usage = 0.;
+	let mut container: sensord_common::Container = Default::default();
+	let mut network_addresses = Default::default();
+
+	let mut uevents = uevent::Monitor::new();
+
+	let empty_network = || hwmon::Network {
+		now: std::time::Instant::now(),
+		rx: 0,
+		tx: 0,
+		addresses: vec![],
+	};
+
+	// The state that depends on the config is (re)initialized at the start of the next iteration when this is set.
+	let mut config_changed = true;
+
+	let tick_interval = std::cell::Cell::new(config.tick_interval(config.interval));
+
+	// Set while no client is subscribed. See below.
+	let mut idle = false;
+
+	interval(&tick_interval, |duration| clients.sleep(duration), || {
+		// While no client is subscribed, everything is sampled at `max_interval` as a keep-alive for clients that only listen to the signal,
+		// like dbus-monitor. Command sensors are stopped since they run at their own intervals. Sampling speeds up again as soon as a client subscribes.
+		let idle_changed = {
+			let new_idle = !clients.has_subscribers()?;
+			new_idle != std::mem::replace(&mut idle, new_idle)
+		};
+		if idle_changed {
+			if idle {
+				eprintln!("no clients are subscribed, slowing down to max_interval");
+			}
+			else {
+				eprintln!("a client subscribed, resuming");
+			}
+		}
+
+		if RELOAD_CONFIG.swap(false, std::sync::atomic::Ordering::Relaxed) {
+			// The new config is only swapped in if it's valid. Otherwise the previous config continues to be used.
+			match load_config(config_path) {
+				Ok((new_config_files, new_config)) => {
+					eprintln!("reloaded config");
+
+					config_files = new_config_files;
+					config = new_config;
+					config_changed = true;
+				},
+
+				Err(err) => eprint!("error: could not reload config, continuing to use the previous config: {err}"),
+			}
+		}
+
+		// hwmon, power_supply, thermal and net devices are resolved to their sysfs paths when the config is parsed,
+		// so when devices are added or removed the parts of the config that refer to them are resolved again to pick up their new paths.
+		// Only the paths of the devices that changed are swapped in, so the state of everything else is kept.
+		let device_changes = uevents.changes();
+
+		if device_changes.devices || device_changes.thermal {
+			// Thermal zones are only looked up again if thermal devices changed.
+			let thermal_zones = (!device_changes.thermal).then_some(&*config.thermal_zones);
+			match config_files.resolve_devices(thermal_zones) {
+				Ok(config::Devices { hwmon: new_hwmon, power_supply: new_power_supply, sensors: new_sensors, thermal_zones: new_thermal_zones }) => {
+					eprintln!("devices changed, re-resolved their paths");
+
+					let new_message_sensor_groups = self::message_sensor_groups_of(&new_sensors);
+					if same_sensors(&message_sensor_groups, &new_message_sensor_groups) {
+						update_availability(&mut message_sensor_groups, &new_message_sensor_groups);
+						reset_changed_healths(&config.sensors, &new_sensors, &mut sensor_group_healths);
 					}
-				})
-				.collect(),
-			fans:
-				sensor_group.fans.iter()
-				.map(|sensor| {
-					sensord_common::FanSensor {
-						name: sensor.name.as_ref().map_or("", AsRef::as_ref).into(),
-						fan: 0,
-						pwm: 0,
+					else {
+						// A label pattern or `num = "all"` matches different sensors of the new device,
+						// so the state of the sensors is reinitialized. Command sensors don't depend on devices, so they keep running.
+						message_sensor_groups = new_message_sensor_groups;
+						sensor_group_healths = new_sensors.iter().map(health::SensorGroup::new).collect();
+						sensor_group_schedules = vec![Default::default(); new_sensors.len()].into_boxed_slice();
+						filter_states = new_sensors.iter().map(filter::SensorGroup::new).collect();
 					}
-				})
-				.collect(),
-			bats:
-				sensor_group.bats.iter()
-				.map(|sensor| {
-					sensord_common::BatSensor {
-						name: sensor.name.as_ref().map_or("", AsRef::as_ref).into(),
-						capacity: 0,
-						charging: false,
+
+					config.hwmon = new_hwmon;
+					config.power_supply = new_power_supply;
+					config.sensors = new_sensors;
+					config.thermal_zones = new_thermal_zones;
+				},
+
+				// Eg a device that isn't optional was removed. Keep using the previous paths until the device comes back.
+				Err(err) => eprint!("warning: devices changed but their paths could not be re-resolved: {err}"),
+			}
+		}
+
+		let networks_changed: Vec<_> = config.networks.iter().map(|network| device_changes.network(&network.name)).collect();
+		if networks_changed.contains(&true) {
+			match config_files.resolve_networks() {
+				Ok(new_networks) => {
+					eprintln!("network interfaces changed, re-resolved their paths");
+
+					// The counters of a network interface that was re-added start from zero again,
+					// so its previous sample is discarded to not compute a bogus rate against it.
+					for ((((network, new_network), changed), previous_network), network_sample) in
+						config.networks.iter_mut()
+						.zip(new_networks)
+						.zip(networks_changed)
+						.zip(&mut *previous_networks)
+						.zip(&mut *networks)
+					{
+						if changed {
+							*network = new_network;
+							*previous_network = empty_network();
+							*network_sample = empty_network();
+						}
 					}
+				},
+
+				Err(err) => eprint!("warning: network interfaces changed but their paths could not be re-resolved: {err}"),
+			}
+		}
+
+		let reinit = std::mem::take(&mut config_changed);
+		if reinit {
+			clients.set_bounds(config.min_interval, config.max_interval);
+
+			message_top_interrupts.clear();
+			message_top_softirqs.clear();
+
+			previous_cgroups = vec![empty_cgroup.clone(); config.cgroups.len()].into_boxed_slice();
+			cgroups.clone_from(&previous_cgroups);
+			message_cgroups =
+				config.cgroups.iter()
+				.map(|cgroup| sensord_common::Cgroup {
+					name: cgroup.name.clone().into(),
+					timestamp: 0,
+					error: "".into(),
+					exists: false,
+					cpu: 0.,
+					memory: 0,
+					memory_max: u64::MAX,
+					read: 0.,
+					write: 0.,
+					tasks: 0,
 				})
-				.collect(),
-		})
-		.collect::<Vec<_>>()
-		.into_boxed_slice();
-
-	let mut previous_networks =
-		vec![
-			hwmon::Network {
-				now: std::time::Instant::now(),
-				rx: 0,
-				tx: 0,
-				addresses: vec![],
-			};
-			config.networks.len()
-		].into_boxed_slice();
-	let mut networks = previous_networks.clone();
-	let mut message_networks: Box<[sensord_common::Network<'_>]> =
-		config.networks.iter()
-		.map(|network| sensord_common::Network {
-			name: (&network.name).into(),
-			rx: 0.,
-			tx: 0.,
-			addresses: vec![],
-		})
-		.collect::<Vec<_>>()
-		.into_boxed_slice();
+				.collect::<Vec<_>>()
+				.into_boxed_slice();
+			cgroup_healths = vec![Default::default(); config.cgroups.len()].into_boxed_slice();
+			cgroup_schedules = vec![Default::default(); config.cgroups.len()].into_boxed_slice();
+
+			// The container cgroup may have moved, so the previous sample is discarded.
+			previous_container_cgroup = empty_cgroup.clone();
+			if config.container_cgroup.is_none() {
+				container = Default::default();
+			}
 
-	interval(config.interval, || {
-		if config.cpus.use_sysfs {
-			for (id, cpu) in cpus.iter_mut().enumerate() {
-				hwmon::parse_scaling_cur_freq(id, &mut cpu.1, &mut buf)?;
+			// The sampler is kept if processes are still enabled, so that their CPU usage doesn't reset.
+			if config.processes.top == 0 {
+				processes = None;
+				message_processes.clear();
+			}
+			else if processes.is_none() {
+				// The rest of the message is still published, and creating the sampler is retried when the config is reloaded.
+				match process::Sampler::new() {
+					Ok(sampler) => processes = Some(sampler),
+					Err(err) => eprint!("error: could not sample processes, the [processes] section is disabled: {err}"),
+				}
 			}
+
+			message_sensor_groups = self::message_sensor_groups(&config);
+			sensor_group_healths = config.sensors.iter().map(health::SensorGroup::new).collect();
+			sensor_group_schedules = vec![Default::default(); config.sensors.len()].into_boxed_slice();
+			file_counters = config.sensors.iter().map(|sensor_group| vec![Default::default(); sensor_group.files.len()].into_boxed_slice()).collect();
+			filter_states = config.sensors.iter().map(filter::SensorGroup::new).collect();
+			// The counters of a network interface that was re-added start from zero again,
+			// so the previous samples are discarded to not compute a bogus rate against them.
+			previous_networks = vec![empty_network(); config.networks.len()].into_boxed_slice();
+			networks.clone_from(&previous_networks);
+			message_networks =
+				config.networks.iter()
+				.map(|network| sensord_common::Network {
+					name: network.name.clone().into(),
+					timestamp: 0,
+					error: "".into(),
+					rx: 0.,
+					tx: 0.,
+					addresses: vec![],
+				})
+				.collect::<Vec<_>>()
+				.into_boxed_slice();
+			network_healths = vec![Default::default(); config.networks.len()].into_boxed_slice();
+			network_schedules = vec![Default::default(); config.networks.len()].into_boxed_slice();
 		}
-		else {
-			hwmon::parse_proc_cpuinfo(
+
+		if reinit || idle_changed {
+			// The threads of the previous command sensors stop when they're dropped here.
+			command_sensors =
+				config.sensors.iter()
+				.map(|sensor_group|
+					if idle {
+						Box::default()
+					}
+					else {
+						sensor_group.commands.iter()
+						.map(|sensor| command::Sensor::spawn(format!("{} command sensor {}", sensor_group.name, sensor.name), sensor))
+						.collect()
+					})
+				.collect();
+		}
+
+		// Clients can request a different interval for the sections that don't set their own. The shortest requested one wins.
+		let interval = if idle { config.max_interval } else { clients.interval().unwrap_or(config.interval) };
+		let section_interval = |section_interval: Option<std::time::Duration>| {
+			let section_interval = section_interval.unwrap_or(interval);
+			if idle { section_interval.max(config.max_interval) } else { section_interval }
+		};
+		tick_interval.set(if idle { config.max_interval } else { config.tick_interval(interval) });
+
+		let now = std::time::Instant::now();
+		let timestamp = self::timestamp();
+
+		let cpus_due = cpus_schedule.due(now, section_interval(config.cpus_interval), tick_interval.get());
+		let interrupts_due = interrupts_schedule.due(now, section_interval(config.interrupts_interval), tick_interval.get());
+		let vmstat_due = vmstat_schedule.due(now, section_interval(config.vmstat_interval), tick_interval.get());
+		let processes_due = processes_schedule.due(now, section_interval(config.processes_interval), tick_interval.get());
+		let sensor_groups_due: Vec<_> =
+			config.sensors.iter().zip(&mut *sensor_group_schedules)
+			.map(|(sensor_group, schedule)| schedule.due(now, section_interval(sensor_group.interval), tick_interval.get()))
+			.collect();
+		let networks_due: Vec<_> =
+			config.networks.iter().zip(&mut *network_schedules)
+			.map(|(network, schedule)| schedule.due(now, section_interval(network.interval), tick_interval.get()))
+			.collect();
+		let cgroups_due: Vec<_> =
+			config.cgroups.iter().zip(&mut *cgroup_schedules)
+			.map(|(cgroup, schedule)| schedule.due(now, section_interval(cgroup.interval), tick_interval.get()))
+			.collect();
+
+		if cpus_due {
+			if config.cpus.use_sysfs {
+				cpu_frequency_health.sample("CPU frequency", || {
+					for (id, cpu) in cpus.iter_mut().enumerate() {
+						hwmon::parse_scaling_cur_freq(id, &mut cpu.1, &mut buf)?;
+					}
+					Ok(())
+				});
+			}
+			else {
+				cpu_frequency_health.sample("CPU frequency", || hwmon::parse_proc_cpuinfo(
+					&mut cpus,
+					&proc_cpu_info_line_regex,
+					&mut buf,
+				));
+			}
+		}
+
+		// /proc/stat has both the CPU usage and the interrupt counts. When only one of the sections is due, the other one's counts are updated too,
+		// but its values are only computed from them when it's due.
+		let proc_stat_ok = (cpus_due || interrupts_due) && {
+			interrupts.now = std::time::Instant::now();
+			proc_stat_health.sample("/proc/stat", || hwmon::parse_proc_stat(
+				&mut average_cpu,
 				&mut cpus,
-				&proc_cpu_info_line_regex,
+				&mut interrupts,
 				&mut buf,
-			)?;
+			)).is_some()
+		};
+
+		let interrupt_sources_ok =
+			interrupts_due && (
+				config.interrupts.top == 0 ||
+				interrupt_sources_health.sample("interrupt sources", || {
+					hwmon::parse_proc_interrupts("/proc/interrupts".as_ref(), cpus.len(), &mut interrupts.interrupt_sources, &mut buf)?;
+					hwmon::parse_proc_interrupts("/proc/softirqs".as_ref(), cpus.len(), &mut interrupts.softirq_sources, &mut buf)?;
+					Ok(())
+				}).is_some()
+			);
+
+		let cgroups_ok: Vec<_> =
+			config.cgroups.iter().zip(&mut *cgroups).zip(&mut *cgroup_healths).zip(&cgroups_due)
+			.map(|(((cgroup_spec, cgroup), health), &due)|
+				due && health.sample(format_args!("cgroup {}", cgroup_spec.name), || cgroup.update(&cgroup_spec.path, &mut buf)).is_some())
+			.collect();
+
+		let container_ok =
+			cpus_due &&
+			config.container_cgroup.as_ref()
+			.is_some_and(|path| container_health.sample("container cgroup", || container_cgroup.update(path, &mut buf)).is_some());
+
+		let vmstat_ok = vmstat_due && {
+			vmstat.now = std::time::Instant::now();
+			vmstat_health.sample("/proc/vmstat", || hwmon::parse_proc_vmstat("/proc/vmstat".as_ref(), &mut vmstat, &mut buf)).is_some()
+		};
+
+		// The addresses are read if any network is due. If they can't be read, the previous addresses are published.
+		if networks_due.contains(&true) &&
+			let Some(addresses) = network_addresses_health.sample("network addresses", hwmon::Network::addresses)
+		{
+			network_addresses = addresses;
 		}
 
-		hwmon::parse_proc_stat(
-			&mut average_cpu,
-			&mut cpus,
-			&mut buf,
-		)?;
+		let networks_ok: Vec<_> =
+			config.networks.iter().zip(&mut *networks).zip(&mut *network_healths).zip(&networks_due)
+			.map(|(((network_spec, network), health), &due)|
+				due && health.sample(format_args!("network {}", network_spec.name), || network.update(network_spec, &network_addresses, &mut buf)).is_some())
+			.collect();
 
-		hwmon::Network::update_all(config.networks.iter().zip(networks.iter_mut()), &mut buf)?;
+		if cpus_due {
+			for (&(_, frequency), message_cpu) in cpus.iter().zip(&mut *message_cpus) {
+				message_cpu.frequency = frequency;
+			}
+		}
 
-		for ((previous_cpu, &(cpu, frequency)), message_cpu) in previous_cpus.iter_mut().zip(&*cpus).zip(&mut *message_cpus) {
-			let diff_total = cpu.total - previous_cpu.total;
-			let diff_used = cpu.used - previous_cpu.used;
+		if cpus_due && proc_stat_ok {
+			cpus_timestamp = timestamp;
 
-			*previous_cpu = cpu;
+			for ((previous_cpu, &(cpu, _)), message_cpu) in previous_cpus.iter_mut().zip(&*cpus).zip(&mut *message_cpus) {
+				let diff_total = cpu.total - previous_cpu.total;
+				let diff_used = cpu.used - previous_cpu.used;
 
-			#[allow(clippy::cast_precision_loss)]
-			let usage = if diff_total == 0 { 0. } else { (100 * diff_used) as f64 / diff_total as f64 };
+				*previous_cpu = cpu;
+
+				#[allow(clippy::cast_precision_loss)]
+				let usage = if diff_total == 0 { 0. } else { (100 * diff_used) as f64 / diff_total as f64 };
+
+				message_cpu.usage = usage;
+			}
 
-			message_cpu.usage = usage;
-			message_cpu.frequency = frequency;
+			cpu_average_usage = {
+				let diff_total = average_cpu.total - previous_average_cpu.total;
+				let diff_used = average_cpu.used - previous_average_cpu.used;
+
+				previous_average_cpu = average_cpu;
+
+				#[allow(clippy::cast_precision_loss)]
+				let usage = if diff_total == 0 { 0. } else { (100 * diff_used) as f64 / diff_total as f64 };
+				usage
+			};
 		}
 
-		let cpu_average_usage = {
-			let diff_total = average_cpu.total - previous_average_cpu.total;
-			let diff_used = average_cpu.used - previous_average_cpu.used;
+		// A reading that the sensor's filter rejects is reported as the sensor's error, and its previous value is kept.
+		for ((((((sensor_group, sensor_group_health), file_counters), filter_states), command_sensors), message_sensor_group), &due) in
+			config.sensors.iter()
+			.zip(&mut *sensor_group_healths)
+			.zip(&mut *file_counters)
+			.zip(&mut *filter_states)
+			.zip(&*command_sensors)
+			.zip(&mut *message_sensor_groups)
+			.zip(&sensor_groups_due)
+		{
+			if !due {
+				continue;
+			}
+
+			message_sensor_group.timestamp = timestamp;
 
-			previous_average_cpu = average_cpu;
+			for (((sensor, health), filter_state), message_temp_sensor) in
+				sensor_group.temps.iter().zip(&mut *sensor_group_health.temps).zip(&mut *filter_states.temps).zip(&mut *message_sensor_group.temps)
+			{
+				let (temp, available, mut error) = sample_sensor(
+					health,
+					format_args!("{} temp sensor {}", sensor_group.name, message_temp_sensor.name),
+					sensor.path.is_some(),
+					|| hwmon::parse_temp_sensor(sensor.path.as_deref(), &mut buf),
+				);
+				if let Some(temp) = temp {
+					match temp.map(|temp| filter_state.apply(&sensor.filter, sensor.calibration.apply(temp))).transpose() {
+						Ok(temp) => message_temp_sensor.value = temp.unwrap_or_default(),
+						Err(err) => error = err,
+					}
+				}
 
-			#[allow(clippy::cast_precision_loss)]
-			let usage = if diff_total == 0 { 0. } else { (100 * diff_used) as f64 / diff_total as f64 };
-			usage
-		};
+				// The calibration is applied to the trip points too, so that they stay in line with the sensor's value.
+				if let Some(thermal_zone) = &sensor.thermal_zone {
+					let hwmon::ThermalZone { trip_points, policy, mode } = hwmon::parse_thermal_zone(thermal_zone, &mut buf);
+					message_temp_sensor.trip_points =
+						trip_points.into_iter()
+						.map(|(kind, temp)| sensord_common::TripPoint {
+							kind: kind.into(),
+							temp: sensor.calibration.apply(temp),
+						})
+						.collect();
+					message_temp_sensor.policy = policy.into();
+					message_temp_sensor.mode = mode.into();
+				}
+				message_temp_sensor.available = available;
+				message_temp_sensor.error = error.into();
+			}
 
-		for (sensor_group, message_sensor_group) in config.sensors.iter().zip(&mut *message_sensor_groups) {
-			for (sensor, message_temp_sensor) in sensor_group.temps.iter().zip(&mut *message_sensor_group.temps) {
-				let temp = hwmon::parse_temp_sensor(sensor.path.as_deref(), &mut buf)?.map(|temp| temp + sensor.offset);
-				message_temp_sensor.value = temp.unwrap_or_default();
+			for (((sensor, health), filter_state), message_fan_sensor) in
+				sensor_group.fans.iter().zip(&mut *sensor_group_health.fans).zip(&mut *filter_states.fans).zip(&mut *message_sensor_group.fans)
+			{
+				let (fan_and_pwm, available, mut error) = sample_sensor(
+					health,
+					format_args!("{} fan sensor {}", sensor_group.name, message_fan_sensor.name),
+					sensor.fan_path.is_some(),
+					|| Ok((
+						hwmon::parse_fan_sensor(sensor.fan_path.as_deref(), &mut buf)?,
+						hwmon::parse_pwm_sensor(sensor.pwm_path.as_deref(), &mut buf)?,
+					)),
+				);
+				if let Some((fan, pwm)) = fan_and_pwm {
+					match fan.map(|fan| filter_state.apply_u16(&sensor.filter, sensor.calibration.apply(fan.into()))).transpose() {
+						Ok(fan) => message_fan_sensor.fan = fan.unwrap_or_default(),
+						Err(err) => error = err,
+					}
+					message_fan_sensor.pwm = pwm.unwrap_or_default();
+				}
+				message_fan_sensor.available = available;
+				message_fan_sensor.error = error.into();
 			}
 
-			for (sensor, message_fan_sensor) in sensor_group.fans.iter().zip(&mut *message_sensor_group.fans) {
-				let fan = hwmon::parse_fan_sensor(sensor.fan_path.as_deref(), &mut buf)?;
-				let pwm = hwmon::parse_pwm_sensor(sensor.pwm_path.as_deref(), &mut buf)?;
-				message_fan_sensor.fan = fan.unwrap_or_default();
-				message_fan_sensor.pwm = pwm.unwrap_or_default();
+			for (((sensor, health), filter_state), message_bat_sensor) in
+				sensor_group.bats.iter().zip(&mut *sensor_group_health.bats).zip(&mut *filter_states.bats).zip(&mut *message_sensor_group.bats)
+			{
+				let (capacity_and_charging, available, mut error) = sample_sensor(
+					health,
+					format_args!("{} battery sensor {}", sensor_group.name, message_bat_sensor.name),
+					sensor.capacity_path.is_some(),
+					|| Ok((
+						hwmon::parse_bat_capacity_sensor(sensor.capacity_path.as_deref(), &mut buf)?,
+						hwmon::parse_bat_status_sensor(sensor.status_path.as_deref(), &mut buf)?,
+					)),
+				);
+				if let Some((capacity, charging)) = capacity_and_charging {
+					match capacity.map(|capacity| filter_state.apply_u8(&sensor.filter, sensor.calibration.apply(capacity.into()))).transpose() {
+						Ok(capacity) => message_bat_sensor.capacity = capacity.unwrap_or_default(),
+						Err(err) => error = err,
+					}
+					message_bat_sensor.charging = charging.unwrap_or_default();
+				}
+				message_bat_sensor.available = available;
+				message_bat_sensor.error = error.into();
 			}
 
-			for (sensor, message_bat_sensor) in sensor_group.bats.iter().zip(&mut *message_sensor_group.bats) {
-				let capacity = hwmon::parse_bat_capacity_sensor(&sensor.capacity_path, &mut buf)?;
-				message_bat_sensor.capacity = capacity.unwrap_or_default();
-				let charging = hwmon::parse_bat_status_sensor(&sensor.status_path, &mut buf)?;
-				message_bat_sensor.charging = charging.unwrap_or_default();
+			for (((sensor, health), filter_state), message_cooling_sensor) in
+				sensor_group.cooling.iter().zip(&mut *sensor_group_health.cooling).zip(&mut *filter_states.cooling).zip(&mut *message_sensor_group.cooling)
+			{
+				let (cur_and_max_state, available, mut error) = sample_sensor(
+					health,
+					format_args!("{} cooling device {}", sensor_group.name, message_cooling_sensor.name),
+					sensor.cur_state_path.is_some(),
+					|| Ok((
+						hwmon::parse_cooling_sensor(sensor.cur_state_path.as_deref(), &mut buf)?,
+						hwmon::parse_cooling_sensor(sensor.max_state_path.as_deref(), &mut buf)?,
+					)),
+				);
+				if let Some((cur_state, max_state)) = cur_and_max_state {
+					match cur_state.map(|cur_state| filter_state.apply_u32(&sensor.filter, cur_state.into())).transpose() {
+						Ok(cur_state) => message_cooling_sensor.cur_state = cur_state.unwrap_or_default(),
+						Err(err) => error = err,
+					}
+					message_cooling_sensor.max_state = max_state.unwrap_or_default();
+				}
+				message_cooling_sensor.available = available;
+				message_cooling_sensor.error = error.into();
+			}
+
+			// The values of file sensors are followed by those of command sensors, and then those of derived sensors.
+			let mut message_value_sensors = message_sensor_group.values.iter_mut();
+
+			for ((((sensor, health), counter), filter_state), message_value_sensor) in
+				sensor_group.files.iter()
+				.zip(&mut *sensor_group_health.files)
+				.zip(&mut **file_counters)
+				.zip(&mut *filter_states.files)
+				.zip(&mut message_value_sensors)
+			{
+				let (value, available, mut error) = sample_sensor(
+					health,
+					format_args!("{} file sensor {}", sensor_group.name, message_value_sensor.name),
+					true,
+					|| hwmon::parse_file_sensor(&sensor.path, sensor.regex.as_ref(), &mut buf),
+				);
+				if let Some(value) = value {
+					let value = if sensor.rate { counter.rate(value) } else { value };
+					match filter_state.apply(&sensor.filter, sensor.calibration.apply(value)) {
+						Ok(value) => message_value_sensor.value = value,
+						Err(err) => error = err,
+					}
+				}
+				message_value_sensor.available = available;
+				message_value_sensor.error = error.into();
+			}
+
+			if idle {
+				for message_value_sensor in message_value_sensors.take(sensor_group.commands.len()) {
+					message_value_sensor.error = "not run while no clients are subscribed".into();
+				}
+			}
+			else {
+				for (command_sensor, message_value_sensor) in command_sensors.iter().zip(&mut message_value_sensors) {
+					let (value, error) = command_sensor.latest();
+					message_value_sensor.value = value.unwrap_or_default();
+					message_value_sensor.error = error.into();
+				}
+			}
+		}
+
+		// Derived sensors are computed once every other sensor has been sampled, since they can refer to sensors of any group.
+		for (((group_index, sensor_group), filter_states), &due) in config.sensors.iter().enumerate().zip(&mut *filter_states).zip(&sensor_groups_due) {
+			if !due {
+				continue;
+			}
+
+			let num_values = sensor_group.files.len() + sensor_group.commands.len();
+			for ((i, sensor), filter_state) in sensor_group.derived.iter().enumerate().zip(&mut *filter_states.derived) {
+				let value = sensor.expression.evaluate(&message_sensor_groups).and_then(|value| filter_state.apply(&sensor.filter, value));
+				let message_value_sensor = &mut message_sensor_groups[group_index].values[num_values + i];
+				match value {
+					Ok(value) => {
+						message_value_sensor.value = value;
+						message_value_sensor.error = "".into();
+					},
+					Err(err) => message_value_sensor.error = err.into(),
+				}
 			}
 		}
 
-		for ((network, previous_network), message_network) in networks.iter_mut().zip(&mut *previous_networks).zip(&mut *message_networks) {
+		for ((((network, previous_network), message_network), health), ok) in
+			networks.iter_mut().zip(&mut *previous_networks).zip(&mut *message_networks).zip(&*network_healths).zip(networks_ok)
+		{
+			message_network.error = health.error().to_owned().into();
+
+			// The previous sample is kept so that the rate is computed over the whole duration since the last successful read.
+			if !ok {
+				continue;
+			}
+
+			message_network.timestamp = timestamp;
+
 			let (rx, tx) =
 				if previous_network.rx == 0 && previous_network.tx == 0 {
 					(0., 0.)
@@ -210,12 +801,215 @@ fn main() -> Result<(), Error> {
 			std::mem::swap(previous_network, network);
 		}
 
+		if interrupts_due {
+			interrupt_rates =
+				if previous_interrupts.context_switches == 0 || !proc_stat_ok || !interrupt_sources_ok {
+					message_top_interrupts.clear();
+					message_top_softirqs.clear();
+					(0., 0., 0.)
+				}
+				else if let Some(duration) = interrupts.now.checked_duration_since(previous_interrupts.now) {
+					let duration = duration.as_secs_f64();
+
+					top_interrupt_sources(
+						&previous_interrupts.interrupt_sources,
+						&interrupts.interrupt_sources,
+						duration,
+						config.interrupts.top,
+						&mut message_top_interrupts,
+					);
+					top_interrupt_sources(
+						&previous_interrupts.softirq_sources,
+						&interrupts.softirq_sources,
+						duration,
+						config.interrupts.top,
+						&mut message_top_softirqs,
+					);
+
+					#[allow(clippy::cast_precision_loss)]
+					let rates = (
+						// The counts can go backwards, eg when a CPU is offlined and its counts are no longer included.
+						interrupts.interrupts.saturating_sub(previous_interrupts.interrupts) as f64 / duration,
+						interrupts.softirqs.saturating_sub(previous_interrupts.softirqs) as f64 / duration,
+						interrupts.context_switches.saturating_sub(previous_interrupts.context_switches) as f64 / duration,
+					);
+					rates
+				}
+				else {
+					(0., 0., 0.)
+				};
+
+			if proc_stat_ok && interrupt_sources_ok {
+				interrupts_timestamp = timestamp;
+				std::mem::swap(&mut previous_interrupts, &mut interrupts);
+			}
+		}
+
+		if vmstat_due {
+			let vmstat_duration =
+				vmstat.now.checked_duration_since(previous_vmstat.now)
+				.filter(|_| previous_vmstat.page_faults != 0 && vmstat_ok)
+				.map(|duration| duration.as_secs_f64());
+			#[allow(clippy::cast_precision_loss)]
+			let vmstat_rate = |value: u64, previous_value: u64| vmstat_duration.map_or(0., |duration| value.saturating_sub(previous_value) as f64 / duration);
+
+			message_thp.clear();
+			message_thp.extend(
+				vmstat.thp.iter()
+				.filter(|_| vmstat_ok)
+				.filter_map(|(name, value)| {
+					let (_, previous_value) = previous_vmstat.thp.iter().find(|(previous_name, _)| previous_name == name)?;
+					Some(sensord_common::VmStatCounter {
+						name: name.clone().into(),
+						rate: vmstat_rate(*value, *previous_value),
+					})
+				}));
+
+			message_vmstat = sensord_common::VmStat {
+				timestamp: if vmstat_ok { timestamp } else { message_vmstat.timestamp },
+				swap_ins: vmstat_rate(vmstat.swap_ins, previous_vmstat.swap_ins),
+				swap_outs: vmstat_rate(vmstat.swap_outs, previous_vmstat.swap_outs),
+				page_faults: vmstat_rate(vmstat.page_faults, previous_vmstat.page_faults),
+				major_page_faults: vmstat_rate(vmstat.major_page_faults, previous_vmstat.major_page_faults),
+				pages_scanned: vmstat_rate(vmstat.pages_scanned, previous_vmstat.pages_scanned),
+				pages_stolen: vmstat_rate(vmstat.pages_stolen, previous_vmstat.pages_stolen),
+				oom_kills: if vmstat_ok { vmstat.oom_kills } else { previous_vmstat.oom_kills },
+				// Borrowed from `message_thp` when the message is published
+				thp: std::borrow::Cow::Borrowed(&[]),
+			};
+
+			if vmstat_ok {
+				std::mem::swap(&mut previous_vmstat, &mut vmstat);
+			}
+		}
+
+		if container_ok {
+			container = {
+				let cpu_quota = container_cgroup.cpu_quota.unwrap_or(f64::from(num_cpus));
+
+				let cpu_usage =
+					if !previous_container_cgroup.exists || !container_cgroup.exists {
+						0.
+					}
+					else if let Some(duration) = container_cgroup.now.checked_duration_since(previous_container_cgroup.now) {
+						#[allow(clippy::cast_precision_loss)]
+						let usage_usec = container_cgroup.usage_usec.saturating_sub(previous_container_cgroup.usage_usec) as f64;
+						100. * usage_usec / 1_000_000. / duration.as_secs_f64() / cpu_quota
+					}
+					else {
+						0.
+					};
+
+				let container = sensord_common::Container {
+					enabled: true,
+					cpu_usage,
+					cpu_quota,
+					memory: container_cgroup.memory_current,
+					memory_max: container_cgroup.memory_max.unwrap_or(u64::MAX),
+				};
+
+				std::mem::swap(&mut previous_container_cgroup, &mut container_cgroup);
+
+				container
+			};
+		}
+
+		for ((((cgroup, previous_cgroup), message_cgroup), health), ok) in
+			cgroups.iter_mut().zip(&mut *previous_cgroups).zip(&mut *message_cgroups).zip(&*cgroup_healths).zip(cgroups_ok)
+		{
+			message_cgroup.error = health.error().to_owned().into();
+
+			if !ok {
+				continue;
+			}
+
+			message_cgroup.timestamp = timestamp;
+
+			let (cpu, read, write) =
+				if !previous_cgroup.exists || !cgroup.exists {
+					(0., 0., 0.)
+				}
+				else if let Some(duration) = cgroup.now.checked_duration_since(previous_cgroup.now) {
+					let duration = duration.as_secs_f64();
+					#[allow(clippy::cast_precision_loss)]
+					let cpu = 100. * cgroup.usage_usec.saturating_sub(previous_cgroup.usage_usec) as f64 / 1_000_000. / duration;
+					#[allow(clippy::cast_precision_loss)]
+					let read = cgroup.read_bytes.saturating_sub(previous_cgroup.read_bytes) as f64 / duration;
+					#[allow(clippy::cast_precision_loss)]
+					let write = cgroup.written_bytes.saturating_sub(previous_cgroup.written_bytes) as f64 / duration;
+					(cpu, read, write)
+				}
+				else {
+					(0., 0., 0.)
+				};
+
+			message_cgroup.exists = cgroup.exists;
+			message_cgroup.cpu = cpu;
+			message_cgroup.memory = cgroup.memory_current;
+			message_cgroup.memory_max = cgroup.memory_max.unwrap_or(u64::MAX);
+			message_cgroup.read = read;
+			message_cgroup.write = write;
+			message_cgroup.tasks = cgroup.pids_current;
+
+			std::mem::swap(previous_cgroup, cgroup);
+		}
+
+		if processes_due &&
+			let Some(processes) = &mut processes &&
+			let Some(processes) = processes_health.sample("processes", || processes.update(config.processes.top, &mut buf))
+		{
+			processes_timestamp = timestamp;
+			message_processes.clear();
+			message_processes.extend(
+				processes.iter()
+				.map(|process| sensord_common::Process {
+					pid: process.pid,
+					name: process.name.clone().into(),
+					user: process.user.clone().into(),
+					cpu: process.cpu,
+					rss: process.rss,
+				}));
+		}
+
 		let body = sensord_common::SensorsMessage {
+			timestamp,
+			interval: u32::try_from(interval.as_millis()).unwrap_or(u32::MAX),
 			num_cpus,
+			cpus_timestamp,
 			cpus: std::borrow::Cow::Borrowed(&message_cpus),
 			cpu_average_usage,
+			container,
 			sensors: std::borrow::Cow::Borrowed(&*message_sensor_groups),
 			networks: std::borrow::Cow::Borrowed(&message_networks),
+			interrupts: sensord_common::Interrupts {
+				timestamp: interrupts_timestamp,
+				interrupts: interrupt_rates.0,
+				softirqs: interrupt_rates.1,
+				context_switches: interrupt_rates.2,
+				top_interrupts: std::borrow::Cow::Borrowed(&message_top_interrupts),
+				top_softirqs: std::borrow::Cow::Borrowed(&message_top_softirqs),
+			},
+			vmstat: sensord_common::VmStat {
+				thp: std::borrow::Cow::Borrowed(&message_thp),
+				..message_vmstat
+			},
+			processes: std::borrow::Cow::Borrowed(&message_processes),
+			processes_timestamp,
+			cgroups: std::borrow::Cow::Borrowed(&message_cgroups),
+			errors:
+				[
+					&cpu_frequency_health,
+					&proc_stat_health,
+					&interrupt_sources_health,
+					&vmstat_health,
+					&container_health,
+					&processes_health,
+					&network_addresses_health,
+				].into_iter()
+				.map(health::Health::error)
+				.filter(|error| !error.is_empty())
+				.map(Into::into)
+				.collect(),
 		};
 
 		let body = dbus_pure::proto::ToVariant::to_variant(&body);
@@ -242,7 +1036,8 @@ fn main() -> Result<(), Error> {
 }
 
 fn interval(
-	interval: std::time::Duration,
+	interval: &std::cell::Cell<std::time::Duration>,
+	mut sleep: impl FnMut(std::time::Duration),
 	mut f: impl FnMut() -> Result<bool, Error>,
 ) -> Result<(), Error> {
 	loop {
@@ -253,13 +1048,270 @@ fn interval(
 		}
 
 		let iteration_end = std::time::Instant::now();
-		let Some(sleep_duration) = (iteration_start + interval).checked_duration_since(iteration_end) else { continue; };
-		std::thread::sleep(sleep_duration);
+		let Some(sleep_duration) = (iteration_start + interval.get()).checked_duration_since(iteration_end) else { continue; };
+		sleep(sleep_duration);
+	}
+
+	Ok(())
+}
+
+// Decides when a section is due to be sampled, so that every section is sampled at its own interval
+// while the main loop ticks at the shortest interval of all of them.
+#[derive(Clone, Debug, Default)]
+struct Schedule {
+	// When the most recent sample was due, which can be slightly before or after it was actually taken.
+	last_due: Option<std::time::Instant>,
+}
+
+impl Schedule {
+	// Ticks are never exactly `tick_interval` apart, so a section is due once its interval has elapsed give or take half a tick.
+	// Otherwise eg a section with an interval of 1s would be sampled every fifth tick of 250ms instead of every fourth.
+	//
+	// The next sample is due one interval after the previous one was due rather than after it was taken, so that the tolerance doesn't add up.
+	// Otherwise eg a section with an interval of 1s would be sampled on every tick of 750ms, instead of on 4 out of every 3 ticks.
+	// If a sample is more than a whole interval late, eg because the interval was just shortened, the schedule starts over from now instead of catching up.
+	fn due(&mut self, now: std::time::Instant, interval: std::time::Duration, tick_interval: std::time::Duration) -> bool {
+		let next_due = self.last_due.map(|last_due| last_due + interval);
+		let due = next_due.is_none_or(|next_due| now + tick_interval / 2 >= next_due);
+		if due {
+			self.last_due = Some(next_due.filter(|&next_due| now < next_due + interval).unwrap_or(now));
+		}
+		due
+	}
+}
+
+// The current time in milliseconds since the Unix epoch, for the timestamps of the message.
+fn timestamp() -> u64 {
+	let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+	u64::try_from(now.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn load_config(path: &std::path::Path) -> Result<(config::Files, config::Config), Error> {
+	let files = config::Files::read(path)?;
+	let config = files.resolve()?;
+	Ok((files, config))
+}
+
+fn handle_sighup() -> Result<(), Error> {
+	extern "C" fn handler(_signum: libc::c_int) {
+		RELOAD_CONFIG.store(true, std::sync::atomic::Ordering::Relaxed);
+	}
+
+	unsafe {
+		let mut action: libc::sigaction = std::mem::zeroed();
+		action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+		// Restart interrupted syscalls, so that a SIGHUP doesn't break an in-progress read of a sensor or the D-Bus connection.
+		action.sa_flags = libc::SA_RESTART;
+		if libc::sigemptyset(&raw mut action.sa_mask) == -1 || libc::sigaction(libc::SIGHUP, &raw const action, std::ptr::null_mut()) == -1 {
+			return Err(Error::Other(std::io::Error::last_os_error().into()));
+		}
 	}
 
 	Ok(())
 }
 
+fn message_sensor_groups(config: &config::Config) -> Box<[sensord_common::SensorGroup<'static>]> {
+	message_sensor_groups_of(&config.sensors)
+}
+
+fn message_sensor_groups_of(sensors: &[config::SensorGroup]) -> Box<[sensord_common::SensorGroup<'static>]> {
+	sensors.iter()
+	.map(|sensor_group| sensord_common::SensorGroup {
+		name: sensor_group.name.clone().into(),
+		timestamp: 0,
+		temps:
+			sensor_group.temps.iter()
+			.map(|sensor| {
+				sensord_common::TempSensor {
+					name: sensor.name.clone().unwrap_or_default().into(),
+					available: sensor.path.is_some(),
+					error: "".into(),
+					value: 0.,
+					trip_points: vec![],
+					policy: "".into(),
+					mode: "".into(),
+				}
+			})
+			.collect(),
+		fans:
+			sensor_group.fans.iter()
+			.map(|sensor| {
+				sensord_common::FanSensor {
+					name: sensor.name.clone().unwrap_or_default().into(),
+					available: sensor.fan_path.is_some(),
+					error: "".into(),
+					fan: 0,
+					pwm: 0,
+				}
+			})
+			.collect(),
+		bats:
+			sensor_group.bats.iter()
+			.map(|sensor| {
+				sensord_common::BatSensor {
+					name: sensor.name.clone().unwrap_or_default().into(),
+					available: sensor.capacity_path.is_some(),
+					error: "".into(),
+					capacity: 0,
+					charging: false,
+				}
+			})
+			.collect(),
+		cooling:
+			sensor_group.cooling.iter()
+			.map(|sensor| {
+				sensord_common::CoolingSensor {
+					name: sensor.name.clone().unwrap_or_default().into(),
+					available: sensor.cur_state_path.is_some(),
+					error: "".into(),
+					cur_state: 0,
+					max_state: 0,
+				}
+			})
+			.collect(),
+		values:
+			sensor_group.files.iter().map(|sensor| (&sensor.name, &sensor.unit))
+			.chain(sensor_group.commands.iter().map(|sensor| (&sensor.name, &sensor.unit)))
+			.chain(sensor_group.derived.iter().map(|sensor| (&sensor.name, &sensor.unit)))
+			.map(|(name, unit)| {
+				sensord_common::ValueSensor {
+					name: name.clone().into(),
+					available: true,
+					error: "".into(),
+					value: 0.,
+					unit: unit.clone().into(),
+				}
+			})
+			.collect(),
+	})
+	.collect::<Vec<_>>()
+	.into_boxed_slice()
+}
+
+// Whether the sensor groups have the same sensors, so that only their availability can differ.
+fn same_sensors(sensor_groups: &[sensord_common::SensorGroup<'_>], new_sensor_groups: &[sensord_common::SensorGroup<'_>]) -> bool {
+	sensor_groups.len() == new_sensor_groups.len() &&
+		sensor_groups.iter().zip(new_sensor_groups).all(|(sensor_group, new_sensor_group)|
+			sensor_group.temps.iter().map(|sensor| &sensor.name).eq(new_sensor_group.temps.iter().map(|sensor| &sensor.name)) &&
+			sensor_group.fans.iter().map(|sensor| &sensor.name).eq(new_sensor_group.fans.iter().map(|sensor| &sensor.name)) &&
+			sensor_group.bats.iter().map(|sensor| &sensor.name).eq(new_sensor_group.bats.iter().map(|sensor| &sensor.name)) &&
+			sensor_group.cooling.iter().map(|sensor| &sensor.name).eq(new_sensor_group.cooling.iter().map(|sensor| &sensor.name)) &&
+			sensor_group.values.iter().map(|sensor| &sensor.name).eq(new_sensor_group.values.iter().map(|sensor| &sensor.name)))
+}
+
+fn update_availability(sensor_groups: &mut [sensord_common::SensorGroup<'_>], new_sensor_groups: &[sensord_common::SensorGroup<'_>]) {
+	for (sensor_group, new_sensor_group) in sensor_groups.iter_mut().zip(new_sensor_groups) {
+		for (sensor, new_sensor) in sensor_group.temps.iter_mut().zip(&new_sensor_group.temps) {
+			sensor.available = new_sensor.available;
+		}
+		for (sensor, new_sensor) in sensor_group.fans.iter_mut().zip(&new_sensor_group.fans) {
+			sensor.available = new_sensor.available;
+		}
+		for (sensor, new_sensor) in sensor_group.bats.iter_mut().zip(&new_sensor_group.bats) {
+			sensor.available = new_sensor.available;
+		}
+		for (sensor, new_sensor) in sensor_group.cooling.iter_mut().zip(&new_sensor_group.cooling) {
+			sensor.available = new_sensor.available;
+		}
+	}
+}
+
+// Resets the health of the sensors whose paths changed. A sensor that failed while its device was missing is backed off,
+// so without this it would stay blank for up to `health::MAX_SKIPPED_SAMPLES` samples after its device came back.
+fn reset_changed_healths(sensor_groups: &[config::SensorGroup], new_sensor_groups: &[config::SensorGroup], healths: &mut [health::SensorGroup]) {
+	for ((sensor_group, new_sensor_group), health) in sensor_groups.iter().zip(new_sensor_groups).zip(healths) {
+		for ((sensor, new_sensor), health) in sensor_group.temps.iter().zip(&new_sensor_group.temps).zip(&mut health.temps) {
+			if sensor.path != new_sensor.path {
+				*health = Default::default();
+			}
+		}
+		for ((sensor, new_sensor), health) in sensor_group.fans.iter().zip(&new_sensor_group.fans).zip(&mut health.fans) {
+			if sensor.fan_path != new_sensor.fan_path {
+				*health = Default::default();
+			}
+		}
+		for ((sensor, new_sensor), health) in sensor_group.bats.iter().zip(&new_sensor_group.bats).zip(&mut health.bats) {
+			if sensor.capacity_path != new_sensor.capacity_path {
+				*health = Default::default();
+			}
+		}
+		for ((sensor, new_sensor), health) in sensor_group.cooling.iter().zip(&new_sensor_group.cooling).zip(&mut health.cooling) {
+			if sensor.cur_state_path != new_sensor.cur_state_path {
+				*health = Default::default();
+			}
+		}
+	}
+}
+
+// Samples a sensor whose device may not have been found. Returns the value if the sensor was read, whether the sensor is available,
+// ie its device was found and its most recent read succeeded, and the error of the most recent read.
+fn sample_sensor<T>(
+	health: &mut health::Health,
+	name: impl std::fmt::Display,
+	found: bool,
+	f: impl FnOnce() -> Result<T, Error>,
+) -> (Option<T>, bool, String) {
+	let value = health.sample(name, f);
+	let error = health.error().to_owned();
+	let available = found && error.is_empty();
+	(value, available, error)
+}
+
+fn top_interrupt_sources(
+	previous_sources: &[hwmon::InterruptSource],
+	sources: &[hwmon::InterruptSource],
+	duration: f64,
+	top: usize,
+	message_sources: &mut Vec<sensord_common::InterruptSource<'_>>,
+) {
+	let count_rate = |count: u64, previous_count: u64| {
+		#[allow(clippy::cast_precision_loss)]
+		let rate = count.saturating_sub(previous_count) as f64 / duration;
+		rate
+	};
+
+	let mut rates: Vec<_> =
+		sources.iter().enumerate()
+		.filter_map(|(i, source)| {
+			// The set of sources rarely changes, so the previous source is usually at the same index.
+			let previous_source =
+				previous_sources.get(i).filter(|previous_source| previous_source.name == source.name)
+				.or_else(|| previous_sources.iter().find(|previous_source| previous_source.name == source.name))?;
+			let rate = source.cpus.iter().zip(&previous_source.cpus).map(|(&count, &previous_count)| count_rate(count, previous_count)).sum::<f64>();
+			Some((source, previous_source, rate))
+		})
+		.collect();
+	rates.sort_by(|(_, _, a), (_, _, b)| b.total_cmp(a));
+	rates.truncate(top);
+
+	// Only the top sources are copied into the message. The top sources rarely change either,
+	// so the elements of the previous message are overwritten to reuse their allocations.
+	message_sources.truncate(rates.len());
+	for (i, (source, previous_source, rate)) in rates.into_iter().enumerate() {
+		let cpus = source.cpus.iter().zip(&previous_source.cpus).map(|(&count, &previous_count)| count_rate(count, previous_count));
+
+		if let Some(message_source) = message_sources.get_mut(i) {
+			if *message_source.name != *source.name {
+				message_source.name = source.name.clone().into();
+			}
+			if *message_source.description != *source.description {
+				message_source.description = source.description.clone().into();
+			}
+			message_source.rate = rate;
+			message_source.cpus.clear();
+			message_source.cpus.extend(cpus);
+		}
+		else {
+			message_sources.push(sensord_common::InterruptSource {
+				name: source.name.clone().into(),
+				description: source.description.clone().into(),
+				rate,
+				cpus: cpus.collect(),
+			});
+		}
+	}
+}
+
 enum Error {
 	Path(Box<dyn std::error::Error>, std::path::PathBuf),
 	Other(Box<dyn std::error::Error>),
@@ -300,9 +1352,125 @@ impl std::fmt::Display for Error {
 
 #[dbus_pure_macros::interface("org.freedesktop.DBus")]
 trait OrgFreeDesktopDbusInterface {
+	#[name = "AddMatch"]
+	fn add_match(rule: &str);
+
 	#[name = "RequestName"]
 	fn request_name(name: &str, flags: u32) -> u32;
 }
 
 #[dbus_pure_macros::object(OrgFreeDesktopDbusInterface)]
 struct OrgFreeDesktopDbusObject;
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn sample_sensor() {
+		let dir = std::env::temp_dir().join(format!("sensord-sample-sensor-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("temp1_input");
+		std::fs::write(&path, "45000\n").unwrap();
+
+		let mut health: super::health::Health = Default::default();
+		let mut buf = vec![];
+
+		let (temp, available, error) = super::sample_sensor(&mut health, "temp", true, || super::hwmon::parse_temp_sensor(Some(&path), &mut buf));
+		assert_eq!((temp, available, &*error), (Some(Some(45.)), true, ""));
+
+		// A failing read makes the sensor unavailable, with the error saying why.
+		std::fs::write(&path, "").unwrap();
+		let (temp, available, error) = super::sample_sensor(&mut health, "temp", true, || super::hwmon::parse_temp_sensor(Some(&path), &mut buf));
+		assert_eq!((temp, available), (None, false));
+		assert!(error.contains("temp1_input") && error.contains("file is empty"), "{error:?}");
+
+		// The sensor stays unavailable while its reads are backed off.
+		let (temp, available, error) = super::sample_sensor(&mut health, "temp", true, || super::hwmon::parse_temp_sensor(Some(&path), &mut buf));
+		assert_eq!((temp, available), (None, false));
+		assert!(error.contains("temp1_input") && error.contains("file is empty"), "{error:?}");
+
+		// A sensor whose device was not found is unavailable without an error.
+		let mut health: super::health::Health = Default::default();
+		let (temp, available, error) = super::sample_sensor(&mut health, "temp", false, || super::hwmon::parse_temp_sensor(None, &mut buf));
+		assert_eq!((temp, available, &*error), (Some(None), false, ""));
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn top_interrupt_sources() {
+		let source = |name: &str, cpus: &[u64]| super::hwmon::InterruptSource { name: name.to_owned(), description: format!("{name} device"), cpus: cpus.to_owned() };
+
+		let previous_sources = [
+			source("0", &[100, 100]),
+			source("1", &[100, 100]),
+			source("2", &[100, 100]),
+			source("3", &[100, 100]),
+		];
+		let sources = [
+			// A new source has no previous count to compute a rate against, so it's skipped.
+			source("4", &[1000, 1000]),
+			// The sources are matched by name, even if their order changed.
+			source("3", &[110, 100]),
+			source("2", &[150, 300]),
+			source("1", &[300, 300]),
+			// A count that went backwards, eg because its CPU was offlined and onlined again, has a rate of 0.
+			source("0", &[50, 110]),
+		];
+
+		let mut message_sources = vec![];
+		let top = |message_sources: &Vec<sensord_common::InterruptSource<'_>>| -> Vec<(String, String, f64, Vec<f64>)> {
+			message_sources.iter()
+			.map(|source| (source.name.clone().into_owned(), source.description.clone().into_owned(), source.rate, source.cpus.clone()))
+			.collect()
+		};
+
+		super::top_interrupt_sources(&previous_sources, &sources, 2., 3, &mut message_sources);
+		assert_eq!(top(&message_sources), [
+			("1".to_owned(), "1 device".to_owned(), 200., vec![100., 100.]),
+			("2".to_owned(), "2 device".to_owned(), 125., vec![25., 100.]),
+			("3".to_owned(), "3 device".to_owned(), 5., vec![5., 0.]),
+		]);
+
+		// The previous message is overwritten. Sources with the same rate stay in the order of the file.
+		super::top_interrupt_sources(&sources, &previous_sources, 1., 3, &mut message_sources);
+		assert_eq!(top(&message_sources), [
+			("0".to_owned(), "0 device".to_owned(), 50., vec![50., 0.]),
+			("1".to_owned(), "1 device".to_owned(), 0., vec![0., 0.]),
+			("2".to_owned(), "2 device".to_owned(), 0., vec![0., 0.]),
+		]);
+
+		super::top_interrupt_sources(&previous_sources, &sources[..1], 1., 3, &mut message_sources);
+		assert!(message_sources.is_empty());
+	}
+
+	#[test]
+	fn schedule() {
+		let start = std::time::Instant::now();
+		let ms = std::time::Duration::from_millis;
+
+		// Returns the ticks on which the section was sampled. Every tick is up to 20ms late, like the ticks of the main loop.
+		let sampled = |schedule: &mut super::Schedule, interval, tick_interval, ticks: std::ops::Range<u32>| -> Vec<u32> {
+			ticks
+			.filter(|&tick| schedule.due(start + tick_interval * tick + ms(u64::from(tick % 3) * 10), interval, tick_interval))
+			.collect()
+		};
+
+		// An interval that is a multiple of the tick interval is sampled on every fourth tick.
+		assert_eq!(sampled(&mut Default::default(), ms(1000), ms(250), 0..13), [0, 4, 8, 12]);
+
+		// An interval equal to the tick interval is sampled on every tick.
+		assert_eq!(sampled(&mut Default::default(), ms(250), ms(250), 0..5), [0, 1, 2, 3, 4]);
+
+		// An interval that isn't a multiple of the tick interval is sampled on the tick nearest to when it's due.
+		// On average it's sampled at its interval, ie 12 more times in the 12s after the first sample, rather than on every tick.
+		assert_eq!(sampled(&mut Default::default(), ms(1000), ms(750), 0..17), [0, 1, 3, 4, 5, 7, 8, 9, 11, 12, 13, 15, 16]);
+
+		// An interval of 2.5 ticks alternates between 2 and 3 ticks.
+		assert_eq!(sampled(&mut Default::default(), ms(2500), ms(1000), 0..11), [0, 2, 5, 7, 10]);
+
+		// When the interval is shortened, the section is sampled right away instead of after the previous interval.
+		let mut schedule = Default::default();
+		assert_eq!(sampled(&mut schedule, ms(10000), ms(10000), 0..1), [0]);
+		assert_eq!(sampled(&mut schedule, ms(1000), ms(1000), 1..4), [1, 2, 3]);
+	}
+}
@@ -0,0 +1,238 @@
+pub(crate) struct Sampler {
+	clock_ticks_per_second: f64,
+	now: std::time::Instant,
+	previous_times: std::collections::HashMap<u32, Times>,
+	times: std::collections::HashMap<u32, Times>,
+	processes: Vec<Process>,
+	user_names: std::collections::HashMap<u32, String>,
+}
+
+#[derive(Clone, Copy)]
+struct Times {
+	// Used to detect that a PID has been reused by a different process since the previous sample.
+	start_time: u64,
+	ticks: u64,
+}
+
+pub(crate) struct Process {
+	pub(crate) pid: u32,
+	pub(crate) name: String,
+	uid: u32,
+	pub(crate) user: String,
+	// Percentage of a single CPU, so a process using two CPUs fully has a usage of 200%.
+	pub(crate) cpu: f64,
+	// Resident set size in bytes.
+	pub(crate) rss: u64,
+}
+
+impl Sampler {
+	pub(crate) fn new() -> Result<Self, crate::Error> {
+		let clock_ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+		if clock_ticks_per_second <= 0 {
+			return Err(crate::Error::Other(std::io::Error::last_os_error().into()));
+		}
+		#[allow(clippy::cast_precision_loss)]
+		let clock_ticks_per_second = clock_ticks_per_second as f64;
+
+		Ok(Sampler {
+			clock_ticks_per_second,
+			now: std::time::Instant::now(),
+			previous_times: Default::default(),
+			times: Default::default(),
+			processes: vec![],
+			user_names: Default::default(),
+		})
+	}
+
+	// Returns the union of the `top` processes with the highest CPU usage and the `top` processes with the highest RSS,
+	// ordered by CPU usage.
+	pub(crate) fn update(&mut self, top: usize, buf: &mut Vec<u8>) -> Result<&[Process], crate::Error> {
+		let now = std::time::Instant::now();
+		let duration = now.checked_duration_since(self.now).map(|duration| duration.as_secs_f64()).filter(|&duration| duration > 0.);
+		self.now = now;
+
+		std::mem::swap(&mut self.previous_times, &mut self.times);
+		self.times.clear();
+		self.processes.clear();
+
+		for entry in crate::std2::fs::read_dir("/proc".as_ref())? {
+			let entry = entry?;
+			let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else { continue; };
+
+			// The process can exit at any point while it's being read, so errors are treated as the process not existing.
+			let Some((times, name)) = parse_proc_pid_stat(pid, buf) else { continue; };
+			let Some((uid, rss)) = parse_proc_pid_status(pid, buf) else { continue; };
+
+			let cpu = match (self.previous_times.get(&pid), duration) {
+				(Some(previous_times), Some(duration)) if previous_times.start_time == times.start_time => {
+					#[allow(clippy::cast_precision_loss)]
+					let ticks = times.ticks.saturating_sub(previous_times.ticks) as f64;
+					100. * ticks / self.clock_ticks_per_second / duration
+				},
+				_ => 0.,
+			};
+
+			self.times.insert(pid, times);
+
+			self.processes.push(Process {
+				pid,
+				name,
+				uid,
+				user: String::new(),
+				cpu,
+				rss,
+			});
+		}
+
+		top_processes(&mut self.processes, top);
+
+		for process in &mut self.processes {
+			process.user.clone_from(self.user_names.entry(process.uid).or_insert_with(|| user_name(process.uid).unwrap_or_else(|| process.uid.to_string())));
+		}
+
+		Ok(&self.processes)
+	}
+}
+
+// See `Sampler::update`
+fn top_processes(processes: &mut Vec<Process>, top: usize) {
+	processes.sort_unstable_by(|a, b| b.cpu.total_cmp(&a.cpu));
+
+	if processes.len() > top {
+		let mut rss: Vec<_> = processes.iter().map(|process| process.rss).collect();
+		let (_, &mut min_top_rss, _) = rss.select_nth_unstable_by(top.saturating_sub(1), |a, b| b.cmp(a));
+
+		// Move the top processes by RSS that aren't already in the top processes by CPU usage to right after them.
+		let rest = &mut processes[top..];
+		rest.sort_unstable_by_key(|process| std::cmp::Reverse(process.rss));
+		let num_top_rss = rest.iter().take(top).take_while(|process| process.rss >= min_top_rss).count();
+		processes.truncate(top + num_top_rss);
+
+		processes[top..].sort_unstable_by(|a, b| b.cpu.total_cmp(&a.cpu));
+	}
+}
+
+fn parse_proc_pid_stat(pid: u32, buf: &mut Vec<u8>) -> Option<(Times, String)> {
+	let line = crate::hwmon::parse_hwmon_raw(format!("/proc/{pid}/stat").as_ref(), buf).ok()??;
+	parse_stat(line)
+}
+
+// Parses a line like `1234 (name) S 1 1234 ...` of `/proc/<pid>/stat`
+fn parse_stat(line: &str) -> Option<(Times, String)> {
+	// The name is surrounded by parentheses and can itself contain parentheses and spaces, so the name ends at the last `)`
+	let name_start = line.find('(')? + 1;
+	let name_end = line.rfind(')')?;
+	let name = line.get(name_start..name_end)?.to_owned();
+
+	// The fields after the name start from field 3 (state), so utime (14), stime (15) and starttime (22) are at these offsets.
+	let mut fields = line.get((name_end + 1)..)?.split_ascii_whitespace();
+	let utime: u64 = fields.nth(14 - 3)?.parse().ok()?;
+	let stime: u64 = fields.next()?.parse().ok()?;
+	let start_time = fields.nth(22 - 16)?.parse().ok()?;
+
+	Some((
+		Times {
+			start_time,
+			ticks: utime + stime,
+		},
+		name,
+	))
+}
+
+fn parse_proc_pid_status(pid: u32, buf: &mut Vec<u8>) -> Option<(u32, u64)> {
+	let mut uid = None;
+	// Kernel threads do not have a VmRSS line.
+	let mut rss = 0;
+
+	crate::hwmon::for_each_line(format!("/proc/{pid}/status").as_ref(), buf, |line| {
+		if let Some(value) = line.strip_prefix(b"Uid:") {
+			// The first of the four UIDs is the real UID.
+			let value = value.split(u8::is_ascii_whitespace).find(|s| !s.is_empty()).ok_or("UID missing")?;
+			uid = Some(crate::hwmon::parse_bytes(value)?);
+		}
+		else if let Some(value) = line.strip_prefix(b"VmRSS:") {
+			let value = value.strip_suffix(b" kB").ok_or("malformed VmRSS")?.trim_ascii();
+			rss = crate::hwmon::parse_bytes::<u64>(value)? * 1024;
+		}
+
+		Ok(false)
+	}).ok()?;
+
+	Some((uid?, rss))
+}
+
+fn user_name(uid: u32) -> Option<String> {
+	let mut buf = vec![0_u8; 1024];
+
+	loop {
+		let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+		let mut result = std::ptr::null_mut();
+		let err = unsafe { libc::getpwuid_r(uid, &raw mut passwd, buf.as_mut_ptr().cast(), buf.len(), &raw mut result) };
+		if err == libc::ERANGE {
+			buf.resize(buf.len() * 2, 0);
+			continue;
+		}
+		if err != 0 || result.is_null() {
+			return None;
+		}
+
+		let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+		return name.to_str().ok().map(ToOwned::to_owned);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn parse_stat() {
+		// utime is 150, stime is 30 and starttime is 98765
+		let fields = "S 1 1234 1234 0 -1 4194560 1000 0 0 0 150 30 0 0 20 0 1 0 98765 12345678 456 18446744073709551615";
+
+		for (name, expected_name) in [
+			("(sensord)", "sensord"),
+			("(tmux: server)", "tmux: server"),
+			// The name can contain `)` and spaces, so it ends at the last `)`
+			("(a) b (c)", "a) b (c"),
+			("(1 2 3 4 5 6 7 8 9 10 11 12)", "1 2 3 4 5 6 7 8 9 10 11 12"),
+		] {
+			let line = format!("1234 {name} {fields}");
+			let (times, name) = super::parse_stat(&line).unwrap();
+			assert_eq!(name, expected_name, "{line}");
+			assert_eq!(times.ticks, 180, "{line}");
+			assert_eq!(times.start_time, 98765, "{line}");
+		}
+
+		assert!(super::parse_stat("1234 (sensord) S 1 1234").is_none());
+		assert!(super::parse_stat("1234 sensord S").is_none());
+	}
+
+	#[test]
+	fn top_processes() {
+		let process = |pid, cpu, rss| super::Process { pid, name: String::new(), uid: 0, user: String::new(), cpu, rss };
+		let top = |processes: Vec<super::Process>, top| {
+			let mut processes = processes;
+			super::top_processes(&mut processes, top);
+			processes.into_iter().map(|process| process.pid).collect::<Vec<_>>()
+		};
+
+		let processes = || vec![
+			process(1, 0.5, 100),
+			process(2, 50., 10),
+			process(3, 0., 5000),
+			process(4, 20., 3000),
+			process(5, 10., 20),
+			process(6, 0.1, 4000),
+		];
+
+		// The top 2 by CPU are 2 and 4, and the top 2 by RSS are 6 and 3. All of them are ordered by CPU.
+		assert_eq!(top(processes(), 2), [2, 4, 6, 3]);
+
+		// 4 is in the top 3 by both, so it's only included once.
+		assert_eq!(top(processes(), 3), [2, 4, 5, 6, 3]);
+
+		// Fewer processes than `top`
+		assert_eq!(top(processes(), 10), [2, 4, 5, 1, 6, 3]);
+
+		assert_eq!(top(vec![], 2), [0_u32; 0]);
+	}
+}
@@ -0,0 +1,287 @@
+// Detects hwmon, power_supply, net and thermal devices being added or removed, so that their paths can be re-resolved
+// when eg a USB sensor is replugged, a GPU is reset or a driver module is reloaded and the device comes back under a different hwmonN.
+//
+// Network interfaces are reported by name, since on eg container hosts veth interfaces come and go all the time,
+// and only the interfaces in the config matter.
+//
+// Devices are watched by listening for kernel uevents on a netlink socket. If the socket can't be created,
+// the device directories under `/sys/class` are instead rescanned every `RESCAN_INTERVAL` and compared with the previous scan.
+pub(crate) struct Monitor {
+	inner: Inner,
+	buf: Box<[u8]>,
+}
+
+enum Inner {
+	Netlink(std::os::fd::OwnedFd),
+	Rescan {
+		last_rescan: std::time::Instant,
+		devices: Vec<std::path::PathBuf>,
+	},
+}
+
+const SUBSYSTEMS: &[&str] = &["hwmon", "power_supply", "net", "thermal"];
+
+// The devices that were added, removed or renamed. See `Monitor::changes`
+#[derive(Debug, Default)]
+pub(crate) struct Changes {
+	// Whether any hwmon or power_supply devices changed
+	pub(crate) devices: bool,
+	// Whether any thermal zones or cooling devices changed
+	pub(crate) thermal: bool,
+	// The names of the network interfaces that changed, including the old names of renamed ones
+	networks: std::collections::BTreeSet<String>,
+	// Set if uevents were lost, so it's not known which network interfaces changed.
+	all_networks: bool,
+}
+
+impl Changes {
+	pub(crate) fn network(&self, name: &str) -> bool {
+		self.all_networks || self.networks.contains(name)
+	}
+
+	// A kernel uevent looks like `add@/devices/...\0ACTION=add\0DEVPATH=/devices/...\0SUBSYSTEM=hwmon\0...`
+	fn add_uevent(&mut self, uevent: &[u8]) {
+		let mut action = None;
+		let mut subsystem = None;
+		let mut devpath = None;
+		let mut devpath_old = None;
+
+		for field in uevent.split(|&b| b == b'\0').skip(1) {
+			if let Some(value) = field.strip_prefix(b"ACTION=") {
+				action = Some(value);
+			}
+			else if let Some(value) = field.strip_prefix(b"SUBSYSTEM=") {
+				subsystem = Some(value);
+			}
+			else if let Some(value) = field.strip_prefix(b"DEVPATH=") {
+				devpath = Some(value);
+			}
+			else if let Some(value) = field.strip_prefix(b"DEVPATH_OLD=") {
+				devpath_old = Some(value);
+			}
+		}
+
+		// `change` uevents are also sent for existing devices, eg when a thermal zone crosses a trip point, so they're ignored.
+		if !matches!(action, Some(b"add" | b"remove" | b"move")) {
+			return;
+		}
+
+		match subsystem {
+			Some(b"hwmon" | b"power_supply") => self.devices = true,
+
+			Some(b"thermal") => self.thermal = true,
+
+			// The last component of the device path is the name of the interface.
+			Some(b"net") =>
+				for devpath in [devpath, devpath_old].into_iter().flatten() {
+					if let Some(name) = devpath.rsplit(|&b| b == b'/').next() {
+						self.networks.insert(String::from_utf8_lossy(name).into_owned());
+					}
+				},
+
+			_ => (),
+		}
+	}
+
+	// A device that was found by `scan_devices`, like `/sys/class/hwmon/hwmon3/../../devices/...`
+	fn add_device(&mut self, device: &std::path::Path) {
+		if let Ok(network) = device.strip_prefix("/sys/class/net") {
+			if let Some(std::path::Component::Normal(name)) = network.components().next() {
+				self.networks.insert(name.to_string_lossy().into_owned());
+			}
+		}
+		else if device.starts_with("/sys/class/thermal") {
+			self.thermal = true;
+		}
+		else {
+			self.devices = true;
+		}
+	}
+}
+
+const RESCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// The kernel's uevent messages are limited to UEVENT_BUFFER_SIZE of 2 KiB of environment plus the header,
+// but the fields that are looked at here always come first anyway.
+const UEVENT_BUFFER_SIZE: usize = 8192;
+
+impl Monitor {
+	pub(crate) fn new() -> Self {
+		let inner = match netlink_socket() {
+			Ok(fd) => Inner::Netlink(fd),
+			Err(err) => {
+				eprintln!("warning: could not listen for device uevents, falling back to rescanning devices every {RESCAN_INTERVAL:?}: {err}");
+				Inner::Rescan {
+					last_rescan: std::time::Instant::now(),
+					devices: scan_devices(),
+				}
+			},
+		};
+
+		Monitor {
+			inner,
+			buf: vec![0_u8; UEVENT_BUFFER_SIZE].into_boxed_slice(),
+		}
+	}
+
+	// Returns the devices that were added, removed or renamed since the previous call.
+	//
+	// This never blocks. All pending uevents are drained, so a burst of uevents like from a module being loaded
+	// is only reported once.
+	pub(crate) fn changes(&mut self) -> Changes {
+		let mut changes: Changes = Default::default();
+
+		match &mut self.inner {
+			Inner::Netlink(fd) => {
+
+				loop {
+					let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+					#[allow(clippy::cast_possible_truncation)]
+					let mut addr_len = std::mem::size_of_val(&addr) as libc::socklen_t;
+					let len = unsafe {
+						libc::recvfrom(
+							std::os::fd::AsRawFd::as_raw_fd(fd),
+							self.buf.as_mut_ptr().cast(),
+							self.buf.len(),
+							0,
+							(&raw mut addr).cast(),
+							&raw mut addr_len,
+						)
+					};
+					let Ok(len) = usize::try_from(len) else {
+						let err = std::io::Error::last_os_error();
+						match err.raw_os_error() {
+							Some(libc::EAGAIN) => break,
+							Some(libc::EINTR) => continue,
+
+							// The socket's receive buffer overflowed, so some uevents were lost. Assume they were relevant.
+							Some(libc::ENOBUFS) => {
+								changes.devices = true;
+								changes.thermal = true;
+								changes.all_networks = true;
+								continue;
+							},
+
+							_ => {
+								eprintln!("warning: could not receive device uevent: {err}");
+								break;
+							},
+						}
+					};
+
+					// Only the kernel is trusted to send uevents.
+					if addr.nl_pid != 0 {
+						continue;
+					}
+
+					changes.add_uevent(&self.buf[..len]);
+				}
+			},
+
+			Inner::Rescan { last_rescan, devices } => {
+				let now = std::time::Instant::now();
+				if now.checked_duration_since(*last_rescan).is_none_or(|duration| duration < RESCAN_INTERVAL) {
+					return changes;
+				}
+				*last_rescan = now;
+
+				// Both lists are sorted.
+				let new_devices = scan_devices();
+				for device in devices.iter().filter(|device| new_devices.binary_search(device).is_err()) {
+					changes.add_device(device);
+				}
+				for device in new_devices.iter().filter(|device| devices.binary_search(device).is_err()) {
+					changes.add_device(device);
+				}
+				*devices = new_devices;
+			},
+		}
+
+		changes
+	}
+}
+
+fn netlink_socket() -> std::io::Result<std::os::fd::OwnedFd> {
+	let fd = unsafe {
+		libc::socket(
+			libc::AF_NETLINK,
+			libc::SOCK_DGRAM | libc::SOCK_CLOEXEC | libc::SOCK_NONBLOCK,
+			libc::NETLINK_KOBJECT_UEVENT,
+		)
+	};
+	if fd == -1 {
+		return Err(std::io::Error::last_os_error());
+	}
+	let fd = unsafe { <std::os::fd::OwnedFd as std::os::fd::FromRawFd>::from_raw_fd(fd) };
+
+	let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+	#[allow(clippy::cast_possible_truncation)]
+	{
+		addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+	}
+	// Multicast group 1 receives the kernel's uevents, as opposed to group 2 which receives udev's.
+	addr.nl_groups = 1;
+
+	#[allow(clippy::cast_possible_truncation)]
+	let addr_len = std::mem::size_of_val(&addr) as libc::socklen_t;
+	let err = unsafe { libc::bind(std::os::fd::AsRawFd::as_raw_fd(&fd), (&raw const addr).cast(), addr_len) };
+	if err == -1 {
+		return Err(std::io::Error::last_os_error());
+	}
+
+	Ok(fd)
+}
+
+fn scan_devices() -> Vec<std::path::PathBuf> {
+	let mut devices: Vec<_> =
+		SUBSYSTEMS.iter()
+		.filter_map(|subsystem| std::fs::read_dir(std::path::Path::new("/sys/class").join(subsystem)).ok())
+		.flatten()
+		.filter_map(|entry| {
+			let path = entry.ok()?.path();
+			let target = std::fs::read_link(&path).ok()?;
+			Some(path.join(target))
+		})
+		.collect();
+	devices.sort();
+	devices
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn changes() {
+		let changes = |uevent: &[u8]| {
+			let mut changes: super::Changes = Default::default();
+			changes.add_uevent(uevent);
+			changes
+		};
+
+		assert!(changes(b"add@/devices/pci0000:00/0000:00:18.3/hwmon/hwmon3\0ACTION=add\0DEVPATH=/devices/pci0000:00/0000:00:18.3/hwmon/hwmon3\0SUBSYSTEM=hwmon\0SEQNUM=4321\0").devices);
+		assert!(!changes(b"change@/devices/virtual/thermal/thermal_zone0\0ACTION=change\0DEVPATH=/devices/virtual/thermal/thermal_zone0\0SUBSYSTEM=thermal\0").thermal);
+		let thermal_changes = changes(b"add@/devices/virtual/thermal/cooling_device4\0ACTION=add\0DEVPATH=/devices/virtual/thermal/cooling_device4\0SUBSYSTEM=thermal\0");
+		assert!(thermal_changes.thermal);
+		assert!(!thermal_changes.devices);
+		assert!(!changes(b"add@/devices/pci0000:00/0000:00:14.0/usb1/1-1\0ACTION=add\0DEVPATH=/devices/pci0000:00/0000:00:14.0/usb1/1-1\0SUBSYSTEM=usb\0").devices);
+
+		// The header is not a field, so a device path that happens to contain `SUBSYSTEM=` doesn't count.
+		assert!(!changes(b"add@/SUBSYSTEM=hwmon\0ACTION=add\0").devices);
+
+		// Network interfaces are reported by name, both the new and the old one of a renamed interface.
+		let network_changes = changes(b"move@/devices/virtual/net/enp4s0\0ACTION=move\0DEVPATH=/devices/virtual/net/enp4s0\0SUBSYSTEM=net\0DEVPATH_OLD=/devices/virtual/net/eth0\0");
+		assert!(!network_changes.devices);
+		assert!(network_changes.network("enp4s0"));
+		assert!(network_changes.network("eth0"));
+		assert!(!network_changes.network("wlan0"));
+
+		let mut scan_changes: super::Changes = Default::default();
+		scan_changes.add_device("/sys/class/net/veth1234/../../devices/virtual/net/veth1234".as_ref());
+		assert!(!scan_changes.devices);
+		assert!(scan_changes.network("veth1234"));
+		scan_changes.add_device("/sys/class/thermal/thermal_zone0/../../devices/virtual/thermal/thermal_zone0".as_ref());
+		assert!(!scan_changes.devices);
+		assert!(scan_changes.thermal);
+		scan_changes.add_device("/sys/class/hwmon/hwmon3/../../devices/pci0000:00/0000:00:18.3/hwmon/hwmon3".as_ref());
+		assert!(scan_changes.devices);
+	}
+}